@@ -0,0 +1,251 @@
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::playlist::Channel;
+
+/// Catchup (archive) settings from a channel's `catchup*` attributes
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Catchup {
+    /// `catchup` type: "default", "append", "shift" or "flussonic"
+    pub mode: String,
+    /// `catchup-source` URL template
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source: Option<String>,
+    /// `catchup-days`: how far back the archive goes
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub days: Option<u32>,
+}
+
+impl Catchup {
+    /// Build from raw attribute values, if the channel has catchup at all
+    pub fn from_attributes(mode: Option<String>, source: Option<String>, days: Option<String>) -> Option<Self> {
+        if mode.is_none() && source.is_none() {
+            return None;
+        }
+
+        Some(Self {
+            mode: mode.unwrap_or_else(|| "default".to_string()).to_ascii_lowercase(),
+            source,
+            days: days.and_then(|days| days.trim().parse().ok()),
+        })
+    }
+}
+
+/// Seconds since the Unix epoch
+pub fn unix_now() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_secs() as i64)
+        .unwrap_or_default()
+}
+
+/// Offset of local time from UTC in seconds, or 0 where it can't be determined
+pub fn local_utc_offset() -> i64 {
+    #[cfg(unix)]
+    {
+        let now = unix_now() as libc::time_t;
+        // SAFETY: localtime_r only writes to the tm we pass in
+        let mut tm: libc::tm = unsafe { std::mem::zeroed() };
+        if !unsafe { libc::localtime_r(&now, &mut tm) }.is_null() {
+            return tm.tm_gmtoff as i64;
+        }
+    }
+    0
+}
+
+/// Parse a catchup start time: an offset like `-2h`, `-30m`, `-1h30m`, or a local clock time
+/// like `20:00` (the most recent one, so a time later than now means yesterday)
+pub fn parse_catchup_time(input: &str, now: i64, utc_offset: i64) -> Result<i64> {
+    let input = input.trim();
+
+    if let Some(offset) = input.strip_prefix('-') {
+        return now.checked_sub(parse_offset(offset)?).with_context(|| invalid_offset(offset));
+    }
+
+    let (hours, minutes) = input
+        .split_once(':')
+        .with_context(|| format!("Expected an offset like -2h or a time like 20:00, got '{}'", input))?;
+    let hours: i64 = hours.trim().parse().with_context(|| format!("Invalid hour in '{}'", input))?;
+    let minutes: i64 = minutes.trim().parse().with_context(|| format!("Invalid minute in '{}'", input))?;
+    if !(0..24).contains(&hours) || !(0..60).contains(&minutes) {
+        anyhow::bail!("Time out of range: '{}'", input);
+    }
+
+    let local_now = now + utc_offset;
+    let local_midnight = local_now - local_now.rem_euclid(86_400);
+    let mut start = local_midnight + hours * 3600 + minutes * 60 - utc_offset;
+    if start > now {
+        start -= 86_400;
+    }
+    Ok(start)
+}
+
+/// Parse `2h`, `30m`, `1h30m` or `90s` into seconds
+fn parse_offset(offset: &str) -> Result<i64> {
+    let mut total: i64 = 0;
+    let mut digits = String::new();
+
+    for c in offset.chars() {
+        match c {
+            '0'..='9' => digits.push(c),
+            'h' | 'm' | 's' if !digits.is_empty() => {
+                let unit = match c {
+                    'h' => 3600,
+                    'm' => 60,
+                    _ => 1,
+                };
+                total = digits
+                    .parse::<i64>()
+                    .ok()
+                    .and_then(|value| value.checked_mul(unit))
+                    .and_then(|seconds| total.checked_add(seconds))
+                    .with_context(|| invalid_offset(offset))?;
+                digits.clear();
+            }
+            _ => anyhow::bail!(invalid_offset(offset)),
+        }
+    }
+
+    if !digits.is_empty() || total == 0 {
+        anyhow::bail!(invalid_offset(offset));
+    }
+    Ok(total)
+}
+
+fn invalid_offset(offset: &str) -> String {
+    format!("Invalid offset '-{}', expected something like -2h or -30m", offset)
+}
+
+/// URL that replays `channel` from `start`, or an error if it has no usable catchup
+pub fn catchup_url(channel: &Channel, start: i64, now: i64) -> Result<String> {
+    let catchup = channel
+        .catchup
+        .as_ref()
+        .with_context(|| format!("{} has no catchup archive", channel.name))?;
+
+    if let Some(days) = catchup.days.filter(|days| now - start > i64::from(*days) * 86_400) {
+        anyhow::bail!("{} only keeps {} days of catchup", channel.name, days);
+    }
+    if start > now {
+        anyhow::bail!("Catchup start is in the future");
+    }
+
+    match (catchup.mode.as_str(), &catchup.source) {
+        ("shift" | "timeshift", _) => {
+            let separator = if channel.url.contains('?') { '&' } else { '?' };
+            Ok(format!("{}{}utc={}&lutc={}", channel.url, separator, start, now))
+        }
+        ("append", Some(source)) => Ok(format!("{}{}", channel.url, expand_placeholders(source, start, now))),
+        (_, Some(source)) => Ok(expand_placeholders(source, start, now)),
+        (mode, None) => anyhow::bail!("{} has catchup type '{}' without a catchup-source", channel.name, mode),
+    }
+}
+
+/// Fill `{utc}`/`${start}`, `{lutc}`/`${now}`, `{offset}` and the `{Y}{m}{d}{H}{M}{S}` UTC date parts
+fn expand_placeholders(template: &str, start: i64, now: i64) -> String {
+    let (year, month, day) = civil_from_days(start.div_euclid(86_400));
+    let seconds = start.rem_euclid(86_400);
+
+    [
+        ("{utc}", start.to_string()),
+        ("${start}", start.to_string()),
+        ("{lutc}", now.to_string()),
+        ("${now}", now.to_string()),
+        ("${timestamp}", now.to_string()),
+        ("{offset}", (now - start).to_string()),
+        ("${offset}", (now - start).to_string()),
+        ("{Y}", format!("{:04}", year)),
+        ("{m}", format!("{:02}", month)),
+        ("{d}", format!("{:02}", day)),
+        ("{H}", format!("{:02}", seconds / 3600)),
+        ("{M}", format!("{:02}", seconds % 3600 / 60)),
+        ("{S}", format!("{:02}", seconds % 60)),
+    ]
+    .iter()
+    .fold(template.to_string(), |url, (placeholder, value)| url.replace(placeholder, value))
+}
+
+/// Days since 1970-01-01 for a proleptic Gregorian date (Howard Hinnant's algorithm)
+pub fn days_from_civil(year: i64, month: u32, day: u32) -> i64 {
+    let year = year - i64::from(month <= 2);
+    let era = year.div_euclid(400);
+    let yoe = year.rem_euclid(400);
+    let mp = i64::from((month + 9) % 12);
+    let doy = (153 * mp + 2) / 5 + i64::from(day) - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    era * 146_097 + doe - 719_468
+}
+
+/// Year, month and day for a count of days since 1970-01-01 (Howard Hinnant's algorithm)
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    (year, month, day)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 2024-03-10 21:15:00 UTC
+    const NOW: i64 = 1_710_105_300;
+
+    #[test]
+    fn test_parse_offsets() {
+        assert_eq!(parse_catchup_time("-2h", NOW, 0).unwrap(), NOW - 7200);
+        assert_eq!(parse_catchup_time("-30m", NOW, 0).unwrap(), NOW - 1800);
+        assert_eq!(parse_catchup_time("-1h30m", NOW, 0).unwrap(), NOW - 5400);
+        assert!(parse_catchup_time("-2x", NOW, 0).is_err());
+        assert!(parse_catchup_time("-", NOW, 0).is_err());
+
+        // Values that overflow are rejected rather than wrapping
+        for offset in ["-9999999999999999h", "-99999999999999999999s", "-2562047788015215h2562047788015215h"] {
+            let err = parse_catchup_time(offset, NOW, 0).unwrap_err();
+            assert!(err.to_string().contains("Invalid offset"), "{}", err);
+        }
+        assert!(parse_catchup_time("-10s", i64::MIN, 0).unwrap_err().to_string().contains("Invalid offset"));
+    }
+
+    #[test]
+    fn test_parse_clock_time() {
+        // 20:00 UTC the same day
+        assert_eq!(parse_catchup_time("20:00", NOW, 0).unwrap(), NOW - 4500);
+        // 22:00 hasn't happened yet today, so it means yesterday
+        assert_eq!(parse_catchup_time("22:00", NOW, 0).unwrap(), NOW + 2700 - 86_400);
+        // 20:00 local at UTC+1 is 19:00 UTC
+        assert_eq!(parse_catchup_time("20:00", NOW, 3600).unwrap(), NOW - 8100);
+        assert!(parse_catchup_time("25:00", NOW, 0).is_err());
+        assert!(parse_catchup_time("tonight", NOW, 0).is_err());
+    }
+
+    #[test]
+    fn test_catchup_url() {
+        let mut channel = Channel::new("BBC".to_string(), "http://example.com/bbc.m3u8".to_string());
+        assert!(catchup_url(&channel, NOW - 60, NOW).is_err());
+
+        channel.catchup = Catchup::from_attributes(
+            Some("default".to_string()),
+            Some("http://example.com/archive/{Y}-{m}-{d}/{H}{M}.m3u8?start={utc}&now={lutc}".to_string()),
+            Some("2".to_string()),
+        );
+        assert_eq!(
+            catchup_url(&channel, NOW - 4500, NOW).unwrap(),
+            format!("http://example.com/archive/2024-03-10/2000.m3u8?start={}&now={}", NOW - 4500, NOW)
+        );
+        assert!(catchup_url(&channel, NOW - 3 * 86_400, NOW).is_err());
+
+        channel.catchup = Catchup::from_attributes(Some("shift".to_string()), None, None);
+        assert_eq!(
+            catchup_url(&channel, NOW - 60, NOW).unwrap(),
+            format!("http://example.com/bbc.m3u8?utc={}&lutc={}", NOW - 60, NOW)
+        );
+    }
+}
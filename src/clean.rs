@@ -0,0 +1,120 @@
+use anyhow::{Context, Result};
+use colored::*;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::config::Config;
+use crate::utils::format_file_size;
+
+/// What `riptv clean` removes
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CleanTargets {
+    /// The riptv cache folder (preview thumbnails)
+    pub cache: bool,
+    /// The config file and any `.bak` left by a failed load; defaults are written in its place
+    pub config: bool,
+}
+
+/// A file or folder `riptv clean` deleted
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Removed {
+    pub path: PathBuf,
+    pub bytes: u64,
+}
+
+/// Paths `targets` would remove that exist right now, for the confirmation prompt
+pub fn planned(targets: CleanTargets, cache_dir: Option<&Path>, config_path: &Path) -> Vec<PathBuf> {
+    let mut paths = Vec::new();
+    if targets.cache {
+        paths.extend(cache_dir.map(Path::to_path_buf));
+    }
+    if targets.config {
+        paths.push(config_path.to_path_buf());
+        paths.push(Config::backup_path(config_path));
+    }
+    paths.retain(|path| path.exists());
+    paths
+}
+
+/// Remove the targeted paths, writing a default config where the old one was
+pub fn clean(targets: CleanTargets, cache_dir: Option<&Path>, config_path: &Path) -> Result<Vec<Removed>> {
+    let mut removed = Vec::new();
+    for path in planned(targets, cache_dir, config_path) {
+        let bytes = path_size(&path);
+        if path.is_dir() {
+            fs::remove_dir_all(&path).with_context(|| format!("Failed to remove {}", path.display()))?;
+        } else {
+            fs::remove_file(&path).with_context(|| format!("Failed to remove {}", path.display()))?;
+        }
+        removed.push(Removed { path, bytes });
+    }
+
+    if targets.config {
+        Config::default().save(Some(&config_path.to_string_lossy()))?;
+    }
+    Ok(removed)
+}
+
+pub fn show(removed: &[Removed], targets: CleanTargets, config_path: &Path) {
+    if removed.is_empty() {
+        println!("{}", "✨ Nothing to clean".bright_green());
+    }
+    for entry in removed {
+        println!("🗑️ Removed {} ({})", entry.path.display().to_string().bright_white(), format_file_size(entry.bytes).bright_yellow());
+    }
+    if removed.len() > 1 {
+        let total: u64 = removed.iter().map(|entry| entry.bytes).sum();
+        println!("{}", format!("🧹 Freed {}", format_file_size(total)).bright_green().bold());
+    }
+    if targets.config {
+        println!("{}", format!("⚙️ Wrote a default config to {}", config_path.display()).bright_cyan());
+    }
+}
+
+/// Bytes used by a file, or by everything under a folder
+fn path_size(path: &Path) -> u64 {
+    let Ok(metadata) = fs::symlink_metadata(path) else {
+        return 0;
+    };
+    if !metadata.is_dir() {
+        return metadata.len();
+    }
+
+    fs::read_dir(path)
+        .map(|entries| entries.flatten().map(|entry| path_size(&entry.path())).sum())
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_clean_targets() {
+        let root = std::env::temp_dir().join(format!("riptv-clean-{}", std::process::id()));
+        let cache = root.join("cache").join("riptv");
+        let config_path = root.join("config").join("riptv").join("config.json");
+        let backup = Config::backup_path(&config_path);
+        fs::create_dir_all(cache.join("thumbnails")).unwrap();
+        fs::write(cache.join("thumbnails").join("frame.jpg"), vec![0u8; 2048]).unwrap();
+        fs::create_dir_all(config_path.parent().unwrap()).unwrap();
+        fs::write(&config_path, r#"{"player_command": "vlc"}"#).unwrap();
+        fs::write(&backup, "{broken").unwrap();
+
+        let cache_only = CleanTargets { cache: true, config: false };
+        assert_eq!(planned(cache_only, Some(&cache), &config_path), vec![cache.clone()]);
+        let removed = clean(cache_only, Some(&cache), &config_path).unwrap();
+        assert_eq!(removed, [Removed { path: cache.clone(), bytes: 2048 }]);
+        assert!(!cache.exists());
+        assert!(config_path.exists() && backup.exists());
+
+        let removed = clean(CleanTargets { cache: true, config: true }, Some(&cache), &config_path).unwrap();
+        let paths: Vec<_> = removed.iter().map(|entry| entry.path.clone()).collect();
+        assert_eq!(paths, [config_path.clone(), backup.clone()]);
+        assert!(!backup.exists());
+        let regenerated = Config::load(Some(&config_path.to_string_lossy()), true).unwrap();
+        assert_eq!(regenerated.player_command, Config::default().player_command);
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+}
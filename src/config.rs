@@ -2,13 +2,28 @@ use anyhow::{Context, Result};
 use dirs::config_dir;
 use serde::{Deserialize, Serialize};
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use tracing::{debug, info, warn};
 
+use crate::playlist::{CaseMode, SearchField};
+
+/// Current config layout; older files are filled in with defaults and rewritten on load
+pub const CONFIG_VERSION: u32 = 1;
+
+/// Missing fields take their defaults, so configs written by older versions still load
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
 pub struct Config {
+    /// Layout version this file was written with (0 for files that predate versioning)
+    #[serde(default)]
+    pub version: u32,
+
     /// Default playlist file path
     pub default_playlist: Option<String>,
+
+    /// What to do when no playlist is given: "welcome", "list" or "error"
+    #[serde(default = "default_on_empty")]
+    pub on_empty: String,
     
     /// Media player command
     pub player_command: String,
@@ -16,31 +31,105 @@ pub struct Config {
     /// Additional arguments for the media player
     pub player_args: Option<Vec<String>>,
     
+    /// Extra directories searched by `--list`, `~` allowed
+    #[serde(default)]
+    pub playlist_dirs: Vec<String>,
+
     /// Enable parallel processing for large playlists
     pub parallel_processing: bool,
+
+    /// Number of concurrent tasks used for parallel parsing (0 = one per CPU)
+    #[serde(default)]
+    pub parse_threads: usize,
+
+    /// Values substituted for `${VAR}` placeholders in channel URLs
+    #[serde(default)]
+    pub url_vars: std::collections::HashMap<String, String>,
     
     /// Maximum number of channels to show in search results
     pub max_search_results: usize,
+
+    /// Parsing fails once a playlist holds more channels than this (0 = no limit)
+    #[serde(default = "default_max_channels")]
+    pub max_channels: usize,
+
+    /// Order group names with numbers by value, so "Sports 2" lists before "Sports 10"
+    #[serde(default)]
+    pub natural_sort: bool,
+
+    /// Where channel names come from: "display", "tvg_name" or "tvg_id"
+    #[serde(default = "default_name_source")]
+    pub name_source: String,
+
+    /// Only accept `http(s)` stream URLs, dropping local files, rtmp and other schemes
+    #[serde(default)]
+    pub strict_url: bool,
     
     /// Enable fuzzy matching in search
     pub fuzzy_search: bool,
+
+    /// Channel fields matched by search: "name", "group", "country", "language", "tvg_id"
+    #[serde(default = "default_search_fields")]
+    pub search_fields: Vec<String>,
+
+    /// Case sensitivity of search
+    #[serde(default)]
+    pub search: SearchConfig,
+
+    /// Regex rules that tidy channel names for display
+    #[serde(default)]
+    pub name_cleanup: NameCleanupConfig,
     
     /// UI preferences
     pub ui: UiConfig,
     
     /// Network settings
     pub network: NetworkConfig,
+
+    /// Media player behaviour
+    #[serde(default)]
+    pub player: PlayerConfig,
+
+    /// Shell commands run around playback
+    #[serde(default)]
+    pub hooks: HooksConfig,
+
+    /// File holding the playing channel's name and URL for status bars;
+    /// defaults to `now_playing.txt` next to the config file
+    #[serde(default)]
+    pub now_playing_file: Option<String>,
+
+    /// Groups to keep or drop when a playlist is parsed
+    #[serde(default)]
+    pub groups: GroupsConfig,
+
+    /// Raw group names mapped to the group their channels are merged into, e.g. `"NEWS": "News"`
+    #[serde(default)]
+    pub group_aliases: std::collections::HashMap<String, String>,
+
+    /// Display names keyed by stream URL, set with F2 in the channel list
+    #[serde(default)]
+    pub name_overrides: std::collections::HashMap<String, String>,
+
+    /// User tags keyed by stream URL, edited with Alt-T in the channel list and filtered with `--tag`
+    #[serde(default)]
+    pub channel_tags: std::collections::HashMap<String, Vec<String>>,
     
     /// Recently played channels (for quick access)
     pub recent_channels: Vec<String>,
     
     /// Favorite channels
     pub favorite_channels: Vec<String>,
+
+    /// File this configuration was loaded from, used when persisting changes
+    #[serde(skip)]
+    pub source_path: Option<PathBuf>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
 pub struct UiConfig {
-    /// Color scheme for the interface
+    /// Color scheme for the interface ("dark", "light" or "none")
     pub color_scheme: String,
     
     /// Show channel preview by default
@@ -57,9 +146,313 @@ pub struct UiConfig {
     
     /// Custom key bindings
     pub key_bindings: std::collections::HashMap<String, String>,
+
+    /// Initial channel order: "playlist", "recency" or "alpha"
+    #[serde(default = "default_sort")]
+    pub sort: String,
+
+    /// Exit interactive mode if no channel is picked within this many seconds
+    #[serde(default)]
+    pub idle_timeout_secs: Option<u64>,
+
+    /// Show the channel list bottom-up with the prompt at the top
+    #[serde(default = "default_reverse_list")]
+    pub reverse_list: bool,
+
+    /// Fixed width for separator lines; follows the terminal when unset
+    #[serde(default)]
+    pub separator_width: Option<usize>,
+
+    /// Grab a frame with ffmpeg and draw it with chafa in the preview; slow, as each stream is opened
+    #[serde(default)]
+    pub preview_thumbnail: bool,
+
+    /// List favorites, then recently played, then the rest, each under a header row
+    #[serde(default)]
+    pub section_headers: bool,
+
+    /// Ask for a starting letter before showing the list, like a contacts list
+    #[serde(default)]
+    pub browse_alpha: bool,
+
+    /// Group the list opens scoped to; Ctrl-G widens it to every channel
+    #[serde(default)]
+    pub default_group: Option<String>,
+}
+
+fn default_reverse_list() -> bool {
+    true
+}
+
+fn default_search_fields() -> Vec<String> {
+    vec!["name".to_string()]
+}
+
+fn default_on_empty() -> String {
+    "error".to_string()
+}
+
+fn default_name_source() -> String {
+    "tvg_name".to_string()
+}
+
+/// Field preferred for channel names by `config.name_source`; the others are fallbacks
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum NameSource {
+    /// The text after the comma on the `#EXTINF` line
+    Display,
+    /// The `tvg-name` attribute
+    #[default]
+    TvgName,
+    /// The `tvg-id` attribute
+    TvgId,
+}
+
+impl NameSource {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "display" => Some(Self::Display),
+            "tvg_name" | "tvg-name" => Some(Self::TvgName),
+            "tvg_id" | "tvg-id" => Some(Self::TvgId),
+            _ => None,
+        }
+    }
+}
+
+/// Behaviour selected by `config.on_empty` when riptv starts without a playlist
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OnEmpty {
+    /// Show the welcome message and exit
+    Welcome,
+    /// Offer the discovered playlists to pick from
+    List,
+    /// Report the missing playlist and exit with an error
+    Error,
+}
+
+impl OnEmpty {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "welcome" => Some(Self::Welcome),
+            "list" => Some(Self::List),
+            "error" => Some(Self::Error),
+            _ => None,
+        }
+    }
+}
+
+fn default_sort() -> String {
+    "playlist".to_string()
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SearchConfig {
+    /// Match case-insensitively unless the query contains uppercase
+    #[serde(default)]
+    pub smart_case: bool,
+
+    /// Ignore case when smart case is off
+    #[serde(default = "default_ignore_case")]
+    pub ignore_case: bool,
+
+    /// Score added to favorite channels; a very large value pins them to the top
+    #[serde(default = "default_favorite_boost")]
+    pub favorite_boost: i64,
+
+    /// Drop matches scoring below this, before the favorite boost
+    #[serde(default)]
+    pub min_score: i64,
+
+    /// Match the query only as a prefix of the channel name, ignoring case
+    #[serde(default)]
+    pub anchored: bool,
+}
+
+impl Default for SearchConfig {
+    fn default() -> Self {
+        Self {
+            smart_case: false,
+            ignore_case: default_ignore_case(),
+            favorite_boost: default_favorite_boost(),
+            min_score: 0,
+            anchored: false,
+        }
+    }
+}
+
+fn default_favorite_boost() -> i64 {
+    50
+}
+
+impl SearchConfig {
+    pub fn case_mode(&self) -> CaseMode {
+        if self.smart_case {
+            CaseMode::Smart
+        } else if self.ignore_case {
+            CaseMode::Ignore
+        } else {
+            CaseMode::Respect
+        }
+    }
+}
+
+fn default_ignore_case() -> bool {
+    true
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NameCleanupConfig {
+    /// Apply the rules when a playlist is parsed
+    #[serde(default)]
+    pub enabled: bool,
+
+    /// Rules applied in order; the original name is kept for export
+    #[serde(default = "default_cleanup_rules")]
+    pub rules: Vec<CleanupRule>,
+}
+
+impl Default for NameCleanupConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            rules: default_cleanup_rules(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CleanupRule {
+    /// Regular expression matched against the channel name
+    pub pattern: String,
+
+    /// Replacement text, may reference capture groups like `$1`
+    #[serde(default)]
+    pub replacement: String,
+}
+
+impl CleanupRule {
+    fn new(pattern: &str, replacement: &str) -> Self {
+        Self {
+            pattern: pattern.to_string(),
+            replacement: replacement.to_string(),
+        }
+    }
+}
+
+/// Strip country prefixes (`US| `), bracketed tags (`[VIP]`) and trailing quality markers
+fn default_cleanup_rules() -> Vec<CleanupRule> {
+    vec![
+        CleanupRule::new(r"^[A-Z]{2,3}\s*[|:]\s*", ""),
+        CleanupRule::new(r"(?i)\s*\[(VIP|HD|FHD|UHD|4K|SD|HEVC)\]\s*", " "),
+        CleanupRule::new(r"(?i)\s*\|\s*(HD|FHD|UHD|4K|SD|HEVC)\s*$", ""),
+        CleanupRule::new(r"(?i)\s+(HD|FHD|UHD|4K|HEVC)$", ""),
+        CleanupRule::new(r"\s{2,}", " "),
+    ]
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PlayerConfig {
+    /// Pass GPU rendering and hardware decoding flags to mpv
+    #[serde(default = "default_gpu")]
+    pub gpu: bool,
+
+    /// Start mpv fullscreen; updated from the window state of the last run
+    #[serde(default)]
+    pub start_fullscreen: bool,
+
+    /// Start mpv with audio muted
+    #[serde(default)]
+    pub mute_on_start: bool,
+
+    /// mpv audio output device, as listed by `mpv --audio-device=help`
+    #[serde(default)]
+    pub audio_device: Option<String>,
+
+    /// Terminal command the player is started in, e.g. `["alacritty", "-e"]` (Linux only)
+    #[serde(default)]
+    pub spawn_terminal: Vec<String>,
+
+    /// Extra environment variables for the player process, e.g. `DISPLAY` or `MPV_HOME`
+    #[serde(default)]
+    pub env: std::collections::HashMap<String, String>,
+
+    /// Cache flags for live streams, tuned for low latency
+    #[serde(default = "default_live_args")]
+    pub live_args: Vec<String>,
+
+    /// Cache flags for VOD (channels with a known duration), tuned for a long readahead
+    #[serde(default = "default_vod_args")]
+    pub vod_args: Vec<String>,
+
+    /// Relaunch a channel this many times in a row when the player fails soon after starting; 0 turns it off
+    #[serde(default)]
+    pub reconnect: u32,
+}
+
+impl Default for PlayerConfig {
+    fn default() -> Self {
+        Self {
+            gpu: default_gpu(),
+            start_fullscreen: false,
+            mute_on_start: false,
+            audio_device: None,
+            spawn_terminal: Vec::new(),
+            env: std::collections::HashMap::new(),
+            live_args: default_live_args(),
+            vod_args: default_vod_args(),
+            reconnect: 0,
+        }
+    }
+}
+
+fn default_max_channels() -> usize {
+    2_000_000
+}
+
+fn default_live_args() -> Vec<String> {
+    ["--demuxer-max-bytes=50M", "--demuxer-readahead-secs=10", "--cache-pause-wait=1"]
+        .iter()
+        .map(|arg| arg.to_string())
+        .collect()
+}
+
+fn default_vod_args() -> Vec<String> {
+    ["--demuxer-max-bytes=500M", "--demuxer-max-back-bytes=100M", "--demuxer-readahead-secs=300"]
+        .iter()
+        .map(|arg| arg.to_string())
+        .collect()
+}
+
+fn default_gpu() -> bool {
+    true
+}
+
+/// Group names or `*`/`?` globs, matched case-insensitively
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct GroupsConfig {
+    /// When non-empty, only channels in a matching group are loaded
+    #[serde(default)]
+    pub include: Vec<String>,
+
+    /// Channels in a matching group are dropped
+    #[serde(default)]
+    pub exclude: Vec<String>,
+}
+
+/// Shell commands run with `RIPTV_CHANNEL_NAME`, `RIPTV_CHANNEL_URL` and `RIPTV_CHANNEL_GROUP` set
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct HooksConfig {
+    /// Run before the player starts
+    #[serde(default)]
+    pub pre_play: Option<String>,
+
+    /// Run after playback ends
+    #[serde(default)]
+    pub post_play: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
 pub struct NetworkConfig {
     /// Connection timeout in seconds
     pub timeout: u64,
@@ -75,9 +468,16 @@ pub struct NetworkConfig {
     
     /// Maximum redirect count
     pub max_redirects: u32,
+
+    /// Headers sent with every stream request; per-channel `#EXTVLCOPT` headers override them
+    #[serde(default)]
+    pub extra_headers: std::collections::HashMap<String, String>,
+
+    /// Playlist downloads larger than this many bytes are refused (0 = no limit)
+    pub max_download_bytes: u64,
 }
 
-impl Default for Config {
+impl Default for UiConfig {
     fn default() -> Self {
         let mut key_bindings = std::collections::HashMap::new();
         key_bindings.insert("quit".to_string(), "q,esc".to_string());
@@ -89,12 +489,47 @@ impl Default for Config {
         key_bindings.insert("page_down".to_string(), "page-down,ctrl-f".to_string());
 
         Self {
+            color_scheme: "dark".to_string(),
+            show_preview: true,
+            preview_size: "50%".to_string(),
+            page_size: 20,
+            show_groups: true,
+            key_bindings,
+            sort: default_sort(),
+            idle_timeout_secs: None,
+            reverse_list: default_reverse_list(),
+            separator_width: None,
+            preview_thumbnail: false,
+            section_headers: false,
+            browse_alpha: false,
+            default_group: None,
+        }
+    }
+}
+
+impl Default for NetworkConfig {
+    fn default() -> Self {
+        Self {
+            timeout: 30,
+            retry_attempts: 3,
+            user_agent: "RIPTV/1.0 (Rust IPTV Player)".to_string(),
+            follow_redirects: true,
+            max_redirects: 5,
+            extra_headers: std::collections::HashMap::new(),
+            max_download_bytes: 1024 * 1024 * 1024,
+        }
+    }
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            version: CONFIG_VERSION,
             default_playlist: None,
+            on_empty: default_on_empty(),
             player_command: "mpv".to_string(),
             player_args: Some(vec![
                 "--cache=yes".to_string(),
-                "--demuxer-max-bytes=100M".to_string(),
-                "--demuxer-readahead-secs=30".to_string(),
                 "--force-window=immediate".to_string(),
                 "--no-terminal".to_string(),
                 "--quiet".to_string(),
@@ -102,33 +537,38 @@ impl Default for Config {
                 "--vo=gpu".to_string(),
                 "--profile=fast".to_string(),
             ]),
+            playlist_dirs: Vec::new(),
             parallel_processing: true,
+            parse_threads: 0,
+            url_vars: std::collections::HashMap::new(),
             max_search_results: 100,
+            max_channels: default_max_channels(),
+            natural_sort: false,
+            name_source: default_name_source(),
+            strict_url: false,
             fuzzy_search: true,
-            ui: UiConfig {
-                color_scheme: "dark".to_string(),
-                show_preview: true,
-                preview_size: "50%".to_string(),
-                page_size: 20,
-                show_groups: true,
-                key_bindings,
-            },
-            network: NetworkConfig {
-                timeout: 30,
-                retry_attempts: 3,
-                user_agent: "RIPTV/1.0 (Rust IPTV Player)".to_string(),
-                follow_redirects: true,
-                max_redirects: 5,
-            },
+            search_fields: default_search_fields(),
+            search: SearchConfig::default(),
+            name_cleanup: NameCleanupConfig::default(),
+            ui: UiConfig::default(),
+            network: NetworkConfig::default(),
+            player: PlayerConfig::default(),
+            hooks: HooksConfig::default(),
+            now_playing_file: None,
+            groups: GroupsConfig::default(),
+            group_aliases: std::collections::HashMap::new(),
+            name_overrides: std::collections::HashMap::new(),
+            channel_tags: std::collections::HashMap::new(),
             recent_channels: Vec::new(),
             favorite_channels: Vec::new(),
+            source_path: None,
         }
     }
 }
 
 impl Config {
-    /// Load configuration from file, creating default if not exists
-    pub fn load(config_path: Option<&str>) -> Result<Self> {
+    /// Load the config file, creating it if missing; unless `strict`, a corrupt file is moved aside and replaced with defaults
+    pub fn load(config_path: Option<&str>, strict: bool) -> Result<Self> {
         let config_file = match config_path {
             Some(path) => PathBuf::from(path),
             None => Self::default_config_path()?,
@@ -140,14 +580,31 @@ impl Config {
             let content = fs::read_to_string(&config_file)
                 .with_context(|| format!("Failed to read config file: {}", config_file.display()))?;
             
-            let config: Config = serde_json::from_str(&content)
-                .with_context(|| format!("Failed to parse config file: {}", config_file.display()))?;
+            let parsed: Result<Config> = serde_json::from_str(&content)
+                .with_context(|| format!("Failed to parse config file: {}", config_file.display()));
+            let mut config = match parsed {
+                Ok(config) => config,
+                Err(e) if strict => return Err(e),
+                Err(e) => return Self::recover_corrupt(&config_file, e),
+            };
+            config.source_path = Some(config_file.clone());
+
+            if config.version < CONFIG_VERSION {
+                info!("🔄 Upgrading config from version {} to {}", config.version, CONFIG_VERSION);
+                config.version = CONFIG_VERSION;
+                if let Err(e) = config.persist() {
+                    warn!("⚠️ Failed to save upgraded config: {}", e);
+                }
+            }
             
             info!("✅ Configuration loaded from {}", config_file.display());
             Ok(config)
         } else {
             info!("No config file found, creating default configuration");
-            let config = Config::default();
+            let config = Config {
+                source_path: Some(config_file.clone()),
+                ..Config::default()
+            };
             
             // Create config directory if it doesn't exist
             if let Some(parent) = config_file.parent() {
@@ -161,6 +618,30 @@ impl Config {
         }
     }
 
+    /// Where a config file that failed to parse is moved
+    pub fn backup_path(config_file: &Path) -> PathBuf {
+        let mut backup = config_file.as_os_str().to_owned();
+        backup.push(".bak");
+        PathBuf::from(backup)
+    }
+
+    /// Move a config file that failed to parse to `<file>.bak` and start over with defaults
+    fn recover_corrupt(config_file: &Path, error: anyhow::Error) -> Result<Self> {
+        let backup = Self::backup_path(config_file);
+        fs::rename(config_file, &backup)
+            .with_context(|| format!("Failed to back up corrupt config file: {}", config_file.display()))?;
+        warn!("⚠️ {:#}; moved it to {} and using defaults", error, backup.display());
+
+        let config = Config {
+            source_path: Some(config_file.to_path_buf()),
+            ..Config::default()
+        };
+        if let Err(e) = config.persist() {
+            warn!("⚠️ Failed to write default config: {}", e);
+        }
+        Ok(config)
+    }
+
     /// Save configuration to file
     pub fn save(&self, config_path: Option<&str>) -> Result<()> {
         let config_file = match config_path {
@@ -184,14 +665,56 @@ impl Config {
         Ok(())
     }
 
+    /// Save configuration back to the file it was loaded from, if any
+    pub fn persist(&self) -> Result<()> {
+        match self.source_path.as_ref().and_then(|path| path.to_str()) {
+            Some(path) => self.save(Some(path)),
+            None => Ok(()),
+        }
+    }
+
     /// Get the default configuration file path
-    fn default_config_path() -> Result<PathBuf> {
+    pub fn default_config_path() -> Result<PathBuf> {
         let config_dir = config_dir()
             .context("Unable to determine config directory")?;
         
         Ok(config_dir.join("riptv").join("config.json"))
     }
 
+    /// Tags on the channel at `url`
+    pub fn tags_for(&self, url: &str) -> &[String] {
+        self.channel_tags.get(url).map(Vec::as_slice).unwrap_or_default()
+    }
+
+    /// Whether the channel at `url` carries `tag`, ignoring case
+    pub fn has_tag(&self, url: &str, tag: &str) -> bool {
+        self.tags_for(url).iter().any(|existing| existing.eq_ignore_ascii_case(tag.trim()))
+    }
+
+    /// Tag the channel at `url`; returns false if the tag is blank or already there
+    pub fn add_tag(&mut self, url: &str, tag: &str) -> bool {
+        let tag = tag.trim();
+        if tag.is_empty() || self.has_tag(url, tag) {
+            return false;
+        }
+        self.channel_tags.entry(url.to_string()).or_default().push(tag.to_string());
+        true
+    }
+
+    /// Untag the channel at `url`, forgetting the channel once it has no tags left
+    pub fn remove_tag(&mut self, url: &str, tag: &str) -> bool {
+        let Some(tags) = self.channel_tags.get_mut(url) else {
+            return false;
+        };
+        let before = tags.len();
+        tags.retain(|existing| !existing.eq_ignore_ascii_case(tag.trim()));
+        let removed = tags.len() < before;
+        if tags.is_empty() {
+            self.channel_tags.remove(url);
+        }
+        removed
+    }
+
     /// Add a channel to recent channels list
     pub fn add_recent_channel(&mut self, channel_name: String) {
         // Remove if already exists
@@ -244,10 +767,35 @@ impl Config {
             anyhow::bail!("Page size must be greater than 0");
         }
 
+        if self.ui.default_group.as_deref().is_some_and(|group| group.trim().is_empty()) {
+            anyhow::bail!("ui.default_group cannot be empty; remove it to open the full list");
+        }
+
         if self.max_search_results == 0 {
             anyhow::bail!("Max search results must be greater than 0");
         }
 
+        if OnEmpty::from_name(&self.on_empty).is_none() {
+            anyhow::bail!("Unknown on_empty action '{}', expected welcome, list or error", self.on_empty);
+        }
+
+        if NameSource::from_name(&self.name_source).is_none() {
+            anyhow::bail!("Unknown name_source '{}', expected display, tvg_name or tvg_id", self.name_source);
+        }
+
+        if let Some(key) = self.player.env.keys().find(|key| key.is_empty() || key.contains('=')) {
+            anyhow::bail!("Invalid player environment variable name '{}'", key);
+        }
+
+        if let Some(field) = self.search_fields.iter().find(|field| SearchField::from_name(field).is_none()) {
+            anyhow::bail!("Unknown search field '{}'", field);
+        }
+
+        for rule in &self.name_cleanup.rules {
+            regex::Regex::new(&rule.pattern)
+                .with_context(|| format!("Invalid name cleanup pattern '{}'", rule.pattern))?;
+        }
+
         debug!("Configuration validation passed");
         Ok(())
     }
@@ -285,3 +833,85 @@ impl Config {
         Ok(Self::default_config_path()?.display().to_string())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_partial_config_fills_defaults() {
+        let config: Config = serde_json::from_str(r#"{"player_command": "vlc", "ui": {"page_size": 5}}"#).unwrap();
+        let defaults = Config::default();
+
+        assert_eq!(config.version, 0);
+        assert_eq!(config.player_command, "vlc");
+        assert_eq!(config.ui.page_size, 5);
+        assert_eq!(config.ui.key_bindings, defaults.ui.key_bindings);
+        assert!(config.ui.reverse_list);
+        assert_eq!(config.network.timeout, defaults.network.timeout);
+        assert_eq!(config.max_search_results, defaults.max_search_results);
+        assert_eq!(config.search_fields, ["name"]);
+        assert!(config.validate().is_ok());
+    }
+
+    #[test]
+    fn test_load_upgrades_old_config() {
+        let path = std::env::temp_dir().join(format!("riptv-config-{}.json", std::process::id()));
+        fs::write(&path, r#"{"favorite_channels": ["CNN"]}"#).unwrap();
+
+        let config = Config::load(path.to_str(), false).unwrap();
+        let saved: serde_json::Value = serde_json::from_str(&fs::read_to_string(&path).unwrap()).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(config.version, CONFIG_VERSION);
+        assert_eq!(config.favorite_channels, ["CNN"]);
+        assert_eq!(saved["version"], CONFIG_VERSION);
+        assert_eq!(saved["player_command"], "mpv");
+    }
+
+    #[test]
+    fn test_corrupt_config_is_backed_up() {
+        let path = std::env::temp_dir().join(format!("riptv-corrupt-{}.json", std::process::id()));
+        let backup = path.with_extension("json.bak");
+        let corrupt = r#"{"favorite_channels": ["CNN""#;
+        fs::write(&path, corrupt).unwrap();
+
+        assert!(Config::load(path.to_str(), true).is_err());
+        assert!(!backup.exists());
+
+        let config = Config::load(path.to_str(), false).unwrap();
+        assert!(config.favorite_channels.is_empty());
+        assert_eq!(config.player_command, "mpv");
+        assert_eq!(fs::read_to_string(&backup).unwrap(), corrupt);
+        let regenerated: Config = serde_json::from_str(&fs::read_to_string(&path).unwrap()).unwrap();
+        assert_eq!(regenerated.version, CONFIG_VERSION);
+
+        fs::remove_file(&path).unwrap();
+        fs::remove_file(&backup).unwrap();
+    }
+
+    #[test]
+    fn test_channel_tags_persist() {
+        let path = std::env::temp_dir().join(format!("riptv-tags-{}.json", std::process::id()));
+        let url = "http://example.com/espn";
+        let mut config = Config::default();
+
+        assert!(config.add_tag(url, " Sports "));
+        assert!(config.add_tag(url, "live"));
+        assert!(!config.add_tag(url, "sports"));
+        assert!(!config.add_tag(url, "  "));
+        config.save(path.to_str()).unwrap();
+
+        let mut loaded = Config::load(path.to_str(), true).unwrap();
+        assert_eq!(loaded.tags_for(url), ["Sports", "live"]);
+        assert!(loaded.has_tag(url, "SPORTS"));
+
+        assert!(loaded.remove_tag(url, "sports"));
+        assert!(!loaded.remove_tag(url, "sports"));
+        assert!(loaded.remove_tag(url, "live"));
+        assert!(loaded.channel_tags.is_empty());
+        assert!(loaded.tags_for("http://example.com/unknown").is_empty());
+
+        fs::remove_file(&path).unwrap();
+    }
+}
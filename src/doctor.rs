@@ -0,0 +1,147 @@
+use anyhow::{Context, Result};
+use colored::*;
+use std::fs;
+use std::path::Path;
+
+use crate::config::Config;
+use crate::playlist::PlaylistParser;
+use crate::ui::{display_error, display_info, display_success, display_warning};
+use crate::utils::{get_system_info, is_command_available, separator, separator_width};
+
+/// Result of a single self-check
+#[derive(Debug, PartialEq)]
+pub enum CheckStatus {
+    Pass(String),
+    Warn(String),
+    Fail(String),
+}
+
+/// Check that a required command (e.g. the media player) is on the `PATH`
+pub fn check_command(command: &str) -> CheckStatus {
+    if is_command_available(command) {
+        CheckStatus::Pass(format!("'{}' found", command))
+    } else {
+        CheckStatus::Fail(format!("'{}' not found on PATH", command))
+    }
+}
+
+/// Check for an optional helper command, only warning when it is missing
+pub fn check_optional_command(command: &str) -> CheckStatus {
+    match check_command(command) {
+        CheckStatus::Fail(reason) => CheckStatus::Warn(format!("{} (optional)", reason)),
+        status => status,
+    }
+}
+
+/// Check that the configuration values are valid
+pub fn check_config(config: &Config) -> CheckStatus {
+    match config.validate() {
+        Ok(()) => CheckStatus::Pass("configuration is valid".to_string()),
+        Err(e) => CheckStatus::Fail(format!("invalid configuration: {}", e)),
+    }
+}
+
+/// Check that the config directory exists (or can be created) and is writable
+pub fn check_dir_writable(dir: &Path) -> CheckStatus {
+    let probe = dir.join(".riptv-doctor");
+    let result = fs::create_dir_all(dir)
+        .and_then(|_| fs::write(&probe, b"ok"))
+        .and_then(|_| fs::remove_file(&probe));
+
+    match result {
+        Ok(()) => CheckStatus::Pass(format!("{} is writable", dir.display())),
+        Err(e) => CheckStatus::Fail(format!("{} is not writable: {}", dir.display(), e)),
+    }
+}
+
+/// Check that a playlist file can be loaded and contains channels
+pub async fn check_playlist(path: &str) -> CheckStatus {
+    let mut parser = PlaylistParser::new(false);
+    match parser.parse_file(path).await {
+        Ok(()) if parser.get_channels().is_empty() => {
+            CheckStatus::Warn(format!("{} parsed but contains no channels", path))
+        }
+        Ok(()) => CheckStatus::Pass(format!("{} parsed ({} channels)", path, parser.get_channels().len())),
+        Err(e) => CheckStatus::Fail(format!("{} failed to parse: {}", path, e)),
+    }
+}
+
+fn report(label: &str, status: &CheckStatus) -> bool {
+    match status {
+        CheckStatus::Pass(detail) => display_success(&format!("{}: {}", label, detail)),
+        CheckStatus::Warn(detail) => display_warning(&format!("{}: {}", label, detail)),
+        CheckStatus::Fail(detail) => display_error(&format!("{}: {}", label, detail)),
+    }
+    !matches!(status, CheckStatus::Fail(_))
+}
+
+/// Run all self-checks and print a report, returning whether every check passed
+pub async fn run(config: &Config, player_cmd: &str, playlist: Option<&str>) -> Result<bool> {
+    println!("{}", "🩺 RIPTV Doctor".bright_cyan().bold());
+    println!("{}", separator('═', separator_width(config.ui.separator_width, 50)).bright_blue());
+
+    let system = get_system_info();
+    display_info(&format!("System: {} {} ({})", system.os, system.arch, system.family));
+
+    let config_dir = match &config.source_path {
+        Some(path) => path.parent().map(Path::to_path_buf),
+        None => Some(Config::default_config_path()?.parent().context("Config path has no parent")?.to_path_buf()),
+    };
+
+    let mut passed = true;
+    passed &= report("Player", &check_command(player_cmd));
+    passed &= report("ffprobe", &check_optional_command("ffprobe"));
+    passed &= report("Config", &check_config(config));
+    if let Some(dir) = config_dir {
+        passed &= report("Config dir", &check_dir_writable(&dir));
+    }
+    match playlist {
+        Some(path) => passed &= report("Playlist", &check_playlist(path).await),
+        None => display_info("Playlist: none configured, skipping"),
+    }
+
+    println!();
+    if passed {
+        println!("{}", "✅ All checks passed".bright_green().bold());
+    } else {
+        println!("{}", "❌ Some checks failed".bright_red().bold());
+    }
+
+    Ok(passed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_check_command() {
+        assert!(matches!(check_command("sh"), CheckStatus::Pass(_)));
+        assert!(matches!(check_command("riptv-no-such-binary"), CheckStatus::Fail(_)));
+        assert!(matches!(check_optional_command("riptv-no-such-binary"), CheckStatus::Warn(_)));
+    }
+
+    #[test]
+    fn test_check_config() {
+        let mut config = Config::default();
+        assert!(matches!(check_config(&config), CheckStatus::Pass(_)));
+
+        config.player_command.clear();
+        assert!(matches!(check_config(&config), CheckStatus::Fail(_)));
+    }
+
+    #[tokio::test]
+    async fn test_check_dir_and_playlist() {
+        let dir = std::env::temp_dir().join(format!("riptv-doctor-{}", std::process::id()));
+        assert!(matches!(check_dir_writable(&dir), CheckStatus::Pass(_)));
+
+        let playlist = dir.join("tv.pls");
+        fs::write(&playlist, "[playlist]\nFile1=http://example.com/live\nTitle1=Live\n").unwrap();
+        assert!(matches!(check_playlist(playlist.to_str().unwrap()).await, CheckStatus::Pass(_)));
+
+        let missing = dir.join("missing.m3u");
+        assert!(matches!(check_playlist(missing.to_str().unwrap()).await, CheckStatus::Fail(_)));
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,392 @@
+use anyhow::{Context, Result};
+use colored::*;
+use indicatif::{ProgressBar, ProgressStyle};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
+use tracing::{debug, info};
+use url::Url;
+
+use crate::config::NetworkConfig;
+
+/// How often a stalled read wakes up to check for Ctrl+C
+const CANCEL_POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Largest response header block we accept
+const MAX_HEADER_BYTES: usize = 64 * 1024;
+
+/// Whether a playlist argument should be fetched instead of read from disk
+pub fn is_remote(path: &str) -> bool {
+    path.starts_with("http://") || path.starts_with("https://")
+}
+
+/// Status line and headers of an HTTP response
+#[derive(Debug)]
+struct ResponseHead {
+    status: u16,
+    content_length: Option<u64>,
+    location: Option<String>,
+}
+
+/// A downloaded playlist and the URL it was finally served from
+#[derive(Debug)]
+pub struct DownloadedPlaylist {
+    /// Where the body came from after following redirects; relative channel URLs resolve against it
+    pub url: Url,
+    pub bytes: Vec<u8>,
+}
+
+/// Download a remote playlist, showing progress and stopping once `running` is cleared
+pub async fn download_playlist(url: &str, network: &NetworkConfig, running: &AtomicBool) -> Result<DownloadedPlaylist> {
+    let requested = Url::parse(url).with_context(|| format!("Invalid playlist URL: {}", url))?;
+    let (url, head, mut stream, body_start) = send_following_redirects(requested.clone(), "GET", network, running).await?;
+    if url != requested {
+        info!("↪️ Playlist redirected to {}", url.as_str().bright_white());
+    }
+
+    if !(200..300).contains(&head.status) {
+        anyhow::bail!("Server returned HTTP {} for {}", head.status, url);
+    }
+
+    if let Some(length) = head.content_length.filter(|length| exceeds_limit(*length, network.max_download_bytes)) {
+        anyhow::bail!(
+            "Playlist at {} is {} bytes, over the {} byte limit (network.max_download_bytes)",
+            url,
+            length,
+            network.max_download_bytes
+        );
+    }
+
+    info!("🌐 Downloading playlist: {}", url);
+    let bytes = read_body(&mut stream, body_start, head.content_length, network.max_download_bytes, running).await?;
+    Ok(DownloadedPlaylist { url, bytes })
+}
+
+/// Send a HEAD request for an `http://` URL and return the final status after redirects
+pub async fn head_status(url: &str, network: &NetworkConfig, running: &AtomicBool) -> Result<u16> {
+    let url = Url::parse(url).with_context(|| format!("Invalid URL: {}", url))?;
+    let (_, head, _, _) = send_following_redirects(url, "HEAD", network, running).await?;
+    Ok(head.status)
+}
+
+/// Send a request, following redirects as the network config allows
+async fn send_following_redirects(
+    mut url: Url,
+    method: &str,
+    network: &NetworkConfig,
+    running: &AtomicBool,
+) -> Result<(Url, ResponseHead, TcpStream, Vec<u8>)> {
+    let mut redirects = 0;
+
+    loop {
+        let (head, stream, body_start) = request(&url, method, network, running).await?;
+
+        if (300..400).contains(&head.status) && network.follow_redirects {
+            let location = head
+                .location
+                .with_context(|| format!("Redirect without Location header from {}", url))?;
+            redirects += 1;
+            if redirects > network.max_redirects {
+                anyhow::bail!("Too many redirects fetching {}", url);
+            }
+            url = url.join(&location).with_context(|| format!("Invalid redirect target: {}", location))?;
+            debug!("Following redirect to {}", url);
+            continue;
+        }
+
+        return Ok((url, head, stream, body_start));
+    }
+}
+
+/// Send a request and read back the response head
+async fn request(
+    url: &Url,
+    method: &str,
+    network: &NetworkConfig,
+    running: &AtomicBool,
+) -> Result<(ResponseHead, TcpStream, Vec<u8>)> {
+    if url.scheme() != "http" {
+        anyhow::bail!("Only http:// playlist URLs can be downloaded; save {} locally first", url);
+    }
+
+    let host = url.host_str().with_context(|| format!("Playlist URL has no host: {}", url))?;
+    let port = url.port_or_known_default().unwrap_or(80);
+    let connect = TcpStream::connect((host, port));
+    let mut stream = tokio::time::timeout(Duration::from_secs(network.timeout), connect)
+        .await
+        .with_context(|| format!("Timed out connecting to {}", host))?
+        .with_context(|| format!("Failed to connect to {}", host))?;
+
+    let mut path = url.path().to_string();
+    if let Some(query) = url.query() {
+        path.push('?');
+        path.push_str(query);
+    }
+
+    // HTTP/1.0 keeps the body un-chunked and ends it by closing the connection
+    let mut request = format!(
+        "{} {} HTTP/1.0\r\nHost: {}\r\nUser-Agent: {}\r\nAccept: */*\r\n",
+        method, path, host, network.user_agent
+    );
+    for (name, value) in &network.extra_headers {
+        request.push_str(&format!("{}: {}\r\n", name, value));
+    }
+    request.push_str("\r\n");
+    stream.write_all(request.as_bytes()).await.context("Failed to send playlist request")?;
+
+    let mut buffer = Vec::new();
+    let header_end = loop {
+        if let Some(end) = buffer.windows(4).position(|window| window == b"\r\n\r\n") {
+            break end;
+        }
+        if buffer.len() > MAX_HEADER_BYTES {
+            anyhow::bail!("Response headers from {} are too large", host);
+        }
+        if read_chunk(&mut stream, &mut buffer, running).await? == 0 {
+            anyhow::bail!("Connection to {} closed before the response headers", host);
+        }
+    };
+
+    let head = parse_response_head(&String::from_utf8_lossy(&buffer[..header_end]))?;
+    let body_start = buffer.split_off(header_end + 4);
+    Ok((head, stream, body_start))
+}
+
+/// Whether `bytes` is over a `max_bytes` limit, where 0 means unlimited
+fn exceeds_limit(bytes: u64, max_bytes: u64) -> bool {
+    max_bytes > 0 && bytes > max_bytes
+}
+
+/// Read the rest of the body, driving a progress bar from `Content-Length` when known
+async fn read_body(
+    stream: &mut TcpStream,
+    mut body: Vec<u8>,
+    content_length: Option<u64>,
+    max_bytes: u64,
+    running: &AtomicBool,
+) -> Result<Vec<u8>> {
+    let pb = match content_length {
+        Some(length) => {
+            let pb = ProgressBar::new(length);
+            pb.set_style(
+                ProgressStyle::default_bar()
+                    .template("{bar:40.cyan/blue} {bytes:>10}/{total_bytes:10} {bytes_per_sec}")
+                    .unwrap(),
+            );
+            pb
+        }
+        None => {
+            let pb = ProgressBar::new_spinner();
+            pb.set_style(ProgressStyle::default_spinner().template("{spinner:.green} {bytes} {msg}").unwrap());
+            pb.set_message("Downloading playlist...");
+            pb
+        }
+    };
+    pb.set_position(body.len() as u64);
+
+    // With a Content-Length the limit was checked up front, and reading stops at that length
+    while content_length.is_none_or(|length| (body.len() as u64) < length) {
+        if content_length.is_none() && exceeds_limit(body.len() as u64, max_bytes) {
+            pb.abandon_with_message("❌ Download too large");
+            anyhow::bail!("Playlist download exceeded the {} byte limit (network.max_download_bytes)", max_bytes);
+        }
+
+        match read_chunk(stream, &mut body, running).await {
+            Ok(0) => break,
+            Ok(_) => pb.set_position(body.len() as u64),
+            Err(e) => {
+                pb.abandon_with_message("❌ Download stopped");
+                return Err(e);
+            }
+        }
+    }
+
+    if let Some(length) = content_length {
+        if (body.len() as u64) < length {
+            pb.abandon();
+            anyhow::bail!("Playlist download ended early ({} of {} bytes)", body.len(), length);
+        }
+        body.truncate(length as usize);
+    }
+
+    pb.finish_and_clear();
+    info!("✅ Downloaded {} bytes", body.len().to_string().bright_green());
+    Ok(body)
+}
+
+/// Append the next chunk from `stream`, failing as soon as `running` is cleared
+async fn read_chunk(stream: &mut TcpStream, buffer: &mut Vec<u8>, running: &AtomicBool) -> Result<usize> {
+    let mut chunk = [0u8; 16 * 1024];
+    loop {
+        if !running.load(Ordering::Relaxed) {
+            anyhow::bail!("Playlist download cancelled");
+        }
+
+        if let Ok(read) = tokio::time::timeout(CANCEL_POLL_INTERVAL, stream.read(&mut chunk)).await {
+            let read = read.context("Failed to read playlist response")?;
+            buffer.extend_from_slice(&chunk[..read]);
+            return Ok(read);
+        }
+    }
+}
+
+/// Parse the status line and the headers we care about
+fn parse_response_head(head: &str) -> Result<ResponseHead> {
+    let mut lines = head.lines();
+    let status_line = lines.next().unwrap_or_default();
+    let status = status_line
+        .split_whitespace()
+        .nth(1)
+        .and_then(|code| code.parse().ok())
+        .with_context(|| format!("Malformed HTTP status line: {}", status_line))?;
+
+    let mut response = ResponseHead { status, content_length: None, location: None };
+    for line in lines {
+        let Some((name, value)) = line.split_once(':') else {
+            continue;
+        };
+        let value = value.trim();
+        if name.eq_ignore_ascii_case("content-length") {
+            response.content_length = value.parse().ok();
+        } else if name.eq_ignore_ascii_case("location") {
+            response.location = Some(value.to_string());
+        }
+    }
+
+    Ok(response)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+    use std::sync::Arc;
+    use std::time::Instant;
+    use tokio::net::TcpListener;
+
+    #[test]
+    fn test_parse_response_head() {
+        let head = parse_response_head("HTTP/1.1 302 Found\r\nlocation: /tv.m3u\r\nContent-Length: 12").unwrap();
+        assert_eq!(head.status, 302);
+        assert_eq!(head.content_length, Some(12));
+        assert_eq!(head.location.as_deref(), Some("/tv.m3u"));
+        assert!(parse_response_head("garbage").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_download_with_content_length() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            let mut request = [0u8; 1024];
+            let _ = socket.read(&mut request).await.unwrap();
+            let body = "#EXTM3U\n";
+            let response = format!("HTTP/1.0 200 OK\r\nContent-Length: {}\r\n\r\n{}", body.len(), body);
+            socket.write_all(response.as_bytes()).await.unwrap();
+        });
+
+        let running = AtomicBool::new(true);
+        let url = format!("http://{}/tv.m3u", addr);
+        let download = download_playlist(&url, &Config::default().network, &running).await.unwrap();
+        assert_eq!(download.bytes, b"#EXTM3U\n");
+        assert_eq!(download.url.as_str(), url);
+    }
+
+    #[tokio::test]
+    async fn test_aborted_download_returns_promptly() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            let mut request = [0u8; 1024];
+            let _ = socket.read(&mut request).await.unwrap();
+            socket
+                .write_all(b"HTTP/1.0 200 OK\r\nContent-Length: 1000000000\r\n\r\n#EXTM3U\n")
+                .await
+                .unwrap();
+            // Stall forever, like a huge download on a slow link
+            tokio::time::sleep(Duration::from_secs(60)).await;
+        });
+
+        let running = Arc::new(AtomicBool::new(true));
+        let stopper = Arc::clone(&running);
+        tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_millis(200)).await;
+            stopper.store(false, Ordering::Relaxed);
+        });
+
+        let start = Instant::now();
+        let url = format!("http://{}/huge.m3u", addr);
+        let err = download_playlist(&url, &Config::default().network, &running).await.unwrap_err();
+
+        assert!(err.to_string().contains("cancelled"));
+        assert!(start.elapsed() < Duration::from_secs(2));
+    }
+
+    #[tokio::test]
+    async fn test_download_size_limit() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            for _ in 0..2 {
+                let (mut socket, _) = listener.accept().await.unwrap();
+                let mut request = [0u8; 1024];
+                let _ = socket.read(&mut request).await.unwrap();
+                // No Content-Length, so the limit has to catch the body as it streams in
+                let response = format!("HTTP/1.0 200 OK\r\n\r\n#EXTM3U\n{}", "x".repeat(4096));
+                socket.write_all(response.as_bytes()).await.unwrap();
+            }
+        });
+
+        let running = AtomicBool::new(true);
+        let url = format!("http://{}/big.m3u", addr);
+        let network = NetworkConfig { max_download_bytes: 1024, ..NetworkConfig::default() };
+        let err = download_playlist(&url, &network, &running).await.unwrap_err();
+        assert!(err.to_string().contains("byte limit"));
+
+        let unlimited = NetworkConfig { max_download_bytes: 0, ..NetworkConfig::default() };
+        assert_eq!(download_playlist(&url, &unlimited, &running).await.unwrap().bytes.len(), 8 + 4096);
+    }
+
+    #[tokio::test]
+    async fn test_redirect_chain_final_url() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            loop {
+                let (mut socket, _) = listener.accept().await.unwrap();
+                let mut request = [0u8; 1024];
+                let read = socket.read(&mut request).await.unwrap();
+                let request = String::from_utf8_lossy(&request[..read]).into_owned();
+                let response = if request.starts_with("GET /start ") {
+                    "HTTP/1.0 302 Found\r\nLocation: /moved\r\n\r\n".to_string()
+                } else if request.starts_with("GET /moved ") {
+                    format!("HTTP/1.0 301 Moved Permanently\r\nLocation: http://{}/lists/uk/tv.m3u\r\n\r\n", addr)
+                } else {
+                    let body = "#EXTM3U\n#EXTINF:-1,BBC One\nbbc1/index.m3u8\n#EXTINF:-1,CNN\nhttp://cdn.example.com/cnn\n";
+                    format!("HTTP/1.0 200 OK\r\nContent-Length: {}\r\n\r\n{}", body.len(), body)
+                };
+                socket.write_all(response.as_bytes()).await.unwrap();
+            }
+        });
+
+        let running = AtomicBool::new(true);
+        let url = format!("http://{}/start", addr);
+        let download = download_playlist(&url, &Config::default().network, &running).await.unwrap();
+        assert_eq!(download.url.as_str(), format!("http://{}/lists/uk/tv.m3u", addr));
+
+        let mut parser = crate::playlist::PlaylistParser::new(false);
+        parser.set_base_url(Some(download.url));
+        parser.parse_bytes(download.bytes, std::path::Path::new(&url)).await.unwrap();
+        let urls: Vec<_> = parser.get_channels().iter().map(|channel| channel.url.as_str()).collect();
+        assert_eq!(urls, [format!("http://{}/lists/uk/bbc1/index.m3u8", addr).as_str(), "http://cdn.example.com/cnn"]);
+        assert_eq!(parser.base_url().unwrap().path(), "/lists/uk/tv.m3u");
+
+        // Two hops are over a limit of one
+        let network = NetworkConfig { max_redirects: 1, ..NetworkConfig::default() };
+        let err = download_playlist(&url, &network, &running).await.unwrap_err();
+        assert!(err.to_string().contains("Too many redirects"));
+    }
+}
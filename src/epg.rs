@@ -0,0 +1,259 @@
+use anyhow::{Context, Result};
+use colored::*;
+use regex::Regex;
+use std::collections::HashSet;
+use std::fs;
+use std::path::Path;
+use tracing::info;
+
+use crate::catchup::days_from_civil;
+use crate::playlist::Channel;
+use crate::utils::separator;
+
+/// One `<programme>` from an XMLTV guide
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Programme {
+    /// Guide channel id, matched against a channel's `tvg-id`
+    pub channel: String,
+    pub title: String,
+    /// Seconds since the Unix epoch
+    pub start: i64,
+    pub stop: i64,
+}
+
+/// Channel ids and programmes known to an XMLTV guide
+#[derive(Debug, Clone, Default)]
+pub struct Epg {
+    pub channel_ids: HashSet<String>,
+    pub programmes: Vec<Programme>,
+}
+
+impl Epg {
+    /// Load an uncompressed XMLTV file
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref();
+        let content = fs::read_to_string(path)
+            .with_context(|| format!("Failed to read EPG file: {}", path.display()))?;
+
+        let epg = Self::parse_xmltv(&content);
+        info!("📅 Loaded EPG with {} channels and {} programmes", epg.channel_ids.len(), epg.programmes.len());
+        Ok(epg)
+    }
+
+    /// Collect ids from `<channel id="...">` and `<programme channel="...">` elements, and every
+    /// programme with a title and a readable start and stop time
+    pub fn parse_xmltv(content: &str) -> Self {
+        let pattern = Regex::new(r#"<(?:channel\s[^>]*?\bid|programme\s[^>]*?\bchannel)\s*=\s*"([^"]*)""#)
+            .expect("valid XMLTV id pattern");
+        let programme_pattern = Regex::new(r"(?s)<programme\s([^>]*)>(.*?)</programme>").expect("valid XMLTV programme pattern");
+        let attribute_pattern = Regex::new(r#"([\w-]+)\s*=\s*"([^"]*)""#).expect("valid XML attribute pattern");
+        let title_pattern = Regex::new(r"(?s)<title[^>]*>(.*?)</title>").expect("valid XMLTV title pattern");
+
+        let channel_ids = pattern
+            .captures_iter(content)
+            .map(|captures| captures[1].trim().to_string())
+            .filter(|id| !id.is_empty())
+            .collect();
+
+        let programmes = programme_pattern
+            .captures_iter(content)
+            .filter_map(|captures| {
+                let attribute = |name: &str| {
+                    attribute_pattern
+                        .captures_iter(&captures[1])
+                        .find(|attribute| &attribute[1] == name)
+                        .map(|attribute| attribute[2].trim().to_string())
+                };
+                Some(Programme {
+                    channel: attribute("channel")?,
+                    title: unescape_xml(title_pattern.captures(&captures[2])?[1].trim()),
+                    start: parse_xmltv_time(&attribute("start")?)?,
+                    stop: parse_xmltv_time(&attribute("stop")?)?,
+                })
+            })
+            .collect();
+
+        Self { channel_ids, programmes }
+    }
+
+    /// The airing of `title` on guide channel `channel_id` that ends soonest after `now`
+    ///
+    /// Titles match ignoring case and surrounding whitespace.
+    pub fn find_programme(&self, channel_id: &str, title: &str, now: i64) -> Option<&Programme> {
+        let title = title.trim().to_lowercase();
+        self.programmes
+            .iter()
+            .filter(|programme| programme.channel == channel_id.trim() && programme.stop > now)
+            .filter(|programme| programme.title.to_lowercase() == title)
+            .min_by_key(|programme| programme.start)
+    }
+}
+
+/// Seconds since the Unix epoch for an XMLTV time like `20240101193000 +0100`; no offset means UTC
+pub fn parse_xmltv_time(value: &str) -> Option<i64> {
+    let (stamp, offset) = match value.trim().split_once(' ') {
+        Some((stamp, offset)) => (stamp, offset.trim()),
+        None => (value.trim(), ""),
+    };
+    if stamp.len() < 12 {
+        return None;
+    }
+    let field = |range: std::ops::Range<usize>| stamp.get(range)?.parse::<i64>().ok();
+    let in_range = |value: i64, range: std::ops::RangeInclusive<i64>| range.contains(&value).then_some(value);
+    let seconds = if stamp.len() >= 14 { in_range(field(12..14)?, 0..=60)? } else { 0 };
+    let month = in_range(field(4..6)?, 1..=12)?;
+    let day = in_range(field(6..8)?, 1..=31)?;
+    let hour = in_range(field(8..10)?, 0..=23)?;
+    let minute = in_range(field(10..12)?, 0..=59)?;
+    let days = days_from_civil(field(0..4)?, month as u32, day as u32);
+    let local = days * 86_400 + hour * 3600 + minute * 60 + seconds;
+
+    let offset = match offset.as_bytes() {
+        [] => 0,
+        [sign @ (b'+' | b'-'), ..] if offset.len() == 5 => {
+            let part = |range: std::ops::Range<usize>| offset.get(range)?.parse::<i64>().ok();
+            let minutes = in_range(part(1..3)?, 0..=14)? * 60 + in_range(part(3..5)?, 0..=59)?;
+            if *sign == b'-' { -minutes * 60 } else { minutes * 60 }
+        }
+        _ => return None,
+    };
+    Some(local - offset)
+}
+
+/// Undo the five predefined XML entities
+fn unescape_xml(text: &str) -> String {
+    text.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&apos;", "'")
+        .replace("&amp;", "&")
+}
+
+/// How well a playlist's `tvg-id`s line up with an EPG
+#[derive(Debug, Default)]
+pub struct Coverage<'a> {
+    /// Channels whose `tvg-id` has guide data
+    pub matched: Vec<&'a Channel>,
+    /// Channels with a `tvg-id` the guide doesn't know
+    pub missing_epg: Vec<&'a Channel>,
+    /// Channels without any `tvg-id`
+    pub no_tvg_id: Vec<&'a Channel>,
+}
+
+/// Sort channels by whether the EPG covers them
+pub fn coverage<'a>(channels: &'a [Channel], epg: &Epg) -> Coverage<'a> {
+    let mut coverage = Coverage::default();
+
+    for channel in channels {
+        match channel.tvg_id.as_deref().map(str::trim).filter(|id| !id.is_empty()) {
+            Some(id) if epg.channel_ids.contains(id) => coverage.matched.push(channel),
+            Some(_) => coverage.missing_epg.push(channel),
+            None => coverage.no_tvg_id.push(channel),
+        }
+    }
+
+    coverage
+}
+
+impl Coverage<'_> {
+    /// Print the channels that need their EPG mapping fixed
+    pub fn show(&self, width: usize) {
+        let total = self.matched.len() + self.missing_epg.len() + self.no_tvg_id.len();
+
+        println!("{}", "📅 EPG Coverage".bright_cyan().bold());
+        println!("{}", separator('═', width).bright_blue());
+        println!("✅ With EPG data: {} of {}", self.matched.len().to_string().bright_green(), total);
+
+        println!(
+            "\n{}",
+            format!("⚠️ tvg-id not in EPG ({}):", self.missing_epg.len()).bright_yellow().bold()
+        );
+        for channel in &self.missing_epg {
+            println!("  📺 {} ({})", channel.name.bright_white(), channel.tvg_id.as_deref().unwrap_or_default());
+        }
+
+        println!(
+            "\n{}",
+            format!("❌ No tvg-id ({}):", self.no_tvg_id.len()).bright_red().bold()
+        );
+        for channel in &self.no_tvg_id {
+            println!("  📺 {}", channel.name.bright_white());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn channel(name: &str, tvg_id: Option<&str>) -> Channel {
+        let mut channel = Channel::new(name.to_string(), format!("http://example.com/{}", name));
+        channel.tvg_id = tvg_id.map(str::to_string);
+        channel
+    }
+
+    #[test]
+    fn test_coverage() {
+        let epg = Epg::parse_xmltv(
+            r#"<tv>
+                <channel id="bbc1.uk"><display-name>BBC One</display-name></channel>
+                <programme start="20240101000000 +0000" channel="cnn.us"><title>News</title></programme>
+            </tv>"#,
+        );
+        assert_eq!(epg.channel_ids.len(), 2);
+
+        let channels = vec![
+            channel("BBC", Some("bbc1.uk")),
+            channel("CNN", Some("cnn.us")),
+            channel("ITV", Some("itv.uk")),
+            channel("Local", None),
+            channel("Blank", Some(" ")),
+        ];
+        let coverage = coverage(&channels, &epg);
+
+        let names = |list: &[&Channel]| list.iter().map(|c| c.name.clone()).collect::<Vec<_>>();
+        assert_eq!(names(&coverage.matched), ["BBC", "CNN"]);
+        assert_eq!(names(&coverage.missing_epg), ["ITV"]);
+        assert_eq!(names(&coverage.no_tvg_id), ["Local", "Blank"]);
+    }
+
+    #[test]
+    fn test_parse_programmes() {
+        let epg = Epg::parse_xmltv(
+            r#"<tv>
+                <programme start="20240310200000 +0100" stop="20240310213000 +0100" channel="bbc1.uk">
+                    <title lang="en">Match of the Day &amp; More</title>
+                </programme>
+                <programme start="20240310" stop="20240311" channel="bbc1.uk"><title>Bad times</title></programme>
+                <programme start="20240310213000" stop="20240310220000" channel="bbc1.uk"></programme>
+            </tv>"#,
+        );
+
+        assert_eq!(
+            epg.programmes,
+            [Programme {
+                channel: "bbc1.uk".to_string(),
+                title: "Match of the Day & More".to_string(),
+                start: 1_710_097_200,
+                stop: 1_710_102_600,
+            }]
+        );
+        assert_eq!(parse_xmltv_time("20240310213000"), Some(1_710_106_200));
+        assert_eq!(parse_xmltv_time("20240310213000 -0030"), Some(1_710_108_000));
+        assert_eq!(parse_xmltv_time("20240310213000 GMT"), None);
+
+        // Malformed fields are rejected instead of panicking or wrapping
+        for value in [
+            "20240310213000 +0é0",
+            "2024-1-1213000",
+            "20241310213000",
+            "20240300213000",
+            "20240310243000",
+            "20240310216000",
+            "20240310213000 +1500",
+            "20240310213000 +01-5",
+        ] {
+            assert_eq!(parse_xmltv_time(value), None, "{}", value);
+        }
+    }
+}
@@ -0,0 +1,123 @@
+use colored::*;
+use std::collections::VecDeque;
+
+use crate::catchup::{local_utc_offset, unix_now};
+use crate::utils::separator;
+
+/// Errors kept for `--show-errors` and the selector's error key
+pub const RECENT_ERRORS: usize = 50;
+
+/// A playback or playlist failure
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ErrorEntry {
+    /// Channel being played, or `None` for playlist errors
+    pub channel: Option<String>,
+    pub reason: String,
+    /// Seconds since the Unix epoch
+    pub at: i64,
+}
+
+/// The most recent errors, oldest first; older entries are dropped once full
+#[derive(Debug)]
+pub struct ErrorLog {
+    entries: VecDeque<ErrorEntry>,
+    capacity: usize,
+}
+
+impl ErrorLog {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            entries: VecDeque::with_capacity(capacity),
+            capacity,
+        }
+    }
+
+    pub fn push(&mut self, channel: Option<&str>, error: &anyhow::Error) {
+        self.push_at(channel, format!("{:#}", error), unix_now());
+    }
+
+    fn push_at(&mut self, channel: Option<&str>, reason: String, at: i64) {
+        if self.capacity == 0 {
+            return;
+        }
+        if self.entries.len() == self.capacity {
+            self.entries.pop_front();
+        }
+        self.entries.push_back(ErrorEntry {
+            channel: channel.map(str::to_string),
+            reason,
+            at,
+        });
+    }
+
+    pub fn entries(&self) -> impl DoubleEndedIterator<Item = &ErrorEntry> {
+        self.entries.iter()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn show(&self, width: usize) {
+        println!("{}", "🚨 Recent Errors".bright_cyan().bold());
+        println!("{}", separator('═', width).bright_blue());
+        if self.is_empty() {
+            println!("{}", "✅ No errors this session".bright_green());
+            return;
+        }
+
+        let offset = local_utc_offset();
+        for entry in self.entries().rev() {
+            println!(
+                "{} {} {}",
+                clock_time(entry.at, offset).bright_black(),
+                entry.channel.as_deref().unwrap_or("playlist").bright_yellow(),
+                entry.reason.bright_red()
+            );
+        }
+    }
+}
+
+impl Default for ErrorLog {
+    fn default() -> Self {
+        Self::new(RECENT_ERRORS)
+    }
+}
+
+/// `HH:MM:SS` in local time
+fn clock_time(at: i64, utc_offset: i64) -> String {
+    let seconds = (at + utc_offset).rem_euclid(86_400);
+    format!("{:02}:{:02}:{:02}", seconds / 3600, seconds / 60 % 60, seconds % 60)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_keeps_last_entries() {
+        let mut log = ErrorLog::new(3);
+        for i in 0..5 {
+            log.push_at(Some(&format!("Channel {}", i)), format!("failed {}", i), i);
+        }
+        log.push(None, &anyhow::anyhow!("No such file").context("Failed to load playlist"));
+
+        let entries: Vec<_> = log.entries().collect();
+        assert_eq!(entries.len(), 3);
+        assert_eq!(entries[0].channel.as_deref(), Some("Channel 3"));
+        assert_eq!(entries[1].reason, "failed 4");
+        assert_eq!(entries[2].channel, None);
+        assert_eq!(entries[2].reason, "Failed to load playlist: No such file");
+
+        let mut disabled = ErrorLog::new(0);
+        disabled.push(None, &anyhow::anyhow!("ignored"));
+        assert!(disabled.is_empty());
+    }
+
+    #[test]
+    fn test_clock_time() {
+        assert_eq!(clock_time(0, 0), "00:00:00");
+        assert_eq!(clock_time(3_723, 0), "01:02:03");
+        assert_eq!(clock_time(0, -3_600), "23:00:00");
+    }
+}
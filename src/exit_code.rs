@@ -0,0 +1,67 @@
+use std::fmt;
+
+/// Process exit codes that scripts can rely on
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExitCode {
+    Success = 0,
+    Failure = 1,
+    NoChannels = 2,
+    PlayerMissing = 3,
+    PlaylistLoad = 4,
+    ConfigInvalid = 5,
+    DoctorFailed = 6,
+}
+
+impl ExitCode {
+    pub fn code(self) -> i32 {
+        self as i32
+    }
+
+    /// The code tagged anywhere in `error`'s context chain, or `Failure` if none was
+    pub fn from_error(error: &anyhow::Error) -> Self {
+        error.downcast_ref::<ExitCode>().copied().unwrap_or(ExitCode::Failure)
+    }
+}
+
+impl fmt::Display for ExitCode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let description = match self {
+            ExitCode::Success => "Success",
+            ExitCode::Failure => "Failed",
+            ExitCode::NoChannels => "No channels to play",
+            ExitCode::PlayerMissing => "Media player not found",
+            ExitCode::PlaylistLoad => "Playlist could not be loaded",
+            ExitCode::ConfigInvalid => "Configuration is invalid",
+            ExitCode::DoctorFailed => "A doctor check failed",
+        };
+        f.write_str(description)
+    }
+}
+
+impl std::error::Error for ExitCode {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::Context;
+
+    #[test]
+    fn test_exit_code_from_error() {
+        let untagged = anyhow::anyhow!("something broke");
+        assert_eq!(ExitCode::from_error(&untagged).code(), 1);
+
+        let load: anyhow::Result<()> = Err(anyhow::anyhow!("No such file"));
+        let load = load.context("Failed to load playlist: tv.m3u").context(ExitCode::PlaylistLoad);
+        assert_eq!(ExitCode::from_error(&load.unwrap_err()).code(), 4);
+
+        // Tagged at the root, with a detailed message on top
+        let empty = anyhow::Error::new(ExitCode::NoChannels).context("No channels found matching 'bbc'");
+        assert_eq!(empty.to_string(), "No channels found matching 'bbc'");
+        assert_eq!(ExitCode::from_error(&empty), ExitCode::NoChannels);
+
+        let config = anyhow::anyhow!("Page size must be greater than 0").context(ExitCode::ConfigInvalid);
+        assert_eq!(ExitCode::from_error(&config).code(), 5);
+        assert_eq!(ExitCode::PlayerMissing.code(), 3);
+        assert_eq!(ExitCode::DoctorFailed.code(), 6);
+    }
+}
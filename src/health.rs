@@ -0,0 +1,257 @@
+use anyhow::{Context, Result};
+use colored::*;
+use indicatif::{ProgressBar, ProgressStyle};
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::Semaphore;
+use tokio::task::JoinSet;
+
+use crate::config::NetworkConfig;
+use crate::download;
+use crate::playlist::Channel;
+use crate::utils::separator;
+
+/// Streams checked over the network at once
+const MAX_CONCURRENT_CHECKS: usize = 32;
+
+/// Error recorded for streams the downloader can't request
+const NOT_CHECKED: &str = "not checked (not http)";
+
+/// Outcome of checking one channel's stream URL
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct CheckResult {
+    pub name: String,
+    pub url: String,
+    /// Final HTTP status after redirects, if a response came back
+    pub status: Option<u16>,
+    pub latency_ms: Option<u64>,
+    pub error: Option<String>,
+}
+
+impl CheckResult {
+    /// Label the result is counted under in the summary
+    pub fn label(&self) -> String {
+        match (self.status, &self.error) {
+            (Some(status), _) => status.to_string(),
+            (None, Some(error)) => error.clone(),
+            (None, None) => "unknown".to_string(),
+        }
+    }
+
+    pub fn is_ok(&self) -> bool {
+        self.status.is_some_and(|status| (200..400).contains(&status))
+    }
+
+    pub fn is_checked(&self) -> bool {
+        self.error.as_deref() != Some(NOT_CHECKED)
+    }
+}
+
+/// Results of a `--check` run, in playlist order
+#[derive(Debug, Default)]
+pub struct HealthReport {
+    pub results: Vec<CheckResult>,
+}
+
+impl HealthReport {
+    /// How many channels ended with each status code or error
+    pub fn distribution(&self) -> BTreeMap<String, usize> {
+        let mut counts = BTreeMap::new();
+        for result in &self.results {
+            *counts.entry(result.label()).or_insert(0) += 1;
+        }
+        counts
+    }
+
+    pub fn show(&self, width: usize) {
+        let ok = self.results.iter().filter(|result| result.is_ok()).count();
+        let unchecked = self.results.iter().filter(|result| !result.is_checked()).count();
+
+        println!("{}", "🩺 Channel Health".bright_cyan().bold());
+        println!("{}", separator('═', width).bright_blue());
+        println!("🔍 Checked: {}", self.results.len().to_string().bright_white());
+        println!("✅ Reachable: {}", ok.to_string().bright_green());
+        println!("❌ Failing: {}", (self.results.len() - ok - unchecked).to_string().bright_red());
+        if unchecked > 0 {
+            println!("❔ Not checked (not http): {}", unchecked.to_string().bright_yellow());
+        }
+        println!();
+        println!("{}", "📊 Status codes:".bright_yellow().bold());
+        for (label, count) in self.distribution() {
+            println!("  {:<24} {}", label, count.to_string().bright_white());
+        }
+    }
+
+    pub fn to_json(&self) -> Result<String> {
+        Ok(serde_json::to_string_pretty(&self.results)? + "\n")
+    }
+
+    pub fn to_csv(&self) -> String {
+        let field = |value: &str| {
+            if value.contains([',', '"', '\n', '\r']) {
+                format!("\"{}\"", value.replace('"', "\"\""))
+            } else {
+                value.to_string()
+            }
+        };
+
+        let mut out = String::from("name,url,status,latency_ms,error\n");
+        for result in &self.results {
+            let row = [
+                field(&result.name),
+                field(&result.url),
+                result.status.map(|status| status.to_string()).unwrap_or_default(),
+                result.latency_ms.map(|latency| latency.to_string()).unwrap_or_default(),
+                field(result.error.as_deref().unwrap_or_default()),
+            ];
+            out.push_str(&row.join(","));
+            out.push('\n');
+        }
+        out
+    }
+
+    /// Write the per-channel results as .csv or .json, chosen by extension
+    pub fn export<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        let path = path.as_ref();
+        let extension = path.extension().and_then(|ext| ext.to_str()).unwrap_or_default();
+        let content = match extension.to_ascii_lowercase().as_str() {
+            "csv" => self.to_csv(),
+            "json" => self.to_json()?,
+            _ => anyhow::bail!("Can't tell the report format of '{}': use a .csv or .json file name", path.display()),
+        };
+        fs::write(path, content).with_context(|| format!("Failed to write health report: {}", path.display()))
+    }
+}
+
+/// HEAD every channel's stream URL, a few at a time, with a progress bar
+pub async fn check_channels(channels: &[Channel], network: &NetworkConfig, running: Arc<AtomicBool>) -> HealthReport {
+    let pb = ProgressBar::new(channels.len() as u64);
+    pb.set_style(
+        ProgressStyle::default_bar()
+            .template("{spinner:.green} [{bar:30.cyan/blue}] {pos}/{len} checked ({elapsed})")
+            .unwrap()
+            .progress_chars("█▉ "),
+    );
+
+    let semaphore = Arc::new(Semaphore::new(MAX_CONCURRENT_CHECKS));
+    let mut checks = JoinSet::new();
+    for (idx, channel) in channels.iter().enumerate() {
+        let semaphore = Arc::clone(&semaphore);
+        let network = network.clone();
+        let running = Arc::clone(&running);
+        let (name, url) = (channel.name.clone(), channel.url.clone());
+        checks.spawn(async move {
+            let _permit = semaphore.acquire_owned().await;
+            if !running.load(Ordering::Relaxed) {
+                return None;
+            }
+            Some((idx, check_url(name, url, &network, &running).await))
+        });
+    }
+
+    let mut results = Vec::with_capacity(channels.len());
+    while let Some(finished) = checks.join_next().await {
+        if let Ok(Some(result)) = finished {
+            results.push(result);
+        }
+        pb.inc(1);
+    }
+    pb.finish_and_clear();
+
+    results.sort_by_key(|(idx, _)| *idx);
+    HealthReport {
+        results: results.into_iter().map(|(_, result)| result).collect(),
+    }
+}
+
+async fn check_url(name: String, url: String, network: &NetworkConfig, running: &AtomicBool) -> CheckResult {
+    let mut result = CheckResult { name, url, status: None, latency_ms: None, error: None };
+
+    // The downloader only speaks plain HTTP
+    if !result.url.starts_with("http://") {
+        result.error = Some(NOT_CHECKED.to_string());
+        return result;
+    }
+
+    let start = Instant::now();
+    let timeout = Duration::from_secs(network.timeout);
+    match tokio::time::timeout(timeout, download::head_status(&result.url, network, running)).await {
+        Ok(Ok(status)) => {
+            result.status = Some(status);
+            result.latency_ms = Some(start.elapsed().as_millis() as u64);
+        }
+        Ok(Err(e)) => result.error = Some(e.to_string()),
+        Err(_) => result.error = Some("timed out".to_string()),
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample_report() -> HealthReport {
+        HealthReport {
+            results: vec![
+                CheckResult {
+                    name: "BBC One".to_string(),
+                    url: "http://example.com/bbc".to_string(),
+                    status: Some(200),
+                    latency_ms: Some(42),
+                    error: None,
+                },
+                CheckResult {
+                    name: "News, Live".to_string(),
+                    url: "http://example.com/news".to_string(),
+                    status: Some(404),
+                    latency_ms: Some(7),
+                    error: None,
+                },
+                CheckResult {
+                    name: "Gone".to_string(),
+                    url: "http://gone.example.com/".to_string(),
+                    status: None,
+                    latency_ms: None,
+                    error: Some("timed out".to_string()),
+                },
+                CheckResult {
+                    name: "ITV".to_string(),
+                    url: "http://example.com/itv".to_string(),
+                    status: Some(200),
+                    latency_ms: Some(18),
+                    error: None,
+                },
+            ],
+        }
+    }
+
+    #[test]
+    fn test_report_serialization() {
+        let report = sample_report();
+        assert_eq!(
+            report.to_csv(),
+            "name,url,status,latency_ms,error\n\
+             BBC One,http://example.com/bbc,200,42,\n\
+             \"News, Live\",http://example.com/news,404,7,\n\
+             Gone,http://gone.example.com/,,,timed out\n\
+             ITV,http://example.com/itv,200,18,\n"
+        );
+
+        let json: serde_json::Value = serde_json::from_str(&report.to_json().unwrap()).unwrap();
+        assert_eq!(json[0]["url"], "http://example.com/bbc");
+        assert_eq!(json[0]["status"], 200);
+        assert_eq!(json[0]["latency_ms"], 42);
+        assert!(json[2]["status"].is_null());
+        assert_eq!(json[2]["error"], "timed out");
+
+        let distribution: Vec<_> = report.distribution().into_iter().collect();
+        assert_eq!(distribution, [("200".to_string(), 2), ("404".to_string(), 1), ("timed out".to_string(), 1)]);
+
+        assert!(report.export("report.txt").is_err());
+    }
+}
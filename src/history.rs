@@ -0,0 +1,162 @@
+use anyhow::{Context, Result};
+use colored::*;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+use tracing::debug;
+
+use crate::config::Config;
+use crate::playlist::Channel;
+use crate::utils::separator;
+
+/// How often a single channel has been played
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PlayCount {
+    /// Channel name at the time it was last played
+    pub name: String,
+    pub count: u64,
+}
+
+/// Play counts keyed by stream URL, persisted next to the config file
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct PlayStats {
+    plays: HashMap<String, PlayCount>,
+
+    /// File the counts were loaded from, used when saving
+    #[serde(skip)]
+    source_path: Option<PathBuf>,
+}
+
+impl PlayStats {
+    /// Load the store kept beside the config file; in-memory only if the config has no file
+    pub fn for_config(config: &Config) -> Result<Self> {
+        match config.source_path.as_ref().and_then(|path| path.parent()) {
+            Some(dir) => Self::load(dir.join("play_stats.json")),
+            None => Ok(Self::default()),
+        }
+    }
+
+    /// Load counts from `path`, starting empty if the file doesn't exist yet
+    pub fn load(path: PathBuf) -> Result<Self> {
+        let mut stats = if path.exists() {
+            let content = fs::read_to_string(&path)
+                .with_context(|| format!("Failed to read play stats: {}", path.display()))?;
+            serde_json::from_str::<PlayStats>(&content)
+                .with_context(|| format!("Failed to parse play stats: {}", path.display()))?
+        } else {
+            PlayStats::default()
+        };
+
+        stats.source_path = Some(path);
+        Ok(stats)
+    }
+
+    /// Write counts back to the file they were loaded from, if any
+    pub fn save(&self) -> Result<()> {
+        let Some(path) = &self.source_path else {
+            return Ok(());
+        };
+
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)
+                .with_context(|| format!("Failed to create directory: {}", parent.display()))?;
+        }
+
+        let content = serde_json::to_string_pretty(self)
+            .context("Failed to serialize play stats")?;
+        fs::write(path, content)
+            .with_context(|| format!("Failed to write play stats: {}", path.display()))?;
+
+        debug!("Play stats saved to {}", path.display());
+        Ok(())
+    }
+
+    /// Count one more play of `channel`
+    pub fn record(&mut self, channel: &Channel) {
+        let entry = self.plays.entry(channel.url.clone()).or_insert_with(|| PlayCount {
+            name: channel.name.clone(),
+            count: 0,
+        });
+        entry.name = channel.name.clone();
+        entry.count += 1;
+    }
+
+    /// Number of times the stream at `url` has been played
+    #[cfg(test)]
+    pub fn count(&self, url: &str) -> u64 {
+        self.plays.get(url).map_or(0, |play| play.count)
+    }
+
+    /// Most played channels first, ties broken by name
+    pub fn most_played(&self, limit: usize) -> Vec<(&str, &PlayCount)> {
+        let mut plays: Vec<_> = self.plays
+            .iter()
+            .map(|(url, play)| (url.as_str(), play))
+            .collect();
+        plays.sort_by(|a, b| b.1.count.cmp(&a.1.count).then_with(|| a.1.name.cmp(&b.1.name)));
+        plays.truncate(limit);
+        plays
+    }
+
+    /// Print the most watched channels
+    pub fn show(&self, limit: usize, width: usize) {
+        println!("{}", "📈 Most Watched Channels".bright_cyan().bold());
+        println!("{}", separator('═', width).bright_blue());
+
+        let plays = self.most_played(limit);
+        if plays.is_empty() {
+            println!("{}", "No channels played yet.".bright_yellow());
+            return;
+        }
+
+        for (i, (_, play)) in plays.iter().enumerate() {
+            println!(
+                "{}. {} ({} plays)",
+                format!("{:2}", i + 1).bright_blue(),
+                play.name.bright_white(),
+                play.count.to_string().bright_green()
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_record_and_count() {
+        let mut stats = PlayStats::default();
+        let bbc = Channel::new("BBC One".to_string(), "http://example.com/bbc1".to_string());
+        let cnn = Channel::new("CNN".to_string(), "http://example.com/cnn".to_string());
+
+        stats.record(&bbc);
+        stats.record(&cnn);
+        stats.record(&cnn);
+
+        assert_eq!(stats.count("http://example.com/bbc1"), 1);
+        assert_eq!(stats.count("http://example.com/cnn"), 2);
+        assert_eq!(stats.count("http://example.com/other"), 0);
+
+        let names: Vec<_> = stats.most_played(10).iter().map(|(_, play)| play.name.clone()).collect();
+        assert_eq!(names, ["CNN", "BBC One"]);
+
+        // Without a source path saving is a no-op
+        assert!(stats.save().is_ok());
+    }
+
+    #[test]
+    fn test_save_and_load() {
+        let path = std::env::temp_dir().join(format!("riptv-play-stats-{}.json", std::process::id()));
+        let channel = Channel::new("BBC One".to_string(), "http://example.com/bbc1".to_string());
+
+        let mut stats = PlayStats::load(path.clone()).unwrap();
+        stats.record(&channel);
+        stats.save().unwrap();
+
+        let reloaded = PlayStats::load(path.clone()).unwrap();
+        assert_eq!(reloaded.count("http://example.com/bbc1"), 1);
+        fs::remove_file(path).unwrap();
+    }
+}
@@ -0,0 +1,223 @@
+use colored::*;
+use std::collections::{BTreeMap, HashMap};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::task::JoinSet;
+use tracing::debug;
+use url::Url;
+
+use crate::config::NetworkConfig;
+use crate::download;
+use crate::utils::{is_valid_url, separator};
+
+/// Logo URLs checked over the network at once
+const MAX_CONCURRENT_CHECKS: usize = 16;
+
+/// What the syntax check makes of a `tvg-logo` URL
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LogoCheck {
+    /// Usable as written
+    Keep,
+    /// Usable once trimmed and escaped, as this URL
+    Repair(String),
+    /// Not an http(s) URL at all
+    Invalid,
+}
+
+/// Check a logo URL without touching the network
+pub fn check_logo_syntax(logo: &str) -> LogoCheck {
+    let trimmed = logo.trim();
+    if !is_valid_url(trimmed) {
+        return LogoCheck::Invalid;
+    }
+
+    match Url::parse(trimmed) {
+        Ok(url) if matches!(url.scheme(), "http" | "https") => {
+            let needs_escaping = trimmed.chars().any(|c| c.is_whitespace() || !c.is_ascii());
+            match (needs_escaping, trimmed == logo) {
+                (false, true) => LogoCheck::Keep,
+                (false, false) => LogoCheck::Repair(trimmed.to_string()),
+                (true, _) => LogoCheck::Repair(url.to_string()),
+            }
+        }
+        _ => LogoCheck::Invalid,
+    }
+}
+
+/// Outcome of checking every distinct logo in a playlist
+#[derive(Debug, Default)]
+pub struct LogoReport {
+    /// Distinct logo URLs looked at
+    pub checked: usize,
+    /// https logos that were kept without a HEAD check
+    pub unverified: usize,
+    /// Broken logo URLs and why they were dropped
+    pub broken: BTreeMap<String, String>,
+    /// Replacement for each logo that changes; `None` strips it
+    pub fixes: HashMap<String, Option<String>>,
+}
+
+impl LogoReport {
+    pub fn repaired(&self) -> usize {
+        self.fixes.values().filter(|fix| fix.is_some()).count()
+    }
+
+    pub fn show(&self, channels_changed: usize, width: usize) {
+        println!("{}", "🖼️ Logo Check".bright_cyan().bold());
+        println!("{}", separator('═', width).bright_blue());
+        println!("🔍 Checked: {}", self.checked.to_string().bright_white());
+        println!("🔧 Repaired: {}", self.repaired().to_string().bright_green());
+        println!("❌ Broken: {}", self.broken.len().to_string().bright_red());
+        if self.unverified > 0 {
+            println!("❔ Not checked (https): {}", self.unverified.to_string().bright_yellow());
+        }
+        println!("📺 Channels updated: {}", channels_changed.to_string().bright_green());
+
+        for (logo, reason) in &self.broken {
+            println!("  {} {} ({})", "✗".bright_red(), logo, reason.bright_yellow());
+        }
+    }
+}
+
+/// Check each distinct logo: fix its syntax, then HEAD the `http://` ones to make sure they load
+pub async fn check_logos<I>(logos: I, network: &NetworkConfig, running: Arc<AtomicBool>) -> LogoReport
+where
+    I: IntoIterator<Item = String>,
+{
+    let mut report = LogoReport::default();
+    let mut checks = JoinSet::new();
+
+    for logo in logos {
+        report.checked += 1;
+
+        let url = match check_logo_syntax(&logo) {
+            LogoCheck::Keep => logo.clone(),
+            LogoCheck::Repair(fixed) => {
+                report.fixes.insert(logo.clone(), Some(fixed.clone()));
+                fixed
+            }
+            LogoCheck::Invalid => {
+                report.broken.insert(logo.clone(), "not an http(s) URL".to_string());
+                report.fixes.insert(logo, None);
+                continue;
+            }
+        };
+
+        // The downloader only speaks plain HTTP, so https logos are kept unverified
+        if !url.starts_with("http://") {
+            report.unverified += 1;
+            continue;
+        }
+        if !running.load(Ordering::Relaxed) {
+            break;
+        }
+
+        if checks.len() >= MAX_CONCURRENT_CHECKS {
+            let finished = checks.join_next().await;
+            if let Some(Ok((logo, problem))) = finished {
+                record_problem(&mut report, logo, problem);
+            }
+        }
+
+        let network = network.clone();
+        let running = Arc::clone(&running);
+        let timeout = Duration::from_secs(network.timeout);
+        checks.spawn(async move {
+            let problem = match tokio::time::timeout(timeout, download::head_status(&url, &network, &running)).await {
+                Ok(Ok(status)) if (200..300).contains(&status) => None,
+                Ok(Ok(status)) => Some(format!("HTTP {}", status)),
+                Ok(Err(e)) => Some(e.to_string()),
+                Err(_) => Some("timed out".to_string()),
+            };
+            (logo, problem)
+        });
+    }
+
+    while let Some(result) = checks.join_next().await {
+        if let Ok((logo, problem)) = result {
+            record_problem(&mut report, logo, problem);
+        }
+    }
+
+    report
+}
+
+fn record_problem(report: &mut LogoReport, logo: String, problem: Option<String>) {
+    if let Some(reason) = problem {
+        debug!("Broken logo {}: {}", logo, reason);
+        report.broken.insert(logo.clone(), reason);
+        report.fixes.insert(logo, None);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::playlist::PlaylistParser;
+
+    #[test]
+    fn test_check_logo_syntax() {
+        assert_eq!(check_logo_syntax("https://example.com/bbc.png"), LogoCheck::Keep);
+        assert_eq!(
+            check_logo_syntax(" http://example.com/bbc.png\t"),
+            LogoCheck::Repair("http://example.com/bbc.png".to_string())
+        );
+        assert_eq!(
+            check_logo_syntax("http://example.com/my logo.png"),
+            LogoCheck::Repair("http://example.com/my%20logo.png".to_string())
+        );
+        assert_eq!(check_logo_syntax("logo.png"), LogoCheck::Invalid);
+        assert_eq!(check_logo_syntax("ftp://example.com/logo.png"), LogoCheck::Invalid);
+        assert_eq!(check_logo_syntax(""), LogoCheck::Invalid);
+    }
+
+    #[tokio::test]
+    async fn test_broken_logos_are_stripped() {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            loop {
+                let (mut socket, _) = listener.accept().await.unwrap();
+                let mut request = [0u8; 1024];
+                let read = socket.read(&mut request).await.unwrap();
+                let status = if String::from_utf8_lossy(&request[..read]).starts_with("HEAD /good.png ") {
+                    "200 OK"
+                } else {
+                    "404 Not Found"
+                };
+                let _ = socket.write_all(format!("HTTP/1.0 {}\r\n\r\n", status).as_bytes()).await;
+            }
+        });
+
+        let good = format!("http://{}/good.png", addr);
+        let missing = format!("http://{}/missing.png", addr);
+        let content = format!(
+            "#EXTM3U\n\
+             #EXTINF:-1 tvg-logo=\"{good}\",Good\nhttp://example.com/1\n\
+             #EXTINF:-1 tvg-logo=\"{missing}\",Missing\nhttp://example.com/2\n\
+             #EXTINF:-1 tvg-logo=\"not a url\",Bad\nhttp://example.com/3\n\
+             #EXTINF:-1 tvg-logo=\" {good} \",Padded\nhttp://example.com/4\n\
+             #EXTINF:-1 tvg-logo=\"https://example.com/logo.png\",Secure\nhttp://example.com/5\n\
+             #EXTINF:-1,No Logo\nhttp://example.com/6\n"
+        );
+        let mut parser = PlaylistParser::new(false);
+        parser.parse_str(content).await.unwrap();
+
+        let report = check_logos(parser.distinct_logos(), &NetworkConfig::default(), Arc::new(AtomicBool::new(true))).await;
+        assert_eq!(report.checked, 5);
+        assert_eq!(report.unverified, 1);
+        assert_eq!(report.repaired(), 1);
+        assert_eq!(report.broken.keys().collect::<Vec<_>>(), [&missing, &"not a url".to_string()]);
+        assert_eq!(report.broken[&missing], "HTTP 404");
+
+        assert_eq!(parser.update_logos(&report.fixes), 3);
+        let logos: Vec<_> = parser.get_channels().iter().map(|channel| channel.logo.as_deref()).collect();
+        assert_eq!(
+            logos,
+            [Some(good.as_str()), None, None, Some(good.as_str()), Some("https://example.com/logo.png"), None]
+        );
+    }
+}
@@ -1,20 +1,44 @@
-use anyhow::Result;
-use clap::Parser;
+use anyhow::{Context, Result};
+use clap::{Parser, Subcommand};
 use colored::*;
 use std::process;
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
-use tracing::{info, error, debug};
+use tracing::{info, error, debug, warn};
 use tokio::signal;
+use tracing_subscriber::fmt::writer::BoxMakeWriter;
 
+mod catchup;
+mod clean;
 mod config;
+mod doctor;
+mod download;
+mod epg;
+mod error_log;
+mod exit_code;
+mod health;
+mod history;
+mod logging;
+mod logos;
 mod player;
 mod playlist;
+mod probe;
+mod recording;
+mod report;
+mod search;
+mod server;
+mod sources;
+mod thumbnail;
 mod ui;
 mod utils;
 
-use config::Config;
+use config::{Config, OnEmpty};
+use exit_code::ExitCode;
+use epg::Epg;
+use history::PlayStats;
+use logging::{JsonFormat, LogFormat};
 use player::IptvPlayer;
+use playlist::StatsSort;
 
 #[derive(Parser)]
 #[command(
@@ -24,6 +48,9 @@ use player::IptvPlayer;
     author = "Your Name"
 )]
 struct Args {
+    #[command(subcommand)]
+    command: Option<Command>,
+
     /// Path to M3U playlist file
     #[arg(short, long, value_name = "FILE")]
     playlist: Option<String>,
@@ -40,10 +67,26 @@ struct Args {
     #[arg(short, long)]
     verbose: bool,
 
+    /// Only log warnings and errors, and skip the startup banner
+    #[arg(short, long, conflicts_with = "verbose")]
+    quiet: bool,
+
+    /// Do not print the startup banner
+    #[arg(long)]
+    no_banner: bool,
+
     /// Configuration file path
     #[arg(short, long)]
     config: Option<String>,
 
+    /// Fail on a corrupt config file instead of backing it up and using defaults
+    #[arg(long)]
+    strict_config: bool,
+
+    /// Network timeout in seconds for this run, overriding network.timeout in the config
+    #[arg(long, value_name = "SECS", value_parser = clap::value_parser!(u64).range(1..))]
+    timeout: Option<u64>,
+
     /// List available playlists and exit
     #[arg(long)]
     list: bool,
@@ -52,21 +95,240 @@ struct Args {
     #[arg(short, long)]
     search: Option<String>,
 
+    /// Play the best search match instead of listing results
+    #[arg(long, requires = "search")]
+    first: bool,
+
+    /// Play a random channel instead of opening the selector
+    #[arg(long, conflicts_with_all = ["search", "smart_random"])]
+    random: bool,
+
+    /// Play a random channel, picking favorites and recently played channels more often
+    #[arg(long, conflicts_with = "search")]
+    smart_random: bool,
+
+    /// Only match channels whose name starts with the search text
+    #[arg(long)]
+    exact: bool,
+
+    /// Only accept http(s) streams, dropping local files, rtmp and other schemes while parsing
+    #[arg(long)]
+    strict_url: bool,
+
+    /// Pick a starting letter before the channel list, like a contacts list
+    #[arg(long)]
+    browse_alpha: bool,
+
+    /// Mark channels with Tab in the selector and save them to this .m3u, .json or .csv file
+    #[arg(long, value_name = "FILE")]
+    select_export: Option<String>,
+
+    /// Print the session's recent playback and playlist errors on exit
+    #[arg(long)]
+    show_errors: bool,
+
+    /// Check every channel's stream URL and summarize the status codes
+    #[arg(long)]
+    check: bool,
+
+    /// Write per-channel --check results (url, status, latency) to this .csv or .json file
+    #[arg(long, value_name = "FILE", requires = "check")]
+    export_report: Option<String>,
+
+    /// Check channel logos, repairing or stripping broken ones; combine with --export to save the result
+    #[arg(long)]
+    fix_logos: bool,
+
+    /// Save the loaded playlist, or the --search matches in rank order, to this file; .m3u, .json or .csv picks the format
+    #[arg(long, value_name = "FILE")]
+    export: Option<String>,
+
+    /// Only include channels from this group
+    #[arg(short, long, value_name = "NAME")]
+    group: Option<String>,
+
+    /// Only include channels with this tag (tags are edited with Alt-T in the channel list)
+    #[arg(long, value_name = "TAG")]
+    tag: Option<String>,
+
+    /// Print only the number of matching channels and exit
+    #[arg(long)]
+    count: bool,
+
     /// Show statistics about the playlist
     #[arg(long)]
     stats: bool,
+
+    /// Order --stats breakdowns by channel count or by name
+    #[arg(long, value_enum, value_name = "ORDER", default_value_t = StatsSort::Count)]
+    stats_sort: StatsSort,
+
+    /// Print --stats or --list output as JSON
+    #[arg(long)]
+    json: bool,
+
+    /// With --stats, show the most watched channels instead
+    #[arg(long, requires = "stats")]
+    plays: bool,
+
+    /// With --stats, also probe each stream's video resolution with ffprobe
+    #[arg(long, requires = "stats", conflicts_with_all = ["json", "plays"])]
+    probe: bool,
+
+    /// XMLTV guide file used for EPG checks
+    #[arg(long, value_name = "FILE")]
+    epg: Option<String>,
+
+    /// List channels whose tvg-id has no EPG entry, or no tvg-id at all
+    #[arg(long, requires = "epg")]
+    export_epg_channels: bool,
+
+    /// Verify the playlist file against this SHA-256 digest before parsing
+    #[arg(long, value_name = "HEX")]
+    sha256: Option<String>,
+
+    /// Treat an HLS master playlist as one channel per variant stream
+    #[arg(long)]
+    expand_hls: bool,
+
+    /// Parse the playlist this many times in each mode and print timings
+    #[arg(long, value_name = "RUNS", hide = true)]
+    profile_parse: Option<usize>,
+
+    /// Log output format
+    #[arg(long, value_enum, default_value_t = LogFormat::Plain)]
+    log_format: LogFormat,
+
+    /// Start the player with audio muted
+    #[arg(long)]
+    mute_on_start: bool,
+
+    /// Set an environment variable for the player; repeatable
+    #[arg(long, value_name = "KEY=VALUE", value_parser = parse_env_var)]
+    player_env: Vec<(String, String)>,
+
+    /// List channels top-down instead of bottom-up
+    #[arg(long)]
+    no_reverse: bool,
+
+    /// Start the player in the background and exit instead of waiting for it
+    #[arg(long)]
+    detach: bool,
+
+    /// Stop playback automatically after this many minutes (up to a week)
+    #[arg(long, value_name = "MINUTES", value_parser = clap::value_parser!(u64).range(1..=MAX_SLEEP_MINUTES))]
+    sleep: Option<u64>,
+}
+
+/// Longest `--sleep` accepted, one week
+const MAX_SLEEP_MINUTES: u64 = 7 * 24 * 60;
+
+#[derive(Subcommand)]
+enum Command {
+    /// Remove riptv's cached files and/or reset the config to defaults
+    #[command(group(clap::ArgGroup::new("targets").required(true).multiple(true).args(["cache", "config", "all"])))]
+    Clean {
+        /// Remove the cache folder (preview thumbnails)
+        #[arg(long)]
+        cache: bool,
+
+        /// Delete the config file and write a default one in its place
+        #[arg(long)]
+        config: bool,
+
+        /// Same as --cache --config
+        #[arg(long)]
+        all: bool,
+
+        /// Don't ask before removing anything
+        #[arg(long, short)]
+        yes: bool,
+    },
+
+    /// Check that the player, config and playlist are set up correctly
+    Doctor,
+
+    /// List all groups with their channel counts
+    Groups {
+        /// Print the list as JSON
+        #[arg(long)]
+        json: bool,
+    },
+
+    /// Play a channel without the selector
+    Play {
+        /// 1-based position of the channel in the playlist
+        #[arg(long, value_name = "INDEX")]
+        at: usize,
+    },
+
+    /// Schedule an ffmpeg recording of a programme from the --epg guide
+    ///
+    /// Scheduled recordings run while riptv is open in interactive mode.
+    RecordSchedule {
+        /// Channel name as listed in the playlist
+        channel: String,
+
+        /// Programme title as listed in the guide
+        title: String,
+
+        /// Minutes to start early and keep recording after the programme ends
+        #[arg(long, value_name = "MINUTES", default_value_t = recording::DEFAULT_PADDING_MINUTES)]
+        padding: u64,
+
+        /// Folder to save the recording in (defaults to the videos folder)
+        #[arg(long, value_name = "DIR")]
+        dir: Option<std::path::PathBuf>,
+    },
+
+    /// Check the playlist's metadata in one pass: counts, duplicate and invalid URLs, missing logos and tvg-ids
+    Report {
+        /// JSON file to save the report to; a summary is also printed
+        #[arg(value_name = "FILE")]
+        file: std::path::PathBuf,
+    },
+
+    /// Serve a small HTTP API for remote control of playback
+    Serve {
+        /// Port to listen on
+        #[arg(long, default_value_t = 8080)]
+        port: u16,
+
+        /// Address to bind to (use 0.0.0.0 to allow other devices on the network)
+        #[arg(long, default_value = "127.0.0.1")]
+        bind: String,
+    },
 }
 
-fn setup_logging(verbose: bool) {
-    let level = if verbose { "debug" } else { "info" };
+/// Log to stdout normally, or to stderr when stdout carries machine-readable output
+fn setup_logging(verbose: bool, quiet: bool, to_stderr: bool, format: LogFormat) {
+    let level = if verbose {
+        "debug"
+    } else if quiet {
+        "warn"
+    } else {
+        "info"
+    };
     
-    tracing_subscriber::fmt()
+    let writer = if to_stderr {
+        BoxMakeWriter::new(std::io::stderr)
+    } else {
+        BoxMakeWriter::new(std::io::stdout)
+    };
+
+    let builder = tracing_subscriber::fmt()
         .with_env_filter(format!("riptv={}", level))
-        .with_target(false)
-        .with_thread_ids(false)
-        .with_file(false)
-        .with_line_number(false)
-        .init();
+        .with_writer(writer);
+
+    match format {
+        LogFormat::Plain => builder
+            .with_target(false)
+            .with_thread_ids(false)
+            .with_file(false)
+            .with_line_number(false)
+            .init(),
+        LogFormat::Json => builder.event_format(JsonFormat).init(),
+    }
 }
 
 fn print_banner() {
@@ -84,12 +346,127 @@ fn print_banner() {
     println!();
 }
 
+/// Print the banner unless `--no-banner` or `--quiet` was given
+fn maybe_print_banner<F: FnOnce()>(args: &Args, print: F) -> bool {
+    if args.no_banner || args.quiet || args.count {
+        return false;
+    }
+
+    print();
+    true
+}
+
+/// Parse a `--player-env` value
+fn parse_env_var(value: &str) -> Result<(String, String), String> {
+    match value.split_once('=') {
+        Some((key, value)) if !key.is_empty() => Ok((key.to_string(), value.to_string())),
+        _ => Err(format!("expected KEY=VALUE, got '{}'", value)),
+    }
+}
+
+/// Apply command-line flags that override config settings for this run
+fn apply_cli_overrides(args: &Args, config: &mut Config) {
+    if args.no_reverse {
+        config.ui.reverse_list = false;
+    }
+    if args.mute_on_start {
+        config.player.mute_on_start = true;
+    }
+    config.player.env.extend(args.player_env.iter().cloned());
+    if args.exact {
+        config.search.anchored = true;
+    }
+    if args.browse_alpha {
+        config.ui.browse_alpha = true;
+    }
+    if let Some(timeout) = args.timeout {
+        config.network.timeout = timeout;
+    }
+    if args.strict_url {
+        config.strict_url = true;
+    }
+}
+
+/// Look `title` up in the guide for `channel_name` and save an ffmpeg capture of it to the schedule
+fn schedule_recording(
+    player: &IptvPlayer,
+    epg_path: Option<&str>,
+    channel_name: &str,
+    title: &str,
+    padding: u64,
+    dir: Option<std::path::PathBuf>,
+) -> anyhow::Result<()> {
+    let Some(epg_path) = epg_path else {
+        anyhow::bail!("Scheduling a recording needs a guide: pass --epg <FILE>");
+    };
+    let channels = player.parser().get_channels();
+    let channel = channels
+        .iter()
+        .find(|channel| channel.name == channel_name)
+        .or_else(|| channels.iter().find(|channel| channel.name.eq_ignore_ascii_case(channel_name)))
+        .with_context(|| format!("No channel named '{}' in the playlist", channel_name))?;
+    let Some(tvg_id) = channel.tvg_id.as_deref() else {
+        anyhow::bail!("{} has no tvg-id, so it can't be found in the guide", channel.name);
+    };
+
+    let epg = Epg::load(epg_path)?;
+    let now = catchup::unix_now();
+    let programme = epg
+        .find_programme(tvg_id, title, now)
+        .with_context(|| format!("No upcoming '{}' on {} in the guide", title, channel.name))?;
+    let job = recording::RecordingJob::for_programme(channel, programme, padding, now, &dir.unwrap_or_else(recording::default_dir))?;
+
+    if !utils::is_command_available("ffmpeg") {
+        println!("{}", "⚠️ ffmpeg was not found; install it before the recording starts".bright_yellow());
+    }
+    println!(
+        "{}",
+        format!(
+            "📼 Scheduled '{}' on {}: {} minutes starting in {} minutes, saved to {}",
+            job.title,
+            job.channel,
+            job.duration_secs() / 60,
+            (job.start - now) / 60,
+            job.output.display()
+        )
+        .bright_green()
+        .bold()
+    );
+    println!("{}", "Recordings run while riptv is open in interactive mode.".bright_cyan());
+
+    let mut schedule = recording::Schedule::for_config(player.config())?;
+    schedule.add(job, now);
+    schedule.save()
+}
+
+/// Handle a start without any playlist as `config.on_empty` says, returning a playlist to load if one was picked
+fn resolve_empty_playlist(config: &Config) -> anyhow::Result<Option<String>> {
+    match OnEmpty::from_name(&config.on_empty).unwrap_or(OnEmpty::Error) {
+        OnEmpty::Welcome => {
+            ui::show_welcome_message();
+            println!("{}", "Start with --playlist <FILE>, or set default_playlist in the config.".bright_cyan());
+            Ok(None)
+        }
+        OnEmpty::List => {
+            let playlists = player::discover_playlists(config, dirs::home_dir().as_deref());
+            Ok(ui::pick_playlist(&playlists)?.map(|path| path.to_string_lossy().into_owned()))
+        }
+        OnEmpty::Error => Err(anyhow::Error::new(ExitCode::PlaylistLoad)
+            .context("No playlist specified. Use --playlist or set default_playlist in the config")),
+    }
+}
+
 /// Restore terminal to normal state
 fn cleanup_terminal() {
     debug!("Cleaning up terminal state");
     
     use utils::terminal::*;
-    use std::io::{self, Write};
+    use std::io::{self, IsTerminal, Write};
+
+    // Nothing to restore when output is piped, and escape codes would corrupt it
+    if !io::stdout().is_terminal() {
+        return;
+    }
     
     // Print escape sequences to restore terminal
     print!("{}{}{}{}", 
@@ -158,17 +535,61 @@ async fn run_app(args: Args) -> Result<()> {
         }
     });
     
+    if let Some(Command::Clean { cache, config, all, yes }) = args.command {
+        let targets = clean::CleanTargets { cache: cache || all, config: config || all };
+        let cache_dir = utils::cache_dir();
+        let config_path = match &args.config {
+            Some(path) => std::path::PathBuf::from(path),
+            None => Config::default_config_path()?,
+        };
+
+        let planned = clean::planned(targets, cache_dir.as_deref(), &config_path);
+        if !yes && !planned.is_empty() {
+            for path in &planned {
+                println!("  {}", path.display());
+            }
+            if !ui::confirm_action("🗑️ Remove these?")? {
+                println!("{}", "Nothing removed".bright_yellow());
+                return Ok(());
+            }
+        }
+        let removed = clean::clean(targets, cache_dir.as_deref(), &config_path)?;
+        clean::show(&removed, targets, &config_path);
+        return Ok(());
+    }
+
     // Load configuration
-    let config = Config::load(args.config.as_deref())?;
+    let mut config = Config::load(args.config.as_deref(), args.strict_config).context(ExitCode::ConfigInvalid)?;
+    apply_cli_overrides(&args, &mut config);
+    config.validate().context(ExitCode::ConfigInvalid)?;
+
+    if let Some(Command::Doctor) = args.command {
+        let playlist = args.playlist.as_deref().or(config.default_playlist.as_deref());
+        let passed = doctor::run(&config, &args.player, playlist).await?;
+        cleanup_terminal();
+        if !passed {
+            return Err(anyhow::Error::new(ExitCode::DoctorFailed).context("Some doctor checks failed"));
+        }
+        return Ok(());
+    }
     
+    if args.stats && args.plays {
+        PlayStats::for_config(&config)?.show(20, utils::separator_width(config.ui.separator_width, 50));
+        cleanup_terminal();
+        return Ok(());
+    }
+
     // Determine playlist path
-    let playlist_path = args.playlist
-        .or(config.default_playlist.clone())
-        .unwrap_or_else(|| {
-            error!("No playlist specified. Use --playlist or set default in config.");
-            cleanup_terminal();
-            process::exit(1);
-        });
+    let playlist_path = match args.playlist.clone().or(config.default_playlist.clone()) {
+        Some(path) => path,
+        None => match resolve_empty_playlist(&config)? {
+            Some(path) => path,
+            None => {
+                cleanup_terminal();
+                return Ok(());
+            }
+        },
+    };
 
     info!("Using playlist: {}", playlist_path);
 
@@ -178,31 +599,175 @@ async fn run_app(args: Args) -> Result<()> {
         config,
         args.parallel,
     );
+    player.set_expected_sha256(args.sha256.clone());
+    player.set_sleep_timer(args.sleep.map(|minutes| std::time::Duration::from_secs(minutes.saturating_mul(60))));
+    player.set_group_filter(args.group.clone());
+    player.set_tag_filter(args.tag.clone());
+    player.set_detach(args.detach);
+    player.set_expand_hls(args.expand_hls);
 
     // Handle special commands
     if args.list {
-        player.list_playlists().await?;
+        player.list_playlists(args.json).await?;
+        cleanup_terminal();
+        return Ok(());
+    }
+
+    if let Some(runs) = args.profile_parse {
+        let content = std::fs::read_to_string(&playlist_path)
+            .with_context(|| format!("Failed to read playlist file: {}", playlist_path))
+            .context(ExitCode::PlaylistLoad)?;
+        let options = playlist::ParseOptions::from_config(player.config());
+        playlist::profile_parse(&content, runs, &options).await?.show();
         cleanup_terminal();
         return Ok(());
     }
 
     // Load playlist
-    player.load_playlist(&playlist_path).await?;
+    player
+        .load_playlist_with_shutdown(&playlist_path, &running)
+        .await
+        .context(ExitCode::PlaylistLoad)?;
+
+    if args.count {
+        println!("{}", player.count_channels(args.search.as_deref()));
+        return Ok(());
+    }
+
+    if player.parser().get_channels().is_empty() {
+        return Err(anyhow::Error::new(ExitCode::NoChannels).context(format!("No channels found in {}", playlist_path)));
+    }
 
     if args.stats {
-        player.show_statistics();
+        player.show_statistics(args.json, args.stats_sort)?;
+        if args.probe {
+            let report = probe::probe_channels(player.parser().get_channels(), probe::PROBE_TIMEOUT, running.clone()).await;
+            report.show(utils::separator_width(player.config().ui.separator_width, 50));
+        }
+        cleanup_terminal();
+        return Ok(());
+    }
+
+    if args.export_epg_channels {
+        if let Some(epg_path) = &args.epg {
+            let epg = Epg::load(epg_path)?;
+            epg::coverage(player.parser().get_channels(), &epg)
+                .show(utils::separator_width(player.config().ui.separator_width, 50));
+        }
+        cleanup_terminal();
+        return Ok(());
+    }
+
+    if let Some(Command::Groups { json }) = &args.command {
+        player.show_groups(*json)?;
+        cleanup_terminal();
+        return Ok(());
+    }
+
+    if let Some(Command::Report { file }) = &args.command {
+        let report = player.parser().quality_report();
+        report.show(utils::separator_width(player.config().ui.separator_width, 50));
+        report.export(file)?;
+        println!("\n{}", format!("💾 Saved playlist report to {}", file.display()).bright_green().bold());
+        cleanup_terminal();
+        return Ok(());
+    }
+
+    if let Some(Command::Play { at }) = &args.command {
+        let result = player.play_at(*at).await;
+        cleanup_terminal();
+        return result;
+    }
+
+    if let Some(Command::RecordSchedule { channel, title, padding, dir }) = &args.command {
+        let result = schedule_recording(&player, args.epg.as_deref(), channel, title, *padding, dir.clone());
+        cleanup_terminal();
+        return result;
+    }
+
+    if let Some(Command::Serve { port, bind }) = &args.command {
+        let result = server::serve(&mut player, bind, *port, running).await;
+        cleanup_terminal();
+        return result;
+    }
+
+    if args.check {
+        let report = health::check_channels(player.parser().get_channels(), &player.config().network, running.clone()).await;
+        report.show(utils::separator_width(player.config().ui.separator_width, 50));
+        if let Some(path) = &args.export_report {
+            report.export(path)?;
+            println!("{}", format!("💾 Saved health report to {}", path).bright_green().bold());
+        }
+        cleanup_terminal();
+        return Ok(());
+    }
+
+    if args.fix_logos {
+        let report = logos::check_logos(player.parser().distinct_logos(), &player.config().network, running.clone()).await;
+        let changed = player.parser_mut().update_logos(&report.fixes);
+        report.show(changed, utils::separator_width(player.config().ui.separator_width, 50));
+        if args.export.is_none() {
+            cleanup_terminal();
+            return Ok(());
+        }
+    }
+
+    if let (Some(search_term), Some(export_path)) = (&args.search, &args.export) {
+        let exported = player.export_matches(search_term, export_path)?;
+        println!(
+            "{}",
+            format!("💾 Exported {} matches for '{}' to {}", exported, search_term, export_path).bright_green().bold()
+        );
+        cleanup_terminal();
+        return Ok(());
+    }
+
+    if let Some(export_path) = &args.export {
+        let format = player.parser().export(export_path)?;
+        println!(
+            "{}",
+            format!("💾 Exported {} channels to {} ({:?})", player.parser().get_channels().len(), export_path, format)
+                .bright_green()
+                .bold()
+        );
         cleanup_terminal();
         return Ok(());
     }
 
     if let Some(search_term) = args.search {
-        player.search_channels(&search_term).await?;
+        if args.first {
+            player.play_first_match(&search_term).await?;
+        } else {
+            player.search_channels(&search_term).await?;
+        }
         cleanup_terminal();
         return Ok(());
     }
 
+    if args.random || args.smart_random {
+        let result = player.play_random(args.smart_random).await;
+        cleanup_terminal();
+        return result;
+    }
+
+    if let Some(export_path) = &args.select_export {
+        let result = player.select_and_export(export_path).await;
+        cleanup_terminal();
+        return result;
+    }
+
+    // Scheduled recordings run until the session ends
+    let schedule = recording::Schedule::for_config(player.config()).unwrap_or_else(|e| {
+        warn!("⚠️ Failed to load scheduled recordings: {:#}", e);
+        recording::Schedule::default()
+    });
+    let _recordings = recording::spawn_scheduled(schedule.pending(catchup::unix_now()), running.clone());
+
     // Start interactive mode with graceful shutdown support
     let result = player.run_interactive_with_shutdown(running).await;
+    if args.show_errors {
+        player.recent_errors().show(utils::separator_width(player.config().ui.separator_width, 50));
+    }
     
     // Always cleanup on exit
     cleanup_terminal();
@@ -214,7 +779,7 @@ async fn run_app(args: Args) -> Result<()> {
 async fn main() {
     let args = Args::parse();
     
-    setup_logging(args.verbose);
+    setup_logging(args.verbose, args.quiet || args.count, args.count, args.log_format);
     
     // Setup panic handler for emergency cleanup
     std::panic::set_hook(Box::new(|panic_info| {
@@ -224,20 +789,162 @@ async fn main() {
         utils::terminal::emergency_terminal_reset();
     }));
     
-    print_banner();
+    maybe_print_banner(&args, print_banner);
 
-    if let Err(e) = run_app(args).await {
-        error!("Application error: {}", e);
-        
-        // Print error chain
-        let mut source = e.source();
-        while let Some(err) = source {
-            error!("  Caused by: {}", err);
-            source = err.source();
+    let exit_code = match run_app(args).await {
+        Ok(()) => ExitCode::Success,
+        Err(e) => {
+            error!("Application error: {}", e);
+
+            // Print error chain
+            let mut source = e.source();
+            while let Some(err) = source {
+                error!("  Caused by: {}", err);
+                source = err.source();
+            }
+
+            // Ensure terminal is cleaned up even on error
+            cleanup_terminal();
+            ExitCode::from_error(&e)
         }
-        
-        // Ensure terminal is cleaned up even on error
-        cleanup_terminal();
-        process::exit(1);
+    };
+
+    if exit_code != ExitCode::Success {
+        process::exit(exit_code.code());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn banner_invoked(argv: &[&str]) -> bool {
+        let args = Args::try_parse_from(argv).unwrap();
+        let mut invoked = false;
+        maybe_print_banner(&args, || invoked = true);
+        invoked
+    }
+
+    #[test]
+    fn test_banner_flags() {
+        assert!(banner_invoked(&["riptv"]));
+        assert!(!banner_invoked(&["riptv", "--no-banner"]));
+        assert!(!banner_invoked(&["riptv", "--quiet"]));
+    }
+
+    #[test]
+    fn test_first_requires_search() {
+        assert!(Args::try_parse_from(["riptv", "--first"]).is_err());
+        assert!(Args::try_parse_from(["riptv", "--search", "bbc", "--first"]).unwrap().first);
+    }
+
+    #[test]
+    fn test_sleep_minutes_bounded() {
+        assert_eq!(Args::try_parse_from(["riptv", "--sleep", "90"]).unwrap().sleep, Some(90));
+        assert!(Args::try_parse_from(["riptv", "--sleep", "0"]).is_err());
+        assert!(Args::try_parse_from(["riptv", "--sleep", "18446744073709551615"]).is_err());
+    }
+
+    #[test]
+    fn test_clean_needs_a_target() {
+        assert!(Args::try_parse_from(["riptv", "clean"]).is_err());
+        let args = Args::try_parse_from(["riptv", "clean", "--cache", "--config", "-y"]).unwrap();
+        assert!(matches!(args.command, Some(Command::Clean { cache: true, config: true, all: false, yes: true })));
+        assert!(matches!(Args::try_parse_from(["riptv", "clean", "--all"]).unwrap().command, Some(Command::Clean { all: true, .. })));
+    }
+
+    #[test]
+    fn test_on_empty_actions() {
+        assert_eq!(OnEmpty::from_name(&Config::default().on_empty), Some(OnEmpty::Error));
+        assert_eq!(OnEmpty::from_name("welcome"), Some(OnEmpty::Welcome));
+        assert_eq!(OnEmpty::from_name("List"), Some(OnEmpty::List));
+        assert_eq!(OnEmpty::from_name("error"), Some(OnEmpty::Error));
+        assert_eq!(OnEmpty::from_name("menu"), None);
+
+        let config = Config { on_empty: "menu".to_string(), ..Config::default() };
+        assert!(config.validate().is_err());
+
+        let err = resolve_empty_playlist(&Config::default()).unwrap_err();
+        assert_eq!(ExitCode::from_error(&err), ExitCode::PlaylistLoad);
+    }
+
+    #[test]
+    fn test_log_format_flag() {
+        assert_eq!(Args::try_parse_from(["riptv"]).unwrap().log_format, LogFormat::Plain);
+        assert_eq!(Args::try_parse_from(["riptv", "--log-format", "json"]).unwrap().log_format, LogFormat::Json);
+        assert!(Args::try_parse_from(["riptv", "--log-format", "xml"]).is_err());
+    }
+
+    #[test]
+    fn test_no_reverse_overrides_config() {
+        let mut config = Config::default();
+        apply_cli_overrides(&Args::try_parse_from(["riptv"]).unwrap(), &mut config);
+        assert!(config.ui.reverse_list);
+
+        apply_cli_overrides(&Args::try_parse_from(["riptv", "--no-reverse"]).unwrap(), &mut config);
+        assert!(!config.ui.reverse_list);
+    }
+
+    #[test]
+    fn test_export_report_requires_check() {
+        assert!(Args::try_parse_from(["riptv", "--export-report", "report.csv"]).is_err());
+        assert!(Args::try_parse_from(["riptv", "--check", "--export-report", "report.csv"]).is_ok());
+    }
+
+    #[test]
+    fn test_player_env_flag() {
+        let args = Args::try_parse_from(["riptv", "--player-env", "DISPLAY=:1", "--player-env", "HTTP_PROXY=http://proxy:3128"]).unwrap();
+        let mut config = Config::default();
+        config.player.env.insert("DISPLAY".to_string(), ":0".to_string());
+        apply_cli_overrides(&args, &mut config);
+        assert_eq!(config.player.env["DISPLAY"], ":1");
+        assert_eq!(config.player.env["HTTP_PROXY"], "http://proxy:3128");
+
+        assert!(Args::try_parse_from(["riptv", "--player-env", "DISPLAY"]).is_err());
+        assert!(Args::try_parse_from(["riptv", "--player-env", "=1"]).is_err());
+    }
+
+    #[test]
+    fn test_timeout_flag() {
+        let mut config = Config::default();
+        apply_cli_overrides(&Args::try_parse_from(["riptv", "--timeout", "90"]).unwrap(), &mut config);
+        assert_eq!(config.network.timeout, 90);
+
+        assert!(Args::try_parse_from(["riptv", "--timeout", "0"]).is_err());
+    }
+
+    #[test]
+    fn test_random_flags() {
+        assert!(Args::try_parse_from(["riptv", "--smart-random"]).unwrap().smart_random);
+        assert!(Args::try_parse_from(["riptv", "--random", "--smart-random"]).is_err());
+        assert!(Args::try_parse_from(["riptv", "--search", "bbc", "--random"]).is_err());
+    }
+
+    #[test]
+    fn test_strict_url_flag() {
+        let mut config = Config::default();
+        apply_cli_overrides(&Args::try_parse_from(["riptv"]).unwrap(), &mut config);
+        assert!(!config.strict_url);
+        apply_cli_overrides(&Args::try_parse_from(["riptv", "--strict-url"]).unwrap(), &mut config);
+        assert!(config.strict_url);
+    }
+
+    #[test]
+    fn test_record_schedule_args() {
+        let args = Args::try_parse_from(["riptv", "--epg", "guide.xml", "record-schedule", "BBC One", "Match of the Day"]).unwrap();
+        match args.command {
+            Some(Command::RecordSchedule { channel, title, padding, dir }) => {
+                assert_eq!((channel.as_str(), title.as_str()), ("BBC One", "Match of the Day"));
+                assert_eq!(padding, recording::DEFAULT_PADDING_MINUTES);
+                assert_eq!(dir, None);
+            }
+            _ => panic!("expected record-schedule"),
+        }
+    }
+
+    #[test]
+    fn test_export_epg_channels_requires_epg() {
+        assert!(Args::try_parse_from(["riptv", "--export-epg-channels"]).is_err());
+        assert!(Args::try_parse_from(["riptv", "--epg", "guide.xml", "--export-epg-channels"]).is_ok());
     }
 }
@@ -1,15 +1,23 @@
 use anyhow::{Context, Result};
 use colored::*;
-use std::process::{Child, Command, Stdio};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::future::Future;
+use std::path::{Path, PathBuf};
+use std::process::{Child, Command, ExitStatus, Stdio};
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
-use std::time::Instant;
+use std::time::{Duration, Instant};
 use tracing::{debug, error, info, warn};
 
 use crate::config::Config;
-use crate::playlist::{Channel, PlaylistParser};
-use crate::ui::ChannelSelector;
-use crate::utils::format_duration;
+use crate::download;
+use crate::error_log::ErrorLog;
+use crate::exit_code::ExitCode;
+use crate::history::PlayStats;
+use crate::playlist::{export_channels, Channel, ParseOptions, PlaylistParser, SearchField, StatsSort};
+use crate::search;
+use crate::ui::{self, ChannelSelector, Selection, TagEdit};
+use crate::utils::{expand_tilde_with, format_duration, is_command_available, nearest, pick_weighted, retry_async, separator, separator_width, Rng};
 
 pub struct IptvPlayer {
     parser: PlaylistParser,
@@ -18,25 +26,208 @@ pub struct IptvPlayer {
     history: Vec<String>,
     favorites: Vec<String>,
     last_played: Option<Instant>,
-    current_player_process: Option<Child>,
+    media_player: Box<dyn MediaPlayer>,
+    current_player_process: Option<PlayHandle>,
+    sleep_timer: Option<Duration>,
+    capabilities: Option<PlayerCapabilities>,
+    play_stats: PlayStats,
+    group_filter: Option<String>,
+    tag_filter: Option<String>,
+    detach: bool,
+    /// Path or URL the playlist was loaded from, for reloading
+    source: Option<String>,
+    errors: ErrorLog,
 }
 
+/// Outcome of racing a future against the sleep timer
+#[derive(Debug, PartialEq)]
+pub enum SleepRace<T> {
+    Finished(T),
+    TimerElapsed,
+}
+
+/// How long shutdown waits for config and play counts to be written
+const SHUTDOWN_FLUSH_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Groups or tags suggested when a filter matches nothing
+const EMPTY_HINT_SUGGESTIONS: usize = 3;
+
+/// How many times likelier `--smart-random` is to pick a favorite than an unplayed channel
+const FAVORITE_WEIGHT: u64 = 5;
+
+/// How many times likelier `--smart-random` is to pick a recently played channel than an unplayed one
+const RECENT_WEIGHT: u64 = 3;
+
 impl IptvPlayer {
     pub fn new(player_cmd: String, config: Config, parallel: bool) -> Self {
+        let mut parser = PlaylistParser::with_options(parallel, ParseOptions::from_config(&config));
+        parser.set_search_fields(
+            config.search_fields.iter().filter_map(|field| SearchField::from_name(field)).collect(),
+        );
+        parser.set_case_mode(config.search.case_mode());
+        parser.set_favorites(config.favorite_channels.iter().cloned(), config.search.favorite_boost);
+        parser.set_min_score(config.search.min_score);
+        parser.set_anchored(config.search.anchored);
+        parser.set_natural_sort(config.natural_sort);
+        parser.set_search_backend(search::backend(config.fuzzy_search));
+
+        let play_stats = PlayStats::for_config(&config).unwrap_or_else(|e| {
+            warn!("⚠️ Failed to load play stats: {}", e);
+            PlayStats::default()
+        });
+
+        let media_player = Box::new(ProcessPlayer { program: player_cmd.clone() });
         Self {
-            parser: PlaylistParser::new(parallel),
+            parser,
             player_cmd,
             config,
             history: Vec::new(),
             favorites: Vec::new(),
             last_played: None,
+            media_player,
             current_player_process: None,
+            sleep_timer: None,
+            capabilities: None,
+            play_stats,
+            group_filter: None,
+            tag_filter: None,
+            detach: false,
+            source: None,
+            errors: ErrorLog::default(),
+        }
+    }
+
+    /// Launch channels through `media_player` instead of spawning `player_cmd`
+    #[cfg(test)]
+    pub fn set_media_player(&mut self, media_player: Box<dyn MediaPlayer>) {
+        self.media_player = media_player;
+    }
+
+    /// List each variant of an HLS master playlist as its own channel
+    pub fn set_expand_hls(&mut self, expand: bool) {
+        self.parser.set_expand_hls(expand);
+    }
+
+    /// Refuse to load a playlist whose SHA-256 doesn't match `digest`
+    pub fn set_expected_sha256(&mut self, digest: Option<String>) {
+        self.parser.set_expected_sha256(digest);
+    }
+
+    /// Leave the player running in its own session instead of waiting for it
+    pub fn set_detach(&mut self, detach: bool) {
+        self.detach = detach;
+    }
+
+    /// Limit listing, search and selection to channels in `group`
+    pub fn set_group_filter(&mut self, group: Option<String>) {
+        self.group_filter = group;
+    }
+
+    /// Limit listing, search and selection to channels tagged `tag`
+    pub fn set_tag_filter(&mut self, tag: Option<String>) {
+        self.tag_filter = tag;
+    }
+
+    fn in_group_filter(&self, channel: &Channel) -> bool {
+        let in_group = match (&self.group_filter, &channel.group) {
+            (None, _) => true,
+            (Some(wanted), Some(group)) => wanted.eq_ignore_ascii_case(group),
+            (Some(_), None) => false,
+        };
+        in_group && self.tag_filter.as_ref().is_none_or(|tag| self.config.has_tag(&channel.url, tag))
+    }
+
+    /// Why the group, tag and search filters left nothing, with the closest groups or tags to try
+    pub fn empty_result_hint(&self, query: Option<&str>) -> String {
+        let groups: Vec<String> = self.parser.group_summary().into_iter().map(|(group, _)| group).collect();
+        let mut tags: Vec<String> = Vec::new();
+        for tag in self.config.channel_tags.values().flatten() {
+            if !tags.iter().any(|known| known.eq_ignore_ascii_case(tag)) {
+                tags.push(tag.clone());
+            }
+        }
+
+        let suggest = |kind: &str, flag: &str, wanted: &str, known: &[String]| {
+            let closest = nearest(wanted, known, EMPTY_HINT_SUGGESTIONS);
+            if closest.is_empty() {
+                format!("No {} named '{}', and the playlist has none to {} by", kind, wanted, flag)
+            } else {
+                format!("No {} named '{}'. Closest {}s: {}", kind, wanted, kind, closest.join(", "))
+            }
+        };
+
+        let unknown = |wanted: &Option<String>, known: &[String]| {
+            wanted.clone().filter(|wanted| !known.iter().any(|known| known.eq_ignore_ascii_case(wanted)))
+        };
+        if let Some(group) = unknown(&self.group_filter, &groups) {
+            return suggest("group", "--group", &group, &groups);
+        }
+        if let Some(tag) = unknown(&self.tag_filter, &tags) {
+            return suggest("tag", "--tag", &tag, &tags);
+        }
+
+        let filters: Vec<String> = [
+            self.group_filter.as_ref().map(|group| format!("in group '{}'", group)),
+            self.tag_filter.as_ref().map(|tag| format!("tagged '{}'", tag)),
+        ]
+        .into_iter()
+        .flatten()
+        .collect();
+
+        match (query, filters.is_empty()) {
+            (Some(query), true) => format!("No channels match '{}'; try fewer or different words", query),
+            (Some(query), false) => format!("No channels {} match '{}'; try without the filter", filters.join(" and "), query),
+            (None, false) => format!("No channels are {}", filters.join(" and ")),
+            (None, true) => "The playlist has no channels".to_string(),
+        }
+    }
+
+    /// Channels matching `query` within the group and tag filters, best match first
+    fn matching_channels(&self, query: &str) -> Vec<&Channel> {
+        self.parser.search_channels(query)
+            .into_iter()
+            .filter(|channel| self.in_group_filter(channel))
+            .collect()
+    }
+
+    /// Number of channels in the group and tag filters, optionally narrowed by a search
+    pub fn count_channels(&self, query: Option<&str>) -> usize {
+        match query {
+            Some(query) => self.matching_channels(query).len(),
+            None => self.parser.get_channels().iter().filter(|channel| self.in_group_filter(channel)).count(),
         }
     }
 
+    /// Stop playback automatically once `duration` has elapsed after it starts
+    pub fn set_sleep_timer(&mut self, duration: Option<Duration>) {
+        self.sleep_timer = duration;
+    }
+
+    #[cfg(test)]
     pub async fn load_playlist(&mut self, path: &str) -> Result<()> {
-        self.parser.parse_file(path).await
-            .with_context(|| format!("Failed to load playlist: {}", path))?;
+        self.load_playlist_with_shutdown(path, &AtomicBool::new(true)).await
+    }
+
+    /// Load a local or `http://` playlist; downloads stop once `running` is cleared
+    pub async fn load_playlist_with_shutdown(&mut self, path: &str, running: &AtomicBool) -> Result<()> {
+        let loaded = if download::is_remote(path) {
+            match download::download_playlist(path, &self.config.network, running).await {
+                Ok(download) => {
+                    self.parser.set_base_url(Some(download.url));
+                    self.parser.parse_bytes(download.bytes, Path::new(path)).await
+                }
+                Err(e) => Err(e),
+            }
+        } else {
+            self.parser.set_base_url(None);
+            self.parser.parse_file(path).await
+        }
+        .with_context(|| format!("Failed to load playlist: {}", path));
+        if let Err(e) = &loaded {
+            self.errors.push(None, e);
+        }
+        loaded?;
+        self.source = Some(path.to_string());
 
         let channels = self.parser.get_channels();
         if channels.is_empty() {
@@ -45,77 +236,126 @@ impl IptvPlayer {
             info!("✅ Successfully loaded {} channels", channels.len().to_string().bright_green().bold());
         }
 
+        if let (Some(group), None) = (&self.config.ui.default_group, self.default_group()) {
+            warn!("⚠️ default_group '{}' is not in the playlist, showing all channels", group);
+        }
+
         Ok(())
     }
 
-    pub async fn list_playlists(&self) -> Result<()> {
+    /// `config.ui.default_group` as the playlist spells it, if the playlist has it
+    fn default_group(&self) -> Option<String> {
+        let group = self.config.ui.default_group.as_deref()?;
+        self.parser.find_group(group).map(str::to_string)
+    }
+
+    /// Selector for interactive mode, opened scoped to the default group
+    fn interactive_selector(&self, channels: Vec<Channel>) -> ChannelSelector {
+        let mut selector = ChannelSelector::new(channels, &self.config);
+        selector.set_scope(self.default_group());
+        selector
+    }
+
+    /// Playlist files in the default locations and `config.playlist_dirs`
+    pub fn find_playlists(&self) -> Vec<PathBuf> {
+        self.find_playlists_with_home(dirs::home_dir().as_deref())
+    }
+
+    /// Like `find_playlists`, expanding `~` to `home`
+    fn find_playlists_with_home(&self, home: Option<&Path>) -> Vec<PathBuf> {
+        discover_playlists(&self.config, home)
+    }
+
+    pub async fn list_playlists(&self, json: bool) -> Result<()> {
+        let playlists = self.find_playlists();
+
+        if json {
+            println!("{}", serde_json::to_string_pretty(&playlists)?);
+            return Ok(());
+        }
+
         println!("{}", "📋 Available Playlists:".bright_cyan().bold());
-        
-        let common_paths = [".", "~/Downloads", "~/Documents", "/tmp"];
-
-        for path in &common_paths {
-            if let Ok(entries) = std::fs::read_dir(path) {
-                for entry in entries.flatten() {
-                    if let Some(ext) = entry.path().extension() {
-                        if ext == "m3u" || ext == "m3u8" {
-                            println!("  📺 {}", entry.path().display().to_string().bright_white());
-                        }
-                    }
-                }
-            }
+        for path in &playlists {
+            println!("  📺 {}", path.display().to_string().bright_white());
+        }
+
+        Ok(())
+    }
+
+    /// Print every group with its channel count, as text or JSON
+    pub fn show_groups(&self, json: bool) -> Result<()> {
+        let summary = self.parser.group_summary();
+
+        if json {
+            let groups: Vec<_> = summary
+                .iter()
+                .map(|(group, count)| serde_json::json!({ "group": group, "channels": count }))
+                .collect();
+            println!("{}", serde_json::to_string_pretty(&groups)?);
+            return Ok(());
+        }
+
+        println!("{}", format!("📁 {} Groups:", summary.len()).bright_cyan().bold());
+        println!("{}", separator('─', separator_width(self.config.ui.separator_width, 60)).bright_blue());
+        for (group, count) in &summary {
+            println!("  📺 {} ({} channels)", group.bright_white(), count.to_string().bright_green());
         }
 
         Ok(())
     }
 
-    pub fn show_statistics(&self) {
+    pub fn show_statistics(&self, json: bool, sort: StatsSort) -> Result<()> {
         let stats = self.parser.get_statistics();
+
+        if json {
+            println!("{}", serde_json::to_string_pretty(&stats)?);
+            return Ok(());
+        }
         
         println!("{}", "📊 Playlist Statistics".bright_cyan().bold());
-        println!("{}", "═".repeat(50).bright_blue());
+        println!("{}", separator('═', separator_width(self.config.ui.separator_width, 50)).bright_blue());
         
         println!("🎯 Total Channels: {}", stats.total_channels.to_string().bright_green().bold());
         println!("📁 Total Groups: {}", stats.total_groups.to_string().bright_yellow().bold());
         
+        let top = if sort == StatsSort::Count { "Top " } else { "" };
+
         if !stats.channels_per_group.is_empty() {
-            println!("\n{}", "📋 Top Groups:".bright_magenta());
-            let mut groups: Vec<_> = stats.channels_per_group.iter().collect();
-            groups.sort_by(|a, b| b.1.cmp(a.1));
-            for (group, count) in groups.iter().take(10) {
+            println!("\n{}", format!("📋 {}Groups:", top).bright_magenta());
+            for (group, count) in sort.apply(&stats.channels_per_group, self.config.natural_sort) {
                 println!("  📺 {} ({} channels)", group.bright_white(), count.to_string().bright_green());
             }
         }
 
         if !stats.countries.is_empty() {
             println!("\n{}", "🌍 Countries:".bright_blue());
-            let mut countries: Vec<_> = stats.countries.iter().collect();
-            countries.sort_by(|a, b| b.1.cmp(a.1));
-            for (country, count) in countries.iter().take(10) {
+            for (country, count) in sort.apply(&stats.countries, self.config.natural_sort) {
                 println!("  🏳️ {} ({} channels)", country.bright_white(), count.to_string().bright_green());
             }
         }
 
         if !stats.languages.is_empty() {
             println!("\n{}", "🗣️ Languages:".bright_cyan());
-            let mut languages: Vec<_> = stats.languages.iter().collect();
-            languages.sort_by(|a, b| b.1.cmp(a.1));
-            for (language, count) in languages.iter().take(10) {
+            for (language, count) in sort.apply(&stats.languages, self.config.natural_sort) {
                 println!("  🔤 {} ({} channels)", language.bright_white(), count.to_string().bright_green());
             }
         }
+
+        Ok(())
     }
 
     pub async fn search_channels(&self, query: &str) -> Result<()> {
         info!("🔍 Searching for: '{}'", query.bright_yellow());
-        let results = self.parser.search_channels(query);
+        let results = self.matching_channels(query);
         
         if results.is_empty() {
             println!("{}", "❌ No channels found matching your search.".bright_red());
+            println!("{}", format!("💡 {}", self.empty_result_hint(Some(query))).bright_yellow());
             return Ok(());
         }
 
         println!("{}", format!("🎯 Found {} matching channels:", results.len()).bright_green().bold());
-        println!("{}", "─".repeat(60).bright_blue());
+        println!("{}", separator('─', separator_width(self.config.ui.separator_width, 60)).bright_blue());
 
         for (i, channel) in results.iter().enumerate().take(20) {
             let index = format!("{:2}", i + 1).bright_blue();
@@ -130,51 +370,155 @@ impl IptvPlayer {
         Ok(())
     }
 
-    pub async fn run_interactive(&mut self) -> Result<()> {
-        let channels = self.parser.get_channels().to_vec();
-        if channels.is_empty() {
-            error!("No channels available for playback");
+    /// Mark channels in the selector and write them to `path` as M3U
+    pub async fn select_and_export(&self, path: &str) -> Result<()> {
+        let mut selector = ChannelSelector::new(self.ordered_channels(), &self.config);
+        let selected = selector.select_many().await?;
+
+        if selected.is_empty() {
+            println!("{}", "No channels marked, nothing exported.".bright_yellow());
             return Ok(());
         }
 
-        info!("🚀 Starting interactive mode with {} channels", channels.len());
-        let mut selector = ChannelSelector::new(channels, &self.config);
+        export_channels(&selected, path)?;
+        println!("{}", format!("💾 Exported {} channels to {}", selected.len(), path).bright_green().bold());
+        Ok(())
+    }
 
-        loop {
-            match selector.select_channel().await? {
-                Some(channel) => {
-                    self.add_to_history(&channel.name);
+    /// Write the matches for `query` to `path` in rank order, returning how many were written
+    pub fn export_matches(&self, query: &str, path: &str) -> Result<usize> {
+        let matches: Vec<Channel> = self.matching_channels(query).into_iter().cloned().collect();
+        export_channels(&matches, path)?;
+        Ok(matches.len())
+    }
 
-                    if let Err(e) = self.play_channel(&channel).await {
-                        error!("Failed to play channel '{}': {}", channel.name, e);
-                        println!("{}", format!("❌ Error playing channel: {}", e).bright_red());
-                        println!("{}", "Press any key to continue...".bright_yellow());
-                        let mut input = String::new();
-                        std::io::stdin().read_line(&mut input).ok();
-                    }
+    /// Play the highest-scoring match for `query`, failing if nothing matches
+    pub async fn play_first_match(&mut self, query: &str) -> Result<()> {
+        let channel = self.parser.top_match(query, |channel| self.in_group_filter(channel))
+            .cloned()
+            .ok_or_else(|| anyhow::Error::new(ExitCode::NoChannels).context(format!("No channels found matching '{}'", query)))?;
 
-                    println!("{}", "🔄 Returning to channel selection...".bright_cyan());
-                }
-                None => {
-                    println!("{}", "👋 Thanks for using RIPTV!".bright_magenta().bold());
-                    break;
-                }
+        self.add_to_history(&channel.name);
+        self.play_channel(&channel).await?;
+        self.cleanup().await
+    }
+
+    /// A random channel from the group and tag filters
+    ///
+    /// With `smart`, favorites and recently played channels are drawn more often than the rest,
+    /// which stay equally likely.
+    pub fn random_channel(&self, smart: bool, rng: &mut Rng) -> Option<Channel> {
+        let channels: Vec<&Channel> = self.parser.get_channels().iter().filter(|channel| self.in_group_filter(channel)).collect();
+        let weights: Vec<u64> = channels
+            .iter()
+            .map(|channel| match smart {
+                true if self.config.is_favorite(&channel.name) => FAVORITE_WEIGHT,
+                true if self.config.recent_channels.contains(&channel.name) => RECENT_WEIGHT,
+                _ => 1,
+            })
+            .collect();
+        pick_weighted(&weights, rng).map(|idx| channels[idx].clone())
+    }
+
+    /// Play a random channel without the selector, weighted toward favorites and recents with `smart`
+    pub async fn play_random(&mut self, smart: bool) -> Result<()> {
+        let channel = self.random_channel(smart, &mut Rng::from_entropy())
+            .ok_or_else(|| anyhow::Error::new(ExitCode::NoChannels).context("No channels to pick from"))?;
+        info!("🎲 Playing random channel: {}", channel.name);
+
+        self.add_to_history(&channel.name);
+        self.play_channel(&channel).await?;
+        self.cleanup().await
+    }
+
+    /// The channel at 1-based `index` in parsed order
+    pub fn channel_at(&self, index: usize) -> Result<Channel> {
+        let channels = self.parser.get_channels();
+        index
+            .checked_sub(1)
+            .and_then(|position| channels.get(position))
+            .cloned()
+            .with_context(|| format!("No channel at index {}, the playlist has {} channels (1-{})", index, channels.len(), channels.len()))
+    }
+
+    /// Play the channel at 1-based `index` without the selector
+    pub async fn play_at(&mut self, index: usize) -> Result<()> {
+        let channel = self.channel_at(index)?;
+        info!("▶️ Playing channel {}: {}", index, channel.name);
+
+        self.add_to_history(&channel.name);
+        self.play_channel(&channel).await?;
+        self.cleanup().await
+    }
+
+    /// Channels in the initial order selected by `config.ui.sort`
+    fn ordered_channels(&self) -> Vec<Channel> {
+        let channels: Vec<Channel> = self.parser.get_channels()
+            .iter()
+            .filter(|channel| self.in_group_filter(channel))
+            .cloned()
+            .collect();
+
+        match self.config.ui.sort.as_str() {
+            "recency" => self.rank_by_history(&channels),
+            "alpha" => {
+                let mut sorted = channels;
+                sorted.sort_by_cached_key(|channel| channel.name.to_lowercase());
+                sorted
             }
+            _ => channels,
         }
+    }
 
-        Ok(())
+    /// Order channels so recently played ones come first, most recent at the top
+    ///
+    /// Channels without history keep their playlist order after the played ones.
+    pub fn rank_by_history(&self, channels: &[Channel]) -> Vec<Channel> {
+        let recency: HashMap<&str, usize> = self.config.recent_channels
+            .iter()
+            .enumerate()
+            .map(|(rank, name)| (name.as_str(), rank))
+            .collect();
+
+        let mut ranked = channels.to_vec();
+        ranked.sort_by_key(|channel| recency.get(channel.name.as_str()).copied().unwrap_or(usize::MAX));
+        ranked
+    }
+
+    pub async fn run_interactive(&mut self) -> Result<()> {
+        self.run_interactive_with_shutdown(Arc::new(AtomicBool::new(true))).await
     }
 
     pub async fn run_interactive_with_shutdown(&mut self, running: Arc<AtomicBool>) -> Result<()> {
-        let channels = self.parser.get_channels().to_vec();
+        let channels = self.ordered_channels();
         if channels.is_empty() {
             error!("No channels available for playback");
+            println!("{}", format!("💡 {}", self.empty_result_hint(None)).bright_yellow());
             return Ok(());
         }
 
         info!("🚀 Starting interactive mode with {} channels", channels.len());
-        let mut selector = ChannelSelector::new(channels, &self.config);
+        let mut selector = self.interactive_selector(channels);
+        selector.set_shutdown_flag(running.clone());
+
+        // Playback and prompts can block the loop, so a signal wakes it here rather than waiting for them
+        tokio::select! {
+            result = self.selection_loop(&mut selector, &running) => result?,
+            _ = ui::shutdown_requested(Some(running.clone())) => {
+                debug!("Shutdown requested, exiting interactive mode");
+            }
+        }
+
+        if !running.load(Ordering::Relaxed) && let Err(e) = self.flush_state(SHUTDOWN_FLUSH_TIMEOUT).await {
+            warn!("⚠️ Failed to save state on shutdown: {:#}", e);
+        }
+
+        self.cleanup().await?;
+        Ok(())
+    }
 
+    /// Pick and play channels until the user quits or playback ends the session
+    async fn selection_loop(&mut self, selector: &mut ChannelSelector, running: &AtomicBool) -> Result<()> {
         loop {
             if !running.load(Ordering::Relaxed) {
                 debug!("Shutdown requested, exiting interactive mode");
@@ -182,29 +526,129 @@ impl IptvPlayer {
             }
 
             match selector.select_channel().await? {
-                Some(channel) => {
+                Selection::Play(channel) => {
                     self.add_to_history(&channel.name);
-                    if let Err(e) = self.play_channel(&channel).await {
-                        error!("Failed to play channel '{}': {}", channel.name, e);
-                        println!("{}", format!("❌ Error playing channel: {}", e).bright_red());
-                        println!("{}", "Press any key to continue...".bright_yellow());
-                        let mut input = String::new();
-                        std::io::stdin().read_line(&mut input).ok();
+                    match self.play_channel(&channel).await {
+                        Ok(SleepRace::TimerElapsed) => break,
+                        Ok(SleepRace::Finished(())) if self.detach => break,
+                        Ok(SleepRace::Finished(())) => {}
+                        Err(e) => {
+                            error!("Failed to play channel '{}': {}", channel.name, e);
+                            self.errors.push(Some(&channel.name), &e);
+                            println!("{}", format!("❌ Error playing channel: {}", e).bright_red());
+                            println!("{}", "Press Enter to continue...".bright_yellow());
+                            ui::wait_for_enter().await;
+                        }
                     }
 
                     println!("{}", "🔄 Returning to channel selection...".bright_cyan());
                 }
-                None => {
+                Selection::Refresh => self.refresh_selector(selector, running).await,
+                Selection::Rename { url, name } => self.rename_channel(selector, &url, &name),
+                Selection::EditTags { url, edits } => self.edit_tags(selector, &url, &edits),
+                Selection::ShowErrors => self.show_recent_errors().await,
+                Selection::Quit => {
                     println!("{}", "👋 Thanks for using RIPTV!".bright_magenta().bold());
                     break;
                 }
             }
         }
 
-        self.cleanup().await?;
         Ok(())
     }
 
+    /// Write the config, with favorites, and play counts, giving up after `timeout` so shutdown stays prompt
+    pub async fn flush_state(&self, timeout: Duration) -> Result<()> {
+        let mut config = self.config.clone();
+        for name in &self.favorites {
+            config.add_favorite_channel(name.clone());
+        }
+        let play_stats = self.play_stats.clone();
+
+        let flush = tokio::task::spawn_blocking(move || -> Result<()> {
+            config.persist()?;
+            play_stats.save()
+        });
+        tokio::time::timeout(timeout, flush)
+            .await
+            .context("Timed out saving state")?
+            .context("State flush task failed")??;
+
+        debug!("State flushed");
+        Ok(())
+    }
+
+    /// Reload the playlist from where it was loaded, keeping the current channels if that fails
+    ///
+    /// A local file that has only been appended to since is parsed from where the last parse stopped.
+    pub async fn reload_playlist(&mut self, running: &AtomicBool) -> Result<()> {
+        let source = self.source.clone().context("No playlist has been loaded yet")?;
+        if !download::is_remote(&source) && self.parser.parse_appended(&source)?.is_some() {
+            return Ok(());
+        }
+
+        info!("🔄 Reloading playlist: {}", source);
+        self.load_playlist_with_shutdown(&source, running).await
+    }
+
+    /// Handle the selector's refresh key
+    async fn refresh_selector(&mut self, selector: &mut ChannelSelector, running: &AtomicBool) {
+        match self.reload_playlist(running).await {
+            Ok(()) => {
+                selector.replace_channels(self.ordered_channels());
+                println!("{}", format!("🔄 Reloaded {} channels", self.parser.get_channels().len()).bright_cyan());
+            }
+            Err(e) => {
+                error!("Failed to reload playlist: {:#}", e);
+                println!("{}", format!("❌ Reload failed, keeping the current list: {:#}", e).bright_red());
+            }
+        }
+    }
+
+    /// Handle the selector's errors key
+    async fn show_recent_errors(&self) {
+        use std::io::Write;
+
+        self.errors.show(separator_width(self.config.ui.separator_width, 50));
+        print!("{}", "Press Enter to return to the list".bright_cyan());
+        std::io::stdout().flush().ok();
+        ui::wait_for_enter().await;
+    }
+
+    /// Playback and playlist errors from this session
+    pub fn recent_errors(&self) -> &ErrorLog {
+        &self.errors
+    }
+
+    /// Save `name` as the display name for the channel at `url` and show it in the selector
+    fn rename_channel(&mut self, selector: &mut ChannelSelector, url: &str, name: &str) {
+        self.parser.rename_channels(url, name);
+        self.config.name_overrides.insert(url.to_string(), name.to_string());
+        if let Err(e) = self.config.persist() {
+            warn!("⚠️ Failed to save channel name: {}", e);
+        }
+        selector.replace_channels(self.ordered_channels());
+        println!("{}", format!("✏️ Renamed to {}", name).bright_cyan());
+    }
+
+    fn edit_tags(&mut self, selector: &mut ChannelSelector, url: &str, edits: &[TagEdit]) {
+        for edit in edits {
+            match edit {
+                TagEdit::Add(tag) => self.config.add_tag(url, tag),
+                TagEdit::Remove(tag) => self.config.remove_tag(url, tag),
+            };
+        }
+        if let Err(e) = self.config.persist() {
+            warn!("⚠️ Failed to save channel tags: {}", e);
+        }
+        selector.set_channel_tags(self.config.channel_tags.clone());
+        selector.replace_channels(self.ordered_channels());
+
+        let tags = self.config.tags_for(url);
+        let shown = if tags.is_empty() { "none".to_string() } else { tags.join(", ") };
+        println!("{}", format!("🏷️ Tags: {}", shown).bright_cyan());
+    }
+
     pub async fn cleanup(&mut self) -> Result<()> {
         debug!("Performing player cleanup");
 
@@ -213,78 +657,94 @@ impl IptvPlayer {
             let _ = child.kill();
             let _ = child.wait();
         }
+        self.set_now_playing(None);
 
         debug!("Player cleanup completed");
         Ok(())
     }
 
-    async fn play_channel(&mut self, channel: &Channel) -> Result<()> {
+    async fn play_channel(&mut self, channel: &Channel) -> Result<SleepRace<()>> {
         info!("🎬 Playing: {}", channel.name.bright_green().bold());
 
         if let Some(group) = &channel.group {
             info!("📁 Group: {}", group.bright_blue());
         }
 
-        self.validate_player()?;
-        let start_time = Instant::now();
-        self.last_played = Some(start_time);
+        if let Some(hook) = &self.config.hooks.pre_play {
+            run_hook("pre_play", hook, channel).await;
+        }
 
-        let mut cmd = Command::new(&self.player_cmd);
-        cmd.arg(&channel.url);
+        let session_start = Instant::now();
+        self.last_played = Some(session_start);
+        let mut failures = 0;
 
-        // Optimized player arguments
-        cmd.args(&[
-            "--cache=yes",
-            "--demuxer-max-bytes=100M",
-            "--demuxer-readahead-secs=30",
-            "--force-window=immediate",
-            "--no-terminal",
-            "--quiet",
-            "--really-quiet",
-            "--hwdec=auto-safe",
-            "--vo=gpu",
-            "--gpu-context=auto",
-            "--profile=fast",
-            "--network-timeout=10",
-            "--stream-buffer-size=1024k",
-            "--demuxer-thread=yes",
-        ]);
+        loop {
+            let start_time = Instant::now();
+            self.start_player_with_retry(channel).await?;
 
-        if let Some(extra_args) = &self.config.player_args {
-            for arg in extra_args {
-                cmd.arg(arg);
+            if failures == 0 {
+                self.record_play(channel);
+
+                if self.detach {
+                    println!("{}", "🚀 Player started in the background".bright_cyan());
+                } else {
+                    println!("{}", "🎥 Player started. Controls:".bright_cyan());
+                    println!("   {} Quit player", "q".bright_white().bold());
+                    println!("   {} Toggle fullscreen", "f".bright_white().bold());
+                    println!("   {} Volume up/down", "9/0".bright_white().bold());
+                    println!("   {} Seek backward/forward", "←/→".bright_white().bold());
+
+                    if let Some(timer) = self.sleep_timer {
+                        println!("   {} Playback stops in {}", "💤".bright_white(), format_duration(timer).bright_yellow());
+                    }
+                }
             }
-        }
 
-        #[cfg(unix)]
-        {
-            cmd.stdout(Stdio::null());
-            cmd.stderr(Stdio::null());
-        }
+            // Wait for process to finish, or for the sleep timer to stop it
+            let Some(mut process) = self.current_player_process.take() else {
+                break;
+            };
 
-        #[cfg(windows)]
-        {
-            use std::os::windows::process::CommandExt;
-            cmd.creation_flags(0x08000000);
-            cmd.stdout(Stdio::null());
-            cmd.stderr(Stdio::null());
-        }
+            #[cfg(unix)]
+            let fullscreen_watch = (!self.detach).then(|| FullscreenWatch::start(ipc_socket_path()));
 
-        debug!("Executing: {} {}", self.player_cmd, channel.url);
-        let child = cmd.spawn()
-            .with_context(|| format!("Failed to start media player: {}", self.player_cmd))?;
-        self.current_player_process = Some(child);
+            if !self.detach {
+                self.set_now_playing(Some(channel));
+            }
+            // Reconnects don't restart the sleep timer
+            let timer = self.sleep_timer.map(|timer| timer.saturating_sub(session_start.elapsed()));
+            let supervision = supervise_player(process.as_mut(), self.detach, timer).await;
+            if !self.detach {
+                self.set_now_playing(None);
+            }
+
+            #[cfg(unix)]
+            if let Some(watch) = fullscreen_watch {
+                self.remember_fullscreen(watch.finish());
+            }
+
+            let reconnect = match &supervision {
+                Supervision::Exited(Ok(status)) => {
+                    should_reconnect(*status, start_time.elapsed(), failures, self.config.player.reconnect)
+                }
+                _ => false,
+            };
 
-        println!("{}", "🎥 Player started. Controls:".bright_cyan());
-        println!("   {} Quit player", "q".bright_white().bold());
-        println!("   {} Toggle fullscreen", "f".bright_white().bold());
-        println!("   {} Volume up/down", "9/0".bright_white().bold());
-        println!("   {} Seek backward/forward", "←/→".bright_white().bold());
+            if let (false, false, Some(hook)) = (self.detach, reconnect, &self.config.hooks.post_play) {
+                run_hook("post_play", hook, channel).await;
+            }
 
-        // Wait for process to finish
-        if let Some(ref mut process) = self.current_player_process {
-            let status = process.wait().with_context(|| "Failed to wait for media player")?;
-            self.current_player_process = None;
+            let status = match supervision {
+                Supervision::Detached => {
+                    info!("🚀 Player detached (pid {})", process.id());
+                    return Ok(SleepRace::Finished(()));
+                }
+                Supervision::Exited(status) => status.with_context(|| "Failed to wait for media player")?,
+                Supervision::TimerElapsed => {
+                    println!("{}", "💤 Sleep timer elapsed, playback stopped".bright_magenta());
+                    return Ok(SleepRace::TimerElapsed);
+                }
+            };
 
             let duration = start_time.elapsed();
             if status.success() {
@@ -292,56 +752,1588 @@ impl IptvPlayer {
             } else {
                 warn!("⚠️ Player exited with error code: {:?}", status.code());
             }
+
+            if !reconnect {
+                break;
+            }
+            failures += 1;
+            warn!("🔁 Stream dropped, reconnecting to {} ({}/{})", channel.name, failures, self.config.player.reconnect);
+            tokio::time::sleep(RECONNECT_DELAY).await;
         }
 
+        Ok(SleepRace::Finished(()))
+    }
+
+    /// Spawn the media player for a channel without waiting for it to exit
+    ///
+    /// Any player still running from a previous channel is stopped first.
+    pub fn start_player(&mut self, channel: &Channel) -> Result<()> {
+        self.prepare_launch()?;
+
+        debug!("Executing: {} {}", self.player_cmd, channel.url);
+        let handle = self.media_player
+            .play(channel, &self.launch_options(channel))
+            .with_context(|| format!("Failed to start media player: {}", self.player_cmd))?;
+        self.current_player_process = Some(handle);
+
         Ok(())
     }
 
-    fn validate_player(&self) -> Result<()> {
-        let output = Command::new("which").arg(&self.player_cmd).output();
-        match output {
-            Ok(o) if o.status.success() => Ok(debug!("Player found: {}", self.player_cmd)),
-            _ => {
-                let output = Command::new("where").arg(&self.player_cmd).output();
-                match output {
-                    Ok(o) if o.status.success() => Ok(debug!("Player found: {}", self.player_cmd)),
-                    _ => anyhow::bail!(
-                        "Media player '{}' not found. Please install {} or specify a different player with --player",
-                        self.player_cmd,
-                        self.player_cmd
-                    ),
-                }
-            }
+    /// Like `start_player`, but retries transient spawn failures with exponential backoff
+    async fn start_player_with_retry(&mut self, channel: &Channel) -> Result<()> {
+        self.prepare_launch()?;
+
+        debug!("Executing: {} {}", self.player_cmd, channel.url);
+        let launch = self.launch_options(channel);
+        let handle = spawn_with_retry(
+            || self.media_player.play(channel, &launch),
+            self.config.network.retry_attempts,
+            SPAWN_RETRY_DELAY,
+        )
+        .await
+        .with_context(|| format!("Failed to start media player: {}", self.player_cmd))?;
+        self.current_player_process = Some(handle);
+
+        Ok(())
+    }
+
+    /// Check the player and stop any previous one before launching
+    fn prepare_launch(&mut self) -> Result<()> {
+        self.validate_player()?;
+
+        if self.capabilities.is_none() {
+            self.capabilities = Some(PlayerCapabilities::probe(&self.player_cmd));
         }
+
+        if let Some(mut previous) = self.current_player_process.take() {
+            debug!("Stopping previous media player process");
+            let _ = previous.kill();
+            let _ = previous.wait();
+        }
+
+        Ok(())
     }
 
-    fn add_to_history(&mut self, channel_name: &str) {
-        self.history.retain(|name| name != channel_name);
-        self.history.insert(0, channel_name.to_string());
-        if self.history.len() > 50 {
-            self.history.truncate(50);
+    /// Whether the configured player is mpv, which the audio options are written for
+    fn is_mpv(&self) -> bool {
+        Path::new(&self.player_cmd)
+            .file_stem()
+            .is_some_and(|stem| stem.eq_ignore_ascii_case("mpv"))
+    }
+
+    /// mpv flags for `mute_on_start` and `audio_device`; other players get none
+    fn audio_args(&self) -> Vec<String> {
+        let player = &self.config.player;
+        if !player.mute_on_start && player.audio_device.is_none() {
+            return Vec::new();
+        }
+        if !self.is_mpv() {
+            debug!("Ignoring mute/audio device options for non-mpv player {}", self.player_cmd);
+            return Vec::new();
         }
+
+        let mut args = Vec::new();
+        if player.mute_on_start {
+            args.push("--mute=yes".to_string());
+        }
+        if let Some(device) = &player.audio_device {
+            args.push(format!("--audio-device={}", device));
+        }
+        args
     }
 
-    pub fn get_history(&self) -> &[String] { &self.history }
-    pub fn get_favorites(&self) -> &[String] { &self.favorites }
+    /// mpv window and media titles showing the channel name instead of the stream URL
+    fn title_args(&self, channel: &Channel) -> Vec<String> {
+        if !self.is_mpv() {
+            return Vec::new();
+        }
 
-    pub fn add_favorite(&mut self, channel_name: &str) {
-        if !self.favorites.contains(&channel_name.to_string()) {
-            self.favorites.push(channel_name.to_string());
+        let title = sanitize_title(&channel.name);
+        if title.is_empty() {
+            return Vec::new();
         }
+        // --title expands ${property} references, so a literal $ is written $$
+        vec![format!("--title={}", title.replace('$', "$$")), format!("--force-media-title={}", title)]
     }
 
-    pub fn remove_favorite(&mut self, channel_name: &str) {
-        self.favorites.retain(|name| name != channel_name);
+    /// The `spawn_terminal` command to run the player in, where that is supported
+    fn terminal_wrapper(&self) -> Option<(&str, &[String])> {
+        let (terminal, args) = self.config.player.spawn_terminal.split_first()?;
+        if cfg!(target_os = "linux") {
+            Some((terminal.as_str(), args))
+        } else {
+            debug!("Ignoring spawn_terminal, it is only supported on Linux");
+            None
+        }
     }
-}
 
-impl Drop for IptvPlayer {
-    fn drop(&mut self) {
+    /// Player flags for a channel; the stream URL and any terminal wrapper are added when launching
+    fn player_args(&self, channel: &Channel) -> Vec<String> {
+        // Optimized player arguments
+        let mut args: Vec<String> = [
+            "--cache=yes",
+            "--force-window=immediate",
+            "--no-terminal",
+            "--quiet",
+            "--really-quiet",
+            "--hwdec=auto-safe",
+            "--vo=gpu",
+            "--gpu-context=auto",
+            "--profile=fast",
+            "--stream-buffer-size=1024k",
+            "--demuxer-thread=yes",
+        ]
+        .iter()
+        .map(|arg| arg.to_string())
+        .collect();
+        args.push(format!("--network-timeout={}", self.config.network.timeout));
+
+        let preset = if channel.is_vod() { &self.config.player.vod_args } else { &self.config.player.live_args };
+        args.extend(preset.iter().cloned());
+
+        if self.config.player.start_fullscreen {
+            args.push("--fullscreen".to_string());
+        }
+
+        #[cfg(unix)]
+        if !self.detach {
+            args.push(format!("--input-ipc-server={}", ipc_socket_path().display()));
+        }
+
+        args.extend(self.audio_args());
+        args.extend(self.title_args(channel));
+
+        if let Some(extra_args) = &self.config.player_args {
+            args.extend(extra_args.iter().cloned());
+        }
+
+        for (name, value) in merge_headers(&self.config.network.extra_headers, &channel.headers) {
+            args.push(format!("--http-header-fields-append={}: {}", name, value));
+        }
+
+        filter_player_args(args, self.config.player.gpu, self.capabilities.as_ref())
+    }
+
+    /// How to start the player for `channel` under the current settings
+    fn launch_options(&self, channel: &Channel) -> LaunchOptions {
+        LaunchOptions {
+            args: self.player_args(channel),
+            terminal: self
+                .terminal_wrapper()
+                .map(|(terminal, args)| std::iter::once(terminal.to_string()).chain(args.iter().cloned()).collect())
+                .unwrap_or_default(),
+            detach: self.detach,
+            env: self.config.player.env.clone(),
+        }
+    }
+
+    /// The full command `start_player` would run for a channel
+    #[cfg(test)]
+    fn build_player_command(&self, channel: &Channel) -> Command {
+        ProcessPlayer { program: self.player_cmd.clone() }.command(channel, &self.launch_options(channel))
+    }
+
+    fn validate_player(&self) -> Result<()> {
+        if !is_command_available(&self.player_cmd) {
+            return Err(anyhow::Error::new(ExitCode::PlayerMissing).context(format!(
+                "Media player '{}' not found. Please install {} or specify a different player with --player",
+                self.player_cmd, self.player_cmd
+            )));
+        }
+
+        if let Some((terminal, _)) = self.terminal_wrapper().filter(|(terminal, _)| !is_command_available(terminal)) {
+            return Err(anyhow::Error::new(ExitCode::PlayerMissing)
+                .context(format!("Terminal '{}' from player.spawn_terminal not found", terminal)));
+        }
+
+        debug!("Player found: {}", self.player_cmd);
+        Ok(())
+    }
+
+    fn record_play(&mut self, channel: &Channel) {
+        self.play_stats.record(channel);
+        if let Err(e) = self.play_stats.save() {
+            warn!("⚠️ Failed to save play stats: {}", e);
+        }
+    }
+
+    /// Publish the playing channel to the now-playing file, or empty it when `None`
+    fn set_now_playing(&self, channel: Option<&Channel>) {
+        let Some(path) = now_playing_path(&self.config) else {
+            return;
+        };
+
+        let content = channel
+            .map(|channel| format!("{}\n{}\n", channel.name, channel.url))
+            .unwrap_or_default();
+        if let Err(e) = std::fs::write(&path, content) {
+            warn!("⚠️ Failed to update now playing file {}: {}", path.display(), e);
+        }
+    }
+
+    /// Store the window state mpv last reported so the next launch matches it
+    #[cfg_attr(not(unix), allow(dead_code))]
+    fn remember_fullscreen(&mut self, fullscreen: Option<bool>) {
+        let Some(fullscreen) = fullscreen else {
+            return;
+        };
+        if fullscreen == self.config.player.start_fullscreen {
+            return;
+        }
+
+        debug!("Remembering fullscreen = {}", fullscreen);
+        self.config.player.start_fullscreen = fullscreen;
+        if let Err(e) = self.config.persist() {
+            warn!("⚠️ Failed to save fullscreen state: {}", e);
+        }
+    }
+
+    fn add_to_history(&mut self, channel_name: &str) {
+        self.history.retain(|name| name != channel_name);
+        self.history.insert(0, channel_name.to_string());
+        if self.history.len() > 50 {
+            self.history.truncate(50);
+        }
+
+        self.config.add_recent_channel(channel_name.to_string());
+        if let Err(e) = self.config.persist() {
+            warn!("⚠️ Failed to save recent channels: {}", e);
+        }
+    }
+
+    pub fn parser(&self) -> &PlaylistParser { &self.parser }
+    pub fn parser_mut(&mut self) -> &mut PlaylistParser { &mut self.parser }
+    pub fn config(&self) -> &Config { &self.config }
+    pub fn get_history(&self) -> &[String] { &self.history }
+    pub fn get_favorites(&self) -> &[String] { &self.favorites }
+
+    pub fn add_favorite(&mut self, channel_name: &str) {
+        if !self.favorites.contains(&channel_name.to_string()) {
+            self.favorites.push(channel_name.to_string());
+        }
+    }
+
+    pub fn remove_favorite(&mut self, channel_name: &str) {
+        self.favorites.retain(|name| name != channel_name);
+    }
+}
+
+/// Video outputs the media player reports as usable
+#[derive(Debug, Clone, Default)]
+pub struct PlayerCapabilities {
+    pub video_outputs: HashSet<String>,
+}
+
+impl PlayerCapabilities {
+    /// Ask mpv which video outputs it supports; an empty set means "unknown"
+    pub fn probe(player_cmd: &str) -> Self {
+        match Command::new(player_cmd).arg("--vo=help").output() {
+            Ok(output) => {
+                let capabilities = Self::from_vo_help(&String::from_utf8_lossy(&output.stdout));
+                debug!("Player video outputs: {:?}", capabilities.video_outputs);
+                capabilities
+            }
+            Err(e) => {
+                debug!("Could not probe {} capabilities: {}", player_cmd, e);
+                Self::default()
+            }
+        }
+    }
+
+    /// Parse the indented `name  description` lines printed by `mpv --vo=help`
+    pub fn from_vo_help(output: &str) -> Self {
+        let video_outputs = output
+            .lines()
+            .filter(|line| line.starts_with(char::is_whitespace))
+            .filter_map(|line| line.split_whitespace().next())
+            .map(|name| name.to_string())
+            .collect();
+
+        Self { video_outputs }
+    }
+}
+
+/// Combine global and per-channel headers, the channel winning on a case-insensitive name clash
+pub fn merge_headers(global: &HashMap<String, String>, channel: &BTreeMap<String, String>) -> Vec<(String, String)> {
+    let mut merged: BTreeMap<String, (String, String)> = BTreeMap::new();
+    for (name, value) in global.iter().chain(channel) {
+        merged.insert(name.to_lowercase(), (name.clone(), value.clone()));
+    }
+    merged.into_values().collect()
+}
+
+/// GPU rendering and hardware decoding flags, which break on headless hosts
+fn is_gpu_flag(arg: &str) -> bool {
+    arg.starts_with("--vo=gpu") || arg.starts_with("--hwdec") || arg.starts_with("--gpu-")
+}
+
+/// Drop GPU flags when disabled and `--vo` outputs the player doesn't support
+pub fn filter_player_args(args: Vec<String>, gpu: bool, capabilities: Option<&PlayerCapabilities>) -> Vec<String> {
+    let supported = |vo: &str| match capabilities {
+        Some(caps) if !caps.video_outputs.is_empty() => caps.video_outputs.contains(vo),
+        _ => true,
+    };
+
+    args.into_iter()
+        .filter(|arg| {
+            if !gpu && is_gpu_flag(arg) {
+                debug!("Skipping GPU flag {}", arg);
+                return false;
+            }
+
+            if let Some(vo) = arg.strip_prefix("--vo=") {
+                let first = vo.split(',').next().unwrap_or(vo);
+                if !supported(first) {
+                    warn!("⚠️ Player does not support --vo={}, skipping", first);
+                    return false;
+                }
+            }
+
+            if arg.starts_with("--gpu-") && !supported("gpu") {
+                debug!("Skipping {} without gpu video output", arg);
+                return false;
+            }
+
+            true
+        })
+        .collect()
+}
+
+/// Percent-encode characters players choke on, such as spaces, leaving valid URLs untouched
+fn normalize_stream_url(url: &str) -> String {
+    let url = url.trim();
+    let needs_escaping = url
+        .chars()
+        .any(|c| c.is_whitespace() || !c.is_ascii() || matches!(c, '"' | '<' | '>' | '`' | '{' | '}' | '|' | '\\' | '^'));
+    if !needs_escaping {
+        return url.to_string();
+    }
+
+    match url::Url::parse(url) {
+        Ok(parsed) => parsed.to_string(),
+        Err(e) => {
+            debug!("Leaving unparseable stream URL as is ({}): {}", e, url);
+            url.to_string()
+        }
+    }
+}
+
+/// Now-playing file from the config, else `now_playing.txt` beside the config file
+fn now_playing_path(config: &Config) -> Option<PathBuf> {
+    match &config.now_playing_file {
+        Some(path) => Some(expand_tilde_with(path, dirs::home_dir().as_deref())),
+        None => config.source_path.as_ref()?.parent().map(|dir| dir.join("now_playing.txt")),
+    }
+}
+
+/// Directories `--list` always searches, before `config.playlist_dirs`
+const DEFAULT_PLAYLIST_DIRS: [&str; 4] = [".", "~/Downloads", "~/Documents", "/tmp"];
+
+/// Playlist files in the default locations and `config.playlist_dirs`, expanding `~` to `home`
+pub fn discover_playlists(config: &Config, home: Option<&Path>) -> Vec<PathBuf> {
+    DEFAULT_PLAYLIST_DIRS
+        .iter()
+        .map(|dir| dir.to_string())
+        .chain(config.playlist_dirs.iter().cloned())
+        .flat_map(|dir| scan_playlist_dir(&expand_tilde_with(&dir, home)))
+        .collect()
+}
+
+/// Playlist files directly inside `dir`, sorted by path
+fn scan_playlist_dir(dir: &Path) -> Vec<PathBuf> {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return Vec::new();
+    };
+
+    let mut playlists: Vec<PathBuf> = entries
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| {
+            path.extension()
+                .and_then(|ext| ext.to_str())
+                .is_some_and(|ext| matches!(ext.to_lowercase().as_str(), "m3u" | "m3u8" | "pls"))
+        })
+        .collect();
+    playlists.sort();
+    playlists
+}
+
+/// A player failing within this long of starting is treated as a dropped stream
+const RECONNECT_WINDOW: Duration = Duration::from_secs(30);
+
+/// Pause before relaunching a dropped stream
+const RECONNECT_DELAY: Duration = Duration::from_millis(500);
+
+/// Whether to relaunch a player that exited with `status` after running for `ran`,
+/// given how many times in a row it has already been relaunched
+fn should_reconnect(status: ExitStatus, ran: Duration, failures: u32, limit: u32) -> bool {
+    !status.success() && ran < RECONNECT_WINDOW && failures < limit
+}
+
+/// Channel name as a single-line window title
+fn sanitize_title(name: &str) -> String {
+    name.split(char::is_control)
+        .flat_map(str::split_whitespace)
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Wait before the first spawn retry; doubled for each further attempt
+const SPAWN_RETRY_DELAY: Duration = Duration::from_millis(500);
+
+/// Spawn errors worth retrying; a missing or non-executable player won't fix itself
+fn is_transient_spawn_error(e: &std::io::Error) -> bool {
+    use std::io::ErrorKind;
+    !matches!(e.kind(), ErrorKind::NotFound | ErrorKind::PermissionDenied | ErrorKind::InvalidInput)
+}
+
+/// Run `spawn` up to `attempts` times, backing off between transient failures
+async fn spawn_with_retry<C, S>(mut spawn: S, attempts: u32, delay: Duration) -> Result<C>
+where
+    S: FnMut() -> Result<C>,
+{
+    retry_async(
+        || {
+            let result = spawn();
+            async move { result }
+        },
+        attempts,
+        delay,
+        |e: &anyhow::Error| e.downcast_ref::<std::io::Error>().is_some_and(is_transient_spawn_error),
+    )
+    .await
+}
+
+/// The parts of a spawned player process that playback supervision needs
+pub trait PlayerProcess {
+    fn id(&self) -> u32;
+    fn try_wait(&mut self) -> std::io::Result<Option<ExitStatus>>;
+    fn wait(&mut self) -> std::io::Result<ExitStatus>;
+    fn kill(&mut self) -> std::io::Result<()>;
+}
+
+impl PlayerProcess for Child {
+    fn id(&self) -> u32 {
+        Child::id(self)
+    }
+
+    fn try_wait(&mut self) -> std::io::Result<Option<ExitStatus>> {
+        Child::try_wait(self)
+    }
+
+    fn wait(&mut self) -> std::io::Result<ExitStatus> {
+        Child::wait(self)
+    }
+
+    fn kill(&mut self) -> std::io::Result<()> {
+        Child::kill(self)
+    }
+}
+
+/// A started player that playback supervision can wait on or stop
+pub type PlayHandle = Box<dyn PlayerProcess + Send>;
+
+/// How to start the player for one channel, read from the settings at launch time
+#[derive(Debug, Clone, Default)]
+pub struct LaunchOptions {
+    /// Player flags to pass along with the stream
+    pub args: Vec<String>,
+    /// Terminal command the player is started in, if any
+    pub terminal: Vec<String>,
+    /// Start the player in its own session so it outlives riptv
+    pub detach: bool,
+    /// Environment variables set on the player process
+    pub env: HashMap<String, String>,
+}
+
+/// Starts playback of a channel as `launch` describes
+pub trait MediaPlayer: Send {
+    fn play(&self, channel: &Channel, launch: &LaunchOptions) -> Result<PlayHandle>;
+}
+
+/// Plays channels by spawning the media player as a child process
+#[derive(Debug, Clone, Default)]
+pub struct ProcessPlayer {
+    /// Player executable, e.g. `mpv`
+    pub program: String,
+}
+
+impl ProcessPlayer {
+    fn command(&self, channel: &Channel, launch: &LaunchOptions) -> Command {
+        let mut cmd = match launch.terminal.split_first() {
+            Some((terminal, terminal_args)) => {
+                let mut cmd = Command::new(terminal);
+                cmd.args(terminal_args).arg(&self.program);
+                cmd
+            }
+            None => Command::new(&self.program),
+        };
+        cmd.arg(normalize_stream_url(&channel.url));
+        cmd.args(&launch.args);
+        cmd.envs(&launch.env);
+
+        #[cfg(unix)]
+        {
+            cmd.stdout(Stdio::null());
+            cmd.stderr(Stdio::null());
+
+            if launch.detach {
+                use std::os::unix::process::CommandExt;
+                // SAFETY: setsid is async-signal-safe and touches no parent state
+                unsafe {
+                    cmd.pre_exec(|| nix::unistd::setsid().map(|_| ()).map_err(std::io::Error::from));
+                }
+            }
+        }
+
+        #[cfg(windows)]
+        {
+            use std::os::windows::process::CommandExt;
+            const CREATE_NO_WINDOW: u32 = 0x08000000;
+            const DETACHED_PROCESS: u32 = 0x00000008;
+            cmd.creation_flags(if launch.detach { CREATE_NO_WINDOW | DETACHED_PROCESS } else { CREATE_NO_WINDOW });
+            cmd.stdout(Stdio::null());
+            cmd.stderr(Stdio::null());
+        }
+
+        cmd
+    }
+}
+
+impl MediaPlayer for ProcessPlayer {
+    fn play(&self, channel: &Channel, launch: &LaunchOptions) -> Result<PlayHandle> {
+        Ok(Box::new(self.command(channel, launch).spawn()?))
+    }
+}
+
+/// How supervising a player process ended
+#[derive(Debug)]
+pub enum Supervision {
+    /// Left running on its own; never waited on
+    Detached,
+    Exited(std::io::Result<ExitStatus>),
+    /// Stopped by the sleep timer
+    TimerElapsed,
+}
+
+/// Wait for the player to exit, stop it when the sleep timer fires, or let it go when detached
+pub async fn supervise_player<P: PlayerProcess + ?Sized>(process: &mut P, detach: bool, sleep_timer: Option<Duration>) -> Supervision {
+    if detach {
+        return Supervision::Detached;
+    }
+
+    match race_sleep_timer(wait_for_exit(process), sleep_timer).await {
+        SleepRace::Finished(status) => Supervision::Exited(status),
+        SleepRace::TimerElapsed => {
+            info!("💤 Sleep timer elapsed, stopping player");
+            let _ = process.kill();
+            let _ = process.wait();
+            Supervision::TimerElapsed
+        }
+    }
+}
+
+/// Shell invocation for a hook, with the channel exposed as environment variables
+fn hook_command(command: &str, channel: &Channel) -> tokio::process::Command {
+    #[cfg(unix)]
+    let mut cmd = {
+        let mut cmd = tokio::process::Command::new("sh");
+        cmd.arg("-c").arg(command);
+        cmd
+    };
+    #[cfg(windows)]
+    let mut cmd = {
+        let mut cmd = tokio::process::Command::new("cmd");
+        cmd.arg("/C").arg(command);
+        cmd
+    };
+
+    cmd.env("RIPTV_CHANNEL_NAME", &channel.name)
+        .env("RIPTV_CHANNEL_URL", &channel.url)
+        .env("RIPTV_CHANNEL_GROUP", channel.group.as_deref().unwrap_or_default())
+        .stdin(Stdio::null());
+    cmd
+}
+
+/// Run a user hook to completion; failures are logged and never stop playback
+async fn run_hook(name: &str, command: &str, channel: &Channel) {
+    debug!("Running {} hook: {}", name, command);
+    match hook_command(command, channel).status().await {
+        Ok(status) if status.success() => {}
+        Ok(status) => warn!("⚠️ {} hook exited with {}", name, status),
+        Err(e) => warn!("⚠️ Failed to run {} hook: {}", name, e),
+    }
+}
+
+/// mpv IPC socket used to read the window state back while it plays
+#[cfg(unix)]
+fn ipc_socket_path() -> PathBuf {
+    std::env::temp_dir().join(format!("riptv-mpv-{}.sock", std::process::id()))
+}
+
+/// Request id tagging our `get_property fullscreen` query on the IPC socket
+const FULLSCREEN_REQUEST_ID: u64 = 1;
+
+/// Polls mpv's `fullscreen` property, keeping the last answer it got
+#[cfg(unix)]
+struct FullscreenWatch {
+    socket: PathBuf,
+    task: tokio::task::JoinHandle<()>,
+    last: Arc<std::sync::Mutex<Option<bool>>>,
+}
+
+#[cfg(unix)]
+impl FullscreenWatch {
+    fn start(socket: PathBuf) -> Self {
+        let last = Arc::new(std::sync::Mutex::new(None));
+        let task = tokio::spawn({
+            let socket = socket.clone();
+            let last = Arc::clone(&last);
+            async move {
+                loop {
+                    tokio::time::sleep(Duration::from_secs(1)).await;
+                    if let Some(fullscreen) = query_fullscreen(&socket).await {
+                        *last.lock().unwrap_or_else(|e| e.into_inner()) = Some(fullscreen);
+                    }
+                }
+            }
+        });
+
+        Self { socket, task, last }
+    }
+
+    /// Stop polling and return the last reported state, if mpv ever answered
+    fn finish(self) -> Option<bool> {
+        self.task.abort();
+        let _ = std::fs::remove_file(&self.socket);
+        *self.last.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+/// Ask mpv over its IPC socket whether it is fullscreen
+#[cfg(unix)]
+async fn query_fullscreen(socket: &Path) -> Option<bool> {
+    use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+
+    let stream = tokio::net::UnixStream::connect(socket).await.ok()?;
+    let (reader, mut writer) = stream.into_split();
+    let request = format!(
+        "{{\"command\":[\"get_property\",\"fullscreen\"],\"request_id\":{}}}\n",
+        FULLSCREEN_REQUEST_ID
+    );
+    writer.write_all(request.as_bytes()).await.ok()?;
+
+    let mut lines = BufReader::new(reader).lines();
+    let read_reply = async {
+        while let Some(line) = lines.next_line().await.ok()? {
+            if let Some(fullscreen) = parse_fullscreen_reply(&line) {
+                return Some(fullscreen);
+            }
+        }
+        None
+    };
+    tokio::time::timeout(Duration::from_secs(1), read_reply).await.ok()?
+}
+
+/// Pull the fullscreen flag out of mpv's reply, skipping unrelated events
+fn parse_fullscreen_reply(line: &str) -> Option<bool> {
+    let reply: serde_json::Value = serde_json::from_str(line).ok()?;
+    if reply.get("request_id")?.as_u64()? != FULLSCREEN_REQUEST_ID {
+        return None;
+    }
+    reply.get("data")?.as_bool()
+}
+
+/// Wait for the player process to exit without blocking the runtime
+async fn wait_for_exit<P: PlayerProcess + ?Sized>(process: &mut P) -> std::io::Result<ExitStatus> {
+    loop {
+        if let Some(status) = process.try_wait()? {
+            return Ok(status);
+        }
+        tokio::time::sleep(Duration::from_millis(200)).await;
+    }
+}
+
+/// Race `fut` against an optional sleep timer, logging the remaining time every minute
+pub async fn race_sleep_timer<F: Future>(fut: F, timer: Option<Duration>) -> SleepRace<F::Output> {
+    let Some(timer) = timer else {
+        return SleepRace::Finished(fut.await);
+    };
+
+    let deadline = tokio::time::Instant::now() + timer;
+    let mut ticker = tokio::time::interval(Duration::from_secs(60));
+    ticker.tick().await;
+    tokio::pin!(fut);
+
+    loop {
+        tokio::select! {
+            output = &mut fut => return SleepRace::Finished(output),
+            _ = tokio::time::sleep_until(deadline) => return SleepRace::TimerElapsed,
+            _ = ticker.tick() => {
+                let remaining = deadline.saturating_duration_since(tokio::time::Instant::now());
+                info!("💤 Sleep timer: {} remaining", format_duration(remaining));
+            }
+        }
+    }
+}
+
+impl Drop for IptvPlayer {
+    fn drop(&mut self) {
         debug!("IptvPlayer being dropped, performing emergency cleanup");
         if let Some(mut child) = self.current_player_process.take() {
             let _ = child.kill();
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn channel(name: &str) -> Channel {
+        Channel::new(name.to_string(), format!("http://example.com/{}", name))
+    }
+
+    #[test]
+    fn test_rank_by_history() {
+        let config = Config {
+            recent_channels: vec!["Gamma".to_string(), "Beta".to_string()],
+            ..Config::default()
+        };
+        let player = IptvPlayer::new("mpv".to_string(), config, false);
+
+        let channels = vec![channel("Alpha"), channel("Beta"), channel("Gamma"), channel("Delta")];
+        let names: Vec<_> = player
+            .rank_by_history(&channels)
+            .into_iter()
+            .map(|channel| channel.name)
+            .collect();
+
+        assert_eq!(names, ["Gamma", "Beta", "Alpha", "Delta"]);
+    }
+
+    #[tokio::test]
+    async fn test_sleep_timer_race() {
+        // Player exits before the timer
+        let outcome = race_sleep_timer(async { 0 }, Some(Duration::from_secs(60))).await;
+        assert_eq!(outcome, SleepRace::Finished(0));
+
+        // Player keeps running past the timer
+        let outcome = race_sleep_timer(std::future::pending::<()>(), Some(Duration::from_millis(20))).await;
+        assert_eq!(outcome, SleepRace::TimerElapsed);
+
+        // No timer just waits for the player
+        let outcome = race_sleep_timer(async {
+            tokio::time::sleep(Duration::from_millis(20)).await;
+            1
+        }, None).await;
+        assert_eq!(outcome, SleepRace::Finished(1));
+    }
+
+    #[test]
+    fn test_filter_player_args() {
+        let args: Vec<String> = ["--cache=yes", "--hwdec=auto-safe", "--vo=gpu", "--gpu-context=auto"]
+            .iter()
+            .map(|arg| arg.to_string())
+            .collect();
+
+        // GPU disabled drops every GPU-related flag
+        assert_eq!(filter_player_args(args.clone(), false, None), ["--cache=yes"]);
+
+        // Unknown capabilities keep everything
+        assert_eq!(filter_player_args(args.clone(), true, None), args);
+
+        // A player without the gpu output loses --vo=gpu and its context flag
+        let capabilities = PlayerCapabilities::from_vo_help(
+            "Available video outputs:\n  xv               X11/Xv\n  null             Null video output\n",
+        );
+        assert!(capabilities.video_outputs.contains("null"));
+        assert_eq!(
+            filter_player_args(args.clone(), true, Some(&capabilities)),
+            ["--cache=yes", "--hwdec=auto-safe"]
+        );
+
+        let capabilities = PlayerCapabilities::from_vo_help("  gpu              Shader-based GPU Renderer\n");
+        assert_eq!(filter_player_args(args.clone(), true, Some(&capabilities)), args);
+    }
+
+    #[test]
+    fn test_headers_in_player_command() {
+        let mut config = Config::default();
+        config.network.extra_headers.insert("Origin".to_string(), "https://global.example".to_string());
+        config.network.extra_headers.insert("referer".to_string(), "https://global.example/".to_string());
+        let player = IptvPlayer::new("mpv".to_string(), config, false);
+
+        let mut channel = channel("Alpha");
+        channel.headers.insert("Referer".to_string(), "https://channel.example/".to_string());
+
+        let command = player.build_player_command(&channel);
+        let headers: Vec<_> = command
+            .get_args()
+            .filter_map(|arg| arg.to_str()?.strip_prefix("--http-header-fields-append="))
+            .collect();
+
+        assert_eq!(headers, ["Origin: https://global.example", "Referer: https://channel.example/"]);
+    }
+
+    #[test]
+    fn test_network_timeout_in_player_command() {
+        let mut config = Config::default();
+        config.network.timeout = 90;
+        let player = IptvPlayer::new("mpv".to_string(), config, false);
+
+        let command = player.build_player_command(&channel("Alpha"));
+        assert!(command.get_args().any(|arg| arg == "--network-timeout=90"));
+    }
+
+    #[test]
+    fn test_title_in_player_command() {
+        let titles = |player_cmd: &str, name: &str| -> Vec<String> {
+            let player = IptvPlayer::new(player_cmd.to_string(), Config::default(), false);
+            let channel = Channel::new(name.to_string(), "http://example.com/live".to_string());
+            player
+                .build_player_command(&channel)
+                .get_args()
+                .map(|arg| arg.to_string_lossy().into_owned())
+                .filter(|arg| arg.starts_with("--title=") || arg.starts_with("--force-media-title="))
+                .collect()
+        };
+
+        assert_eq!(titles("mpv", "BBC One HD"), ["--title=BBC One HD", "--force-media-title=BBC One HD"]);
+        assert_eq!(titles("/usr/bin/mpv", "Sky\n News\t$5"), ["--title=Sky News $$5", "--force-media-title=Sky News $5"]);
+        assert!(titles("mpv", "\n").is_empty());
+        assert!(titles("vlc", "BBC One HD").is_empty());
+    }
+
+    #[test]
+    fn test_fullscreen_state_in_player_command() {
+        let has_fullscreen = |start_fullscreen: bool| {
+            let mut config = Config::default();
+            config.player.start_fullscreen = start_fullscreen;
+            let player = IptvPlayer::new("mpv".to_string(), config, false);
+            player.build_player_command(&channel("Alpha")).get_args().any(|arg| arg == "--fullscreen")
+        };
+
+        assert!(has_fullscreen(true));
+        assert!(!has_fullscreen(false));
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_hook_environment() {
+        let path = std::env::temp_dir().join(format!("riptv-hook-{}.txt", std::process::id()));
+        let command = format!(
+            "printf '%s|%s|%s' \"$RIPTV_CHANNEL_NAME\" \"$RIPTV_CHANNEL_URL\" \"$RIPTV_CHANNEL_GROUP\" > '{}'",
+            path.display()
+        );
+        let mut channel = channel("Alpha");
+        channel.group = Some("News".to_string());
+
+        run_hook("pre_play", &command, &channel).await;
+        let written = std::fs::read_to_string(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(written, format!("Alpha|{}|News", channel.url));
+    }
+
+    #[test]
+    fn test_audio_options_in_player_command() {
+        let mut config = Config::default();
+        config.player.mute_on_start = true;
+        config.player.audio_device = Some("pulse/headphones".to_string());
+
+        let args = |player_cmd: &str| {
+            IptvPlayer::new(player_cmd.to_string(), config.clone(), false)
+                .build_player_command(&channel("Alpha"))
+                .get_args()
+                .map(|arg| arg.to_string_lossy().into_owned())
+                .collect::<Vec<_>>()
+        };
+
+        let mpv = args("/usr/bin/mpv");
+        assert!(mpv.contains(&"--mute=yes".to_string()));
+        assert!(mpv.contains(&"--audio-device=pulse/headphones".to_string()));
+
+        let vlc = args("vlc");
+        assert!(!vlc.iter().any(|arg| arg.starts_with("--mute") || arg.starts_with("--audio-device")));
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_now_playing_file() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = std::env::temp_dir().join(format!("riptv-now-playing-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let stub = dir.join("stub-player");
+        std::fs::write(&stub, "#!/bin/sh\nsleep 1\n").unwrap();
+        std::fs::set_permissions(&stub, std::fs::Permissions::from_mode(0o755)).unwrap();
+        let status_file = dir.join("now_playing.txt");
+
+        let config = Config {
+            now_playing_file: Some(status_file.to_string_lossy().into_owned()),
+            ..Config::default()
+        };
+        let mut player = IptvPlayer::new(stub.to_string_lossy().into_owned(), config, false);
+        player.capabilities = Some(PlayerCapabilities::default());
+        let channel = channel("Alpha");
+
+        let during = async {
+            tokio::time::sleep(Duration::from_millis(500)).await;
+            std::fs::read_to_string(&status_file).unwrap_or_default()
+        };
+        let (played, during) = tokio::join!(player.play_channel(&channel), during);
+        played.unwrap();
+        let after = std::fs::read_to_string(&status_file).unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(during, format!("Alpha\n{}\n", channel.url));
+        assert_eq!(after, "");
+    }
+
+    #[test]
+    fn test_normalize_stream_url() {
+        assert_eq!(
+            normalize_stream_url("http://example.com/live/my stream.m3u8"),
+            "http://example.com/live/my%20stream.m3u8"
+        );
+        assert_eq!(normalize_stream_url("http://Example.com:8080/a?b=c&d=%20"), "http://Example.com:8080/a?b=c&d=%20");
+        assert_eq!(normalize_stream_url("rtmp://example.com/live"), "rtmp://example.com/live");
+        assert_eq!(normalize_stream_url("not a url"), "not a url");
+
+        let player = IptvPlayer::new("mpv".to_string(), Config::default(), false);
+        let mut spaced = channel("Alpha");
+        spaced.url = "http://example.com/a b".to_string();
+        let command = player.build_player_command(&spaced);
+        assert_eq!(command.get_args().next().unwrap(), "http://example.com/a%20b");
+    }
+
+    #[test]
+    fn test_parse_fullscreen_reply() {
+        assert_eq!(parse_fullscreen_reply(r#"{"data":true,"request_id":1,"error":"success"}"#), Some(true));
+        assert_eq!(parse_fullscreen_reply(r#"{"data":false,"request_id":1,"error":"success"}"#), Some(false));
+        assert_eq!(parse_fullscreen_reply(r#"{"event":"playback-restart"}"#), None);
+        assert_eq!(parse_fullscreen_reply(r#"{"data":true,"request_id":7,"error":"success"}"#), None);
+    }
+
+    #[test]
+    fn test_remember_fullscreen() {
+        let mut player = IptvPlayer::new("mpv".to_string(), Config::default(), false);
+        player.remember_fullscreen(None);
+        assert!(!player.config().player.start_fullscreen);
+        player.remember_fullscreen(Some(true));
+        assert!(player.config().player.start_fullscreen);
+    }
+
+    #[tokio::test]
+    async fn test_count_channels() {
+        let path = std::env::temp_dir().join(format!("riptv-count-{}.m3u", std::process::id()));
+        std::fs::write(
+            &path,
+            "#EXTM3U\n\
+            #EXTINF:-1 group-title=\"News\",CNN\nhttp://example.com/cnn\n\
+            #EXTINF:-1 group-title=\"News\",BBC News\nhttp://example.com/bbcnews\n\
+            #EXTINF:-1 group-title=\"UK\",BBC One\nhttp://example.com/bbc1\n",
+        )
+        .unwrap();
+
+        let mut player = IptvPlayer::new("mpv".to_string(), Config::default(), false);
+        player.load_playlist(path.to_str().unwrap()).await.unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(player.count_channels(None), 3);
+        assert_eq!(player.count_channels(Some("bbc")), 2);
+
+        player.set_group_filter(Some("news".to_string()));
+        assert_eq!(player.count_channels(None), 2);
+        assert_eq!(player.count_channels(Some("bbc")), 1);
+    }
+
+    #[tokio::test]
+    async fn test_default_group_scope() {
+        let path = std::env::temp_dir().join(format!("riptv-default-group-{}.m3u", std::process::id()));
+        std::fs::write(
+            &path,
+            "#EXTM3U\n\
+            #EXTINF:-1 group-title=\"News\",CNN\nhttp://example.com/cnn\n\
+            #EXTINF:-1 group-title=\"UK\",BBC One\nhttp://example.com/bbc1\n\
+            #EXTINF:-1 group-title=\"News\",BBC News\nhttp://example.com/bbcnews\n",
+        )
+        .unwrap();
+
+        let mut config = Config::default();
+        config.ui.default_group = Some("news".to_string());
+        let mut player = IptvPlayer::new("mpv".to_string(), config, false);
+        player.load_playlist(path.to_str().unwrap()).await.unwrap();
+
+        let selector = player.interactive_selector(player.ordered_channels());
+        let names: Vec<_> = selector.initial_items().iter().map(|item| item.channel.name.clone()).collect();
+        assert_eq!(names, ["CNN", "BBC News"]);
+
+        // A group the playlist doesn't have opens the full list
+        player.config.ui.default_group = Some("Sports".to_string());
+        let selector = player.interactive_selector(player.ordered_channels());
+        assert_eq!(selector.initial_items().len(), 3);
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_tag_filter() {
+        let path = std::env::temp_dir().join(format!("riptv-tags-{}.m3u", std::process::id()));
+        std::fs::write(
+            &path,
+            "#EXTM3U\n\
+            #EXTINF:-1 group-title=\"News\",CNN\nhttp://example.com/cnn\n\
+            #EXTINF:-1 group-title=\"News\",BBC News\nhttp://example.com/bbcnews\n\
+            #EXTINF:-1 group-title=\"UK\",BBC One\nhttp://example.com/bbc1\n",
+        )
+        .unwrap();
+
+        let mut config = Config::default();
+        config.add_tag("http://example.com/cnn", "Watch");
+        config.add_tag("http://example.com/bbc1", "watch");
+        let mut player = IptvPlayer::new("mpv".to_string(), config, false);
+        player.load_playlist(path.to_str().unwrap()).await.unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        player.set_tag_filter(Some("WATCH".to_string()));
+        let names: Vec<_> = player.ordered_channels().into_iter().map(|channel| channel.name).collect();
+        assert_eq!(names, ["CNN", "BBC One"]);
+        assert_eq!(player.count_channels(Some("bbc")), 1);
+
+        player.set_group_filter(Some("news".to_string()));
+        assert_eq!(player.count_channels(None), 1);
+
+        player.set_tag_filter(Some("sports".to_string()));
+        assert_eq!(player.count_channels(None), 0);
+    }
+
+    #[tokio::test]
+    async fn test_empty_result_hint() {
+        let mut config = Config::default();
+        config.add_tag("http://example.com/cnn", "Watch");
+        let mut player = IptvPlayer::new("mpv".to_string(), config, false);
+        player.parser_mut().parse_str(
+            "#EXTM3U\n\
+            #EXTINF:-1 group-title=\"News\",CNN\nhttp://example.com/cnn\n\
+            #EXTINF:-1 group-title=\"Sports\",Sky Sports\nhttp://example.com/sky\n\
+            #EXTINF:-1 group-title=\"UK News\",BBC News\nhttp://example.com/bbcnews\n\
+            #EXTINF:-1 group-title=\"Kids\",CBeebies\nhttp://example.com/cbeebies\n"
+                .to_string(),
+        )
+        .await
+        .unwrap();
+
+        player.set_group_filter(Some("Nwes".to_string()));
+        assert_eq!(player.count_channels(None), 0);
+        assert_eq!(player.empty_result_hint(None), "No group named 'Nwes'. Closest groups: News, Kids, Sports");
+
+        player.set_group_filter(Some("news".to_string()));
+        assert_eq!(player.empty_result_hint(Some("zzz")), "No channels in group 'news' match 'zzz'; try without the filter");
+
+        player.set_tag_filter(Some("wacth".to_string()));
+        assert_eq!(player.empty_result_hint(None), "No tag named 'wacth'. Closest tags: Watch");
+
+        player.set_group_filter(Some("Sports".to_string()));
+        player.set_tag_filter(Some("watch".to_string()));
+        assert_eq!(player.empty_result_hint(None), "No channels are in group 'Sports' and tagged 'watch'");
+    }
+
+    #[tokio::test]
+    async fn test_smart_random_favors_favorites() {
+        let mut config = Config::default();
+        config.add_favorite_channel("Channel 0".to_string());
+        config.recent_channels = vec!["Channel 1".to_string()];
+        let mut player = IptvPlayer::new("mpv".to_string(), config, false);
+        let playlist: String = std::iter::once("#EXTM3U\n".to_string())
+            .chain((0..10).map(|i| format!("#EXTINF:-1,Channel {}\nhttp://example.com/{}\n", i, i)))
+            .collect();
+        player.parser_mut().parse_str(playlist).await.unwrap();
+
+        let draws = |smart: bool| {
+            let mut rng = Rng::seeded(42);
+            let mut counts: HashMap<String, usize> = HashMap::new();
+            for _ in 0..14_000 {
+                *counts.entry(player.random_channel(smart, &mut rng).unwrap().name).or_insert(0) += 1;
+            }
+            counts
+        };
+
+        // Weights 5 + 3 + 8 * 1 = 16: the favorite expects 4375 of 14000, a recent 2625, the rest 875 each
+        let smart = draws(true);
+        assert!((3_900..4_900).contains(&smart["Channel 0"]), "{:?}", smart);
+        assert!((2_300..3_000).contains(&smart["Channel 1"]), "{:?}", smart);
+        assert!((650..1_100).contains(&smart["Channel 7"]), "{:?}", smart);
+
+        // Uniform picks give every channel about 1400
+        let uniform = draws(false);
+        assert!(uniform.values().all(|count| (1_150..1_650).contains(count)), "{:?}", uniform);
+        assert_eq!(draws(true), smart);
+    }
+
+    #[derive(Default)]
+    struct MockProcess {
+        waits: usize,
+        kills: usize,
+    }
+
+    impl PlayerProcess for MockProcess {
+        fn id(&self) -> u32 {
+            0
+        }
+
+        fn try_wait(&mut self) -> std::io::Result<Option<ExitStatus>> {
+            self.waits += 1;
+            Ok(None)
+        }
+
+        fn wait(&mut self) -> std::io::Result<ExitStatus> {
+            self.waits += 1;
+            Err(std::io::Error::other("mock process never exits"))
+        }
+
+        fn kill(&mut self) -> std::io::Result<()> {
+            self.kills += 1;
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_detached_player_is_not_waited_on() {
+        let mut process = MockProcess::default();
+        let outcome = supervise_player(&mut process, true, Some(Duration::from_millis(10))).await;
+        assert!(matches!(outcome, Supervision::Detached));
+        assert_eq!((process.waits, process.kills), (0, 0));
+
+        // Attached players are polled and stopped by the sleep timer
+        let mut process = MockProcess::default();
+        let outcome = supervise_player(&mut process, false, Some(Duration::from_millis(10))).await;
+        assert!(matches!(outcome, Supervision::TimerElapsed));
+        assert!(process.waits > 0);
+        assert_eq!(process.kills, 1);
+    }
+
+    /// Stream URL and launch options of one launch
+    type Launch = (String, LaunchOptions);
+
+    /// Records what would be launched and hands back a process that never exits
+    #[derive(Clone, Default)]
+    struct MockPlayer {
+        launches: Arc<std::sync::Mutex<Vec<Launch>>>,
+    }
+
+    impl MediaPlayer for MockPlayer {
+        fn play(&self, channel: &Channel, launch: &LaunchOptions) -> Result<PlayHandle> {
+            self.launches.lock().unwrap().push((channel.url.clone(), launch.clone()));
+            Ok(Box::new(MockProcess::default()))
+        }
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_play_through_media_player() {
+        let mut config = Config::default();
+        config.player.start_fullscreen = true;
+        // Any installed command passes the availability check; the mock does the launching
+        let mut player = IptvPlayer::new("sh".to_string(), config, false);
+        player.capabilities = Some(PlayerCapabilities::default());
+        let mock = MockPlayer::default();
+        player.set_media_player(Box::new(mock.clone()));
+        // Settings changed after injecting the mock still reach it
+        player.set_detach(true);
+
+        let outcome = player.play_channel(&channel("Alpha")).await.unwrap();
+        assert_eq!(outcome, SleepRace::Finished(()));
+
+        let launches = mock.launches.lock().unwrap();
+        assert_eq!(launches.len(), 1);
+        let (url, launch) = &launches[0];
+        assert_eq!(url, "http://example.com/Alpha");
+        assert!(launch.detach);
+        assert!(launch.args.contains(&"--fullscreen".to_string()));
+        assert!(!launch.args.iter().any(|arg| arg.starts_with("--input-ipc-server")));
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_play_first_match_picks_top_ranked() {
+        let mut player = IptvPlayer::new("sh".to_string(), Config::default(), false);
+        player.capabilities = Some(PlayerCapabilities::default());
+        player.set_detach(true);
+        let mock = MockPlayer::default();
+        player.set_media_player(Box::new(mock.clone()));
+        player
+            .parser
+            .parse_str(
+                "#EXTM3U\n\
+                #EXTINF:-1 group-title=\"Sports\",Sport One\nhttp://example.com/one\n\
+                #EXTINF:-1 group-title=\"Sports\",Sport Two\nhttp://example.com/two\n\
+                #EXTINF:-1 group-title=\"Kids\",Sport Two Junior\nhttp://example.com/junior\n"
+                    .to_string(),
+            )
+            .await
+            .unwrap();
+
+        let best = player.parser.search_scored("sport two")[0].1;
+        player.play_first_match("sport two").await.unwrap();
+        player.set_group_filter(Some("Kids".to_string()));
+        player.play_first_match("sport two").await.unwrap();
+        assert!(player.play_first_match("zzzz").await.is_err());
+
+        let urls: Vec<_> = mock.launches.lock().unwrap().iter().map(|(url, _)| url.clone()).collect();
+        assert_eq!(urls, [player.parser.get_channels()[best].url.clone(), "http://example.com/junior".to_string()]);
+        assert_eq!(urls[0], "http://example.com/two");
+    }
+
+    /// A player process that has already exited with `code`
+    #[cfg(unix)]
+    struct ExitedProcess(i32);
+
+    #[cfg(unix)]
+    impl PlayerProcess for ExitedProcess {
+        fn id(&self) -> u32 {
+            0
+        }
+
+        fn try_wait(&mut self) -> std::io::Result<Option<ExitStatus>> {
+            self.wait().map(Some)
+        }
+
+        fn wait(&mut self) -> std::io::Result<ExitStatus> {
+            use std::os::unix::process::ExitStatusExt;
+            Ok(ExitStatus::from_raw(self.0 << 8))
+        }
+
+        fn kill(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    /// Counts launches of a player that exits straight away with `code`
+    #[cfg(unix)]
+    #[derive(Clone)]
+    struct ExitingPlayer {
+        code: i32,
+        launches: Arc<std::sync::atomic::AtomicUsize>,
+    }
+
+    #[cfg(unix)]
+    impl MediaPlayer for ExitingPlayer {
+        fn play(&self, _channel: &Channel, _launch: &LaunchOptions) -> Result<PlayHandle> {
+            self.launches.fetch_add(1, Ordering::SeqCst);
+            Ok(Box::new(ExitedProcess(self.code)))
+        }
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_reconnect_after_quick_failure() {
+        async fn launches(code: i32, reconnect: u32) -> usize {
+            let mut config = Config::default();
+            config.player.reconnect = reconnect;
+            let mut player = IptvPlayer::new("sh".to_string(), config, false);
+            player.capabilities = Some(PlayerCapabilities::default());
+            let mock = ExitingPlayer { code, launches: Arc::default() };
+            player.set_media_player(Box::new(mock.clone()));
+
+            player.play_channel(&channel("Alpha")).await.unwrap();
+            mock.launches.load(Ordering::SeqCst)
+        }
+
+        // A quick non-zero exit relaunches up to the limit, then gives up
+        assert_eq!(launches(2, 2).await, 3);
+        // A clean exit, or reconnect turned off, launches once
+        assert_eq!(launches(0, 2).await, 1);
+        assert_eq!(launches(2, 0).await, 1);
+
+        use std::os::unix::process::ExitStatusExt;
+        let failed = ExitStatus::from_raw(1 << 8);
+        assert!(!should_reconnect(failed, RECONNECT_WINDOW, 0, 2));
+    }
+
+    #[tokio::test]
+    async fn test_spawn_retried_after_transient_failures() {
+        use std::io::{Error, ErrorKind};
+
+        let mut attempts = 0;
+        let result = spawn_with_retry(
+            || {
+                attempts += 1;
+                match attempts {
+                    1 | 2 => Err(Error::from(ErrorKind::WouldBlock).into()),
+                    _ => Ok("player"),
+                }
+            },
+            3,
+            Duration::from_millis(1),
+        )
+        .await;
+        assert_eq!(result.unwrap(), "player");
+        assert_eq!(attempts, 3);
+
+        // A missing player is reported on the first failure
+        let mut attempts = 0;
+        let result: Result<()> = spawn_with_retry(
+            || {
+                attempts += 1;
+                Err(Error::from(ErrorKind::NotFound).into())
+            },
+            3,
+            Duration::from_millis(1),
+        )
+        .await;
+        assert_eq!(result.unwrap_err().downcast_ref::<Error>().unwrap().kind(), ErrorKind::NotFound);
+        assert_eq!(attempts, 1);
+    }
+
+    #[test]
+    fn test_find_playlists_in_extra_dirs() {
+        let dir = std::env::temp_dir().join(format!("riptv-find-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("tv.m3u"), "#EXTM3U\n").unwrap();
+        std::fs::write(dir.join("notes.txt"), "").unwrap();
+
+        let mut config = Config::default();
+        config.playlist_dirs.push(dir.to_string_lossy().into_owned());
+        let found = IptvPlayer::new("mpv".to_string(), config, false).find_playlists();
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        assert!(found.contains(&dir.join("tv.m3u")));
+        assert!(!found.contains(&dir.join("notes.txt")));
+
+        let home = Path::new("/home/viewer");
+        assert_eq!(expand_tilde_with("~/Downloads", Some(home)), home.join("Downloads"));
+        assert_eq!(expand_tilde_with("~", Some(home)), home);
+        assert_eq!(expand_tilde_with("/tmp", Some(home)), PathBuf::from("/tmp"));
+        assert_eq!(expand_tilde_with("~/Downloads", None), PathBuf::from("~/Downloads"));
+    }
+
+    #[test]
+    fn test_find_playlists_under_fake_home() {
+        let home = std::env::temp_dir().join(format!("riptv-home-{}", std::process::id()));
+        std::fs::create_dir_all(home.join("Downloads")).unwrap();
+        std::fs::write(home.join("Downloads").join("fake.m3u8"), "#EXTM3U\n").unwrap();
+
+        let player = IptvPlayer::new("mpv".to_string(), Config::default(), false);
+        let found = player.find_playlists_with_home(Some(&home));
+        std::fs::remove_dir_all(&home).unwrap();
+
+        assert!(found.contains(&home.join("Downloads").join("fake.m3u8")));
+    }
+
+    #[tokio::test]
+    async fn test_error_exit_codes() {
+        let mut player = IptvPlayer::new("riptv-no-such-player".to_string(), Config::default(), false);
+        let missing = player.validate_player().unwrap_err();
+        assert!(missing.to_string().contains("riptv-no-such-player"));
+        assert_eq!(ExitCode::from_error(&missing), ExitCode::PlayerMissing);
+
+        let unmatched = player.play_first_match("bbc").await.unwrap_err();
+        assert_eq!(ExitCode::from_error(&unmatched), ExitCode::NoChannels);
+
+        let unreadable = player
+            .load_playlist("/nonexistent/riptv.m3u")
+            .await
+            .context(ExitCode::PlaylistLoad)
+            .unwrap_err();
+        assert_eq!(ExitCode::from_error(&unreadable).code(), 4);
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_spawn_terminal_prefixes_command() {
+        let mut config = Config::default();
+        config.player.spawn_terminal = vec!["alacritty".to_string(), "-e".to_string()];
+        let player = IptvPlayer::new("mpv".to_string(), config, false);
+        let channel = Channel::new("Alpha".to_string(), "http://example.com/alpha".to_string());
+
+        let command = player.build_player_command(&channel);
+        let args: Vec<_> = command.get_args().map(|arg| arg.to_string_lossy().into_owned()).collect();
+        assert_eq!(command.get_program(), "alacritty");
+        assert_eq!(args[..3], ["-e", "mpv", "http://example.com/alpha"]);
+
+        let plain = IptvPlayer::new("mpv".to_string(), Config::default(), false).build_player_command(&channel);
+        assert_eq!(plain.get_program(), "mpv");
+    }
+
+    #[test]
+    fn test_live_and_vod_presets() {
+        let mut config = Config::default();
+        config.player.live_args = vec!["--demuxer-readahead-secs=5".to_string()];
+        config.player.vod_args = vec!["--demuxer-readahead-secs=600".to_string()];
+        let player = IptvPlayer::new("mpv".to_string(), config, false);
+        let args_for = |channel: &Channel| -> Vec<String> {
+            player
+                .build_player_command(channel)
+                .get_args()
+                .map(|arg| arg.to_string_lossy().into_owned())
+                .collect()
+        };
+
+        let live = Channel::new("News".to_string(), "http://example.com/news".to_string());
+        let mut movie = Channel::new("Movie".to_string(), "http://example.com/movie.mkv".to_string());
+        movie.duration = Some(5400.0);
+
+        let live_args = args_for(&live);
+        assert!(live_args.contains(&"--demuxer-readahead-secs=5".to_string()));
+        assert!(!live_args.contains(&"--demuxer-readahead-secs=600".to_string()));
+
+        let vod_args = args_for(&movie);
+        assert!(vod_args.contains(&"--demuxer-readahead-secs=600".to_string()));
+        assert!(!vod_args.contains(&"--demuxer-readahead-secs=5".to_string()));
+    }
+
+    #[test]
+    fn test_player_env_set_on_command() {
+        let mut config = Config::default();
+        config.player.env.insert("MPV_HOME".to_string(), "/tmp/mpv".to_string());
+        config.player.env.insert("DISPLAY".to_string(), ":1".to_string());
+        let player = IptvPlayer::new("mpv".to_string(), config, false);
+        let channel = Channel::new("Alpha".to_string(), "http://example.com/alpha".to_string());
+
+        let command = player.build_player_command(&channel);
+        let mut envs: Vec<_> = command
+            .get_envs()
+            .map(|(key, value)| (key.to_string_lossy().into_owned(), value.map(|value| value.to_string_lossy().into_owned())))
+            .collect();
+        envs.sort();
+        assert_eq!(
+            envs,
+            [("DISPLAY".to_string(), Some(":1".to_string())), ("MPV_HOME".to_string(), Some("/tmp/mpv".to_string()))]
+        );
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_play_at_index() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = std::env::temp_dir().join(format!("riptv-play-at-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let played_file = dir.join("played.txt");
+        let stub = dir.join("stub-player");
+        std::fs::write(&stub, format!("#!/bin/sh\necho \"$1\" > {}\n", played_file.display())).unwrap();
+        std::fs::set_permissions(&stub, std::fs::Permissions::from_mode(0o755)).unwrap();
+
+        let mut player = IptvPlayer::new(stub.to_string_lossy().into_owned(), Config::default(), false);
+        player.capabilities = Some(PlayerCapabilities::default());
+        player
+            .parser
+            .parse_str("#EXTM3U\n#EXTINF:-1,First\nhttp://example.com/1\n#EXTINF:-1,Second\nhttp://example.com/2\n".to_string())
+            .await
+            .unwrap();
+
+        assert_eq!(player.channel_at(2).unwrap().name, "Second");
+        assert!(player.channel_at(0).is_err());
+        assert!(player.play_at(3).await.unwrap_err().to_string().contains("has 2 channels"));
+
+        player.play_at(1).await.unwrap();
+        let played = std::fs::read_to_string(&played_file).unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(played.trim(), "http://example.com/1");
+    }
+
+    #[tokio::test]
+    async fn test_export_matches_in_rank_order() {
+        let mut player = IptvPlayer::new("mpv".to_string(), Config::default(), false);
+        player
+            .parser
+            .parse_str(
+                "#EXTM3U\n#EXTINF:-1,BBC News World\nhttp://example.com/1\n#EXTINF:-1,CNN\nhttp://example.com/2\n\
+                 #EXTINF:-1,BBC News\nhttp://example.com/3\n#EXTINF:-1,Sky Sports\nhttp://example.com/4\n"
+                    .to_string(),
+            )
+            .await
+            .unwrap();
+        let ranked: Vec<String> = player.matching_channels("bbc news").iter().map(|channel| channel.url.clone()).collect();
+
+        let path = std::env::temp_dir().join(format!("riptv-search-export-{}.m3u", std::process::id()));
+        let path = path.to_string_lossy().into_owned();
+        assert_eq!(player.export_matches("bbc news", &path).unwrap(), ranked.len());
+
+        let mut exported = PlaylistParser::new(false);
+        exported.parse_file(&path).await.unwrap();
+        std::fs::remove_file(&path).unwrap();
+        let urls: Vec<String> = exported.get_channels().iter().map(|channel| channel.url.clone()).collect();
+        assert_eq!(urls, ranked);
+        assert!(!urls.contains(&"http://example.com/2".to_string()));
+    }
+
+    #[tokio::test]
+    async fn test_reload_replaces_channels() {
+        let path = std::env::temp_dir().join(format!("riptv-reload-{}.m3u", std::process::id()));
+        std::fs::write(&path, "#EXTM3U\n#EXTINF:-1,Old One\nhttp://example.com/1\n#EXTINF:-1,Old Two\nhttp://example.com/2\n").unwrap();
+
+        let mut player = IptvPlayer::new("mpv".to_string(), Config::default(), false);
+        let running = AtomicBool::new(true);
+        assert!(player.reload_playlist(&running).await.is_err());
+        player.load_playlist(&path.to_string_lossy()).await.unwrap();
+
+        std::fs::write(&path, "#EXTM3U\n#EXTINF:-1,New\nhttp://example.com/3\n").unwrap();
+        player.reload_playlist(&running).await.unwrap();
+        let names: Vec<_> = player.parser().get_channels().iter().map(|channel| channel.name.clone()).collect();
+        assert_eq!(names, ["New"]);
+        assert!(player.parser().get_channel_by_name("Old One").is_none());
+
+        // A failed reload keeps the current channels
+        std::fs::remove_file(&path).unwrap();
+        assert!(player.reload_playlist(&running).await.is_err());
+        assert_eq!(player.parser().get_channels().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_reload_appended_non_m3u() {
+        use std::io::Write;
+
+        let dir = std::env::temp_dir().join(format!("riptv-reload-formats-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let running = AtomicBool::new(true);
+        let cases = [
+            (
+                "radio.pls",
+                "[playlist]\nFile1=http://example.com/one\nTitle1=One\n",
+                "File2=http://example.com/two\nTitle2=Two\nNumberOfEntries=2\n",
+                vec!["One", "Two"],
+            ),
+            (
+                "urls.txt",
+                "http://one.example.com/live\n",
+                "http://two.example.com/live\n",
+                vec!["one.example.com", "two.example.com"],
+            ),
+        ];
+
+        for (file, initial, appended, expected) in cases {
+            let path = dir.join(file);
+            std::fs::write(&path, initial).unwrap();
+            let mut player = IptvPlayer::new("mpv".to_string(), Config::default(), false);
+            player.load_playlist(&path.to_string_lossy()).await.unwrap();
+
+            std::fs::OpenOptions::new().append(true).open(&path).unwrap().write_all(appended.as_bytes()).unwrap();
+            player.reload_playlist(&running).await.unwrap();
+            let names: Vec<_> = player.parser().get_channels().iter().map(|channel| channel.name.clone()).collect();
+            assert_eq!(names, expected, "{}", file);
+        }
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_shutdown_flushes_state() {
+        let dir = std::env::temp_dir().join(format!("riptv-shutdown-{}", std::process::id()));
+        let config_path = dir.join("config.json");
+        let config = Config { source_path: Some(config_path.clone()), ..Config::default() };
+        let mut player = IptvPlayer::new("mpv".to_string(), config, false);
+        player.parser_mut().parse_str("#EXTM3U\n#EXTINF:-1,BBC\nhttp://example.com/bbc\n".to_string()).await.unwrap();
+        player.add_favorite("BBC");
+        assert!(!config_path.exists());
+
+        // A signal cleared the flag; the session ends and saves state on the way out
+        player.run_interactive_with_shutdown(Arc::new(AtomicBool::new(false))).await.unwrap();
+        let saved = Config::load(config_path.to_str(), true).unwrap();
+        assert_eq!(saved.favorite_channels, ["BBC"]);
+        assert!(dir.join("play_stats.json").exists());
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
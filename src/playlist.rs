@@ -3,12 +3,22 @@ use colored::*;
 use indicatif::{ProgressBar, ProgressStyle};
 use regex::Regex;
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::fs;
-use std::path::Path;
-use std::time::Instant;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
 use tokio::task;
-use tracing::{debug, info};
+use tracing::{debug, error, info, warn};
+use url::Url;
+
+use crate::catchup::Catchup;
+use crate::config::{Config, NameSource};
+use crate::report::QualityReport;
+use crate::search::{ChannelSearch, FuzzySearch, SearchIndex, SearchOpts};
+use crate::sources;
+use crate::utils::{extract_domain, fnv1a, format_extvlcopt_header, natural_cmp, parse_extinf_metadata, parse_extvlcopt_header, sha256_hex, ExtinfMetadata};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Channel {
@@ -19,6 +29,21 @@ pub struct Channel {
     pub language: Option<String>,
     pub country: Option<String>,
     pub tvg_id: Option<String>,
+    /// Length in seconds from `#EXTINF`; `None` for live streams (`-1`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub duration: Option<f64>,
+    /// Original URL before `${VAR}` substitution, kept for export
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub url_template: Option<String>,
+    /// Name as written in the playlist, kept for export when name cleanup changed it
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub raw_name: Option<String>,
+    /// HTTP headers from `#EXTVLCOPT` lines, sent with this channel's stream requests
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub headers: BTreeMap<String, String>,
+    /// Archive playback settings from the `catchup*` attributes
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub catchup: Option<Catchup>,
 }
 
 impl Channel {
@@ -31,6 +56,11 @@ impl Channel {
             language: None,
             country: None,
             tvg_id: None,
+            duration: None,
+            url_template: None,
+            raw_name: None,
+            headers: BTreeMap::new(),
+            catchup: None,
         }
     }
 
@@ -51,15 +81,297 @@ impl Channel {
             language,
             country,
             tvg_id,
+            duration: None,
+            url_template: None,
+            raw_name: None,
+            headers: BTreeMap::new(),
+            catchup: None,
         }
     }
 
+    /// Build a channel from `#EXTINF` metadata, naming it from `source` first
+    fn from_extinf(metadata: ExtinfMetadata, url: String, source: NameSource) -> Self {
+        let preferred = match source {
+            NameSource::Display => Some(&metadata.display_name),
+            NameSource::TvgName => metadata.tvg_name.as_ref(),
+            NameSource::TvgId => metadata.tvg_id.as_ref(),
+        };
+        let name = [preferred, Some(&metadata.channel_name)]
+            .into_iter()
+            .flatten()
+            .find(|name| !name.trim().is_empty())
+            .cloned()
+            .unwrap_or_else(|| "Unknown Channel".to_string());
+
+        let mut channel = Self::with_metadata(
+            name,
+            url,
+            metadata.group_title,
+            metadata.tvg_logo,
+            metadata.tvg_language,
+            metadata.tvg_country,
+            metadata.tvg_id,
+        );
+        channel.duration = metadata.duration;
+        channel.catchup = Catchup::from_attributes(metadata.catchup, metadata.catchup_source, metadata.catchup_days);
+        channel
+    }
+
+    /// Whether this is on-demand content with a known length rather than a live stream
+    pub fn is_vod(&self) -> bool {
+        self.duration.is_some()
+    }
+
     pub fn display_name(&self) -> String {
         match &self.group {
             Some(group) => format!("[{}] {}", group.bright_blue(), self.name),
             None => self.name.clone(),
         }
     }
+
+    /// Identity used for equality and hashing: the stream URL
+    pub fn key(&self) -> &str {
+        &self.url
+    }
+}
+
+/// Channels are equal when they point at the same stream URL, whatever their
+/// names or metadata, so a `HashSet<Channel>` holds each stream once
+impl PartialEq for Channel {
+    fn eq(&self, other: &Self) -> bool {
+        self.key() == other.key()
+    }
+}
+
+impl Eq for Channel {}
+
+impl std::hash::Hash for Channel {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        self.key().hash(state);
+    }
+}
+
+/// Channel metadata that search can match against
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SearchField {
+    Name,
+    Group,
+    Country,
+    Language,
+    TvgId,
+}
+
+impl SearchField {
+    /// Look up a field by its `search_fields` config name
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_lowercase().as_str() {
+            "name" => Some(Self::Name),
+            "group" => Some(Self::Group),
+            "country" => Some(Self::Country),
+            "language" => Some(Self::Language),
+            "tvg_id" | "tvg-id" => Some(Self::TvgId),
+            _ => None,
+        }
+    }
+
+    /// Multiplier applied to match scores so name matches rank first
+    pub fn weight(self) -> i64 {
+        match self {
+            Self::Name => 3,
+            Self::Group => 2,
+            Self::Country | Self::Language | Self::TvgId => 1,
+        }
+    }
+
+    pub fn value(self, channel: &Channel) -> Option<&str> {
+        match self {
+            Self::Name => Some(&channel.name),
+            Self::Group => channel.group.as_deref(),
+            Self::Country => channel.country.as_deref(),
+            Self::Language => channel.language.as_deref(),
+            Self::TvgId => channel.tvg_id.as_deref(),
+        }
+    }
+}
+
+/// How search treats upper and lower case
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum CaseMode {
+    #[default]
+    Ignore,
+    Respect,
+    /// Case-insensitive unless the query contains an uppercase letter
+    Smart,
+}
+
+/// Options controlling how playlists are parsed
+#[derive(Debug, Clone, Default)]
+pub struct ParseOptions {
+    /// Number of concurrent parse tasks for parallel processing (0 = one per CPU)
+    pub parse_threads: usize,
+
+    /// Values for `${VAR}` placeholders in channel URLs, checked before the environment
+    pub url_vars: HashMap<String, String>,
+
+    /// Expected SHA-256 of the playlist file; parsing is refused on mismatch
+    pub expected_sha256: Option<String>,
+
+    /// Rules applied to channel names after parsing
+    pub name_rules: Vec<NameRule>,
+
+    /// Expand HLS master playlists into per-variant channels
+    pub expand_hls: bool,
+
+    /// Only keep channels whose group matches one of these, when non-empty
+    pub include_groups: Vec<GroupPattern>,
+
+    /// Drop channels whose group matches one of these
+    pub exclude_groups: Vec<GroupPattern>,
+
+    /// Raw group names mapped to the canonical group they are merged into
+    pub group_aliases: HashMap<String, String>,
+
+    /// Parsing fails once more channels than this are found (0 = no limit)
+    pub max_channels: usize,
+
+    /// Display names keyed by stream URL, applied after name cleanup
+    pub name_overrides: HashMap<String, String>,
+
+    /// Only keep `http(s)` streams, dropping local files, rtmp and other schemes
+    pub strict_url: bool,
+
+    /// Which `#EXTINF` field channel names are taken from first
+    pub name_source: NameSource,
+}
+
+/// A group name or `*`/`?` glob, matched case-insensitively
+#[derive(Debug, Clone)]
+pub struct GroupPattern(Regex);
+
+impl GroupPattern {
+    pub fn new(pattern: &str) -> Self {
+        let regex = regex::escape(pattern).replace(r"\*", ".*").replace(r"\?", ".");
+        Self(Regex::new(&format!("(?i)^{}$", regex)).expect("escaped glob is a valid regex"))
+    }
+
+    pub fn matches(&self, group: &str) -> bool {
+        self.0.is_match(group)
+    }
+}
+
+/// A compiled channel-name cleanup rule
+#[derive(Debug, Clone)]
+pub struct NameRule {
+    pattern: Regex,
+    replacement: String,
+}
+
+impl NameRule {
+    pub fn new(pattern: &str, replacement: &str) -> Result<Self> {
+        Ok(Self {
+            pattern: Regex::new(pattern)
+                .with_context(|| format!("Invalid name cleanup pattern '{}'", pattern))?,
+            replacement: replacement.to_string(),
+        })
+    }
+}
+
+/// Apply cleanup rules in order and trim the result, keeping the original if nothing is left
+pub fn clean_name(name: &str, rules: &[NameRule]) -> String {
+    let cleaned = rules.iter().fold(name.to_string(), |name, rule| {
+        rule.pattern.replace_all(&name, rule.replacement.as_str()).into_owned()
+    });
+
+    match cleaned.trim() {
+        "" => name.to_string(),
+        cleaned => cleaned.to_string(),
+    }
+}
+
+/// Set a display name, keeping the playlist's own name for export
+fn rename(channel: &mut Channel, name: &str) {
+    if channel.name != name {
+        let original = std::mem::replace(&mut channel.name, name.to_string());
+        channel.raw_name.get_or_insert(original);
+    }
+}
+
+impl ParseOptions {
+    pub fn from_config(config: &Config) -> Self {
+        Self {
+            parse_threads: config.parse_threads,
+            url_vars: config.url_vars.clone(),
+            expected_sha256: None,
+            name_rules: Self::name_rules(config),
+            expand_hls: false,
+            include_groups: config.groups.include.iter().map(|pattern| GroupPattern::new(pattern)).collect(),
+            exclude_groups: config.groups.exclude.iter().map(|pattern| GroupPattern::new(pattern)).collect(),
+            group_aliases: config.group_aliases.clone(),
+            max_channels: config.max_channels,
+            name_overrides: config.name_overrides.clone(),
+            strict_url: config.strict_url,
+            name_source: NameSource::from_name(&config.name_source).unwrap_or_default(),
+        }
+    }
+
+    fn name_rules(config: &Config) -> Vec<NameRule> {
+        if !config.name_cleanup.enabled {
+            return Vec::new();
+        }
+
+        config.name_cleanup.rules
+            .iter()
+            .filter_map(|rule| match NameRule::new(&rule.pattern, &rule.replacement) {
+                Ok(rule) => Some(rule),
+                Err(e) => {
+                    warn!("⚠️ Skipping name cleanup rule: {:#}", e);
+                    None
+                }
+            })
+            .collect()
+    }
+
+    /// Fail once `count` channels is over the configured cap
+    fn check_channel_cap(&self, count: usize) -> Result<()> {
+        if self.max_channels > 0 && count > self.max_channels {
+            anyhow::bail!(
+                "Playlist has more than {} channels (max_channels); refusing to parse the rest",
+                self.max_channels
+            );
+        }
+        Ok(())
+    }
+
+    /// Expand URL variables, then apply name cleanup, group aliases and name overrides
+    fn prepare(&self, channel: &mut Channel) -> Result<()> {
+        if channel.url.contains("${") {
+            let resolved = expand_url_vars(&channel.url, &self.url_vars)?;
+            channel.url_template = Some(std::mem::replace(&mut channel.url, resolved));
+        }
+
+        if !self.name_rules.is_empty() {
+            let cleaned = clean_name(&channel.name, &self.name_rules);
+            if cleaned != channel.name {
+                channel.raw_name = Some(std::mem::replace(&mut channel.name, cleaned));
+            }
+        }
+
+        if let Some(canonical) = channel.group.as_ref().and_then(|group| self.group_aliases.get(group)) {
+            channel.group = Some(canonical.clone());
+        }
+
+        if let Some(name) = self.name_overrides.get(&channel.url) {
+            rename(channel, name);
+        }
+        Ok(())
+    }
+
+    fn thread_count(&self) -> usize {
+        match self.parse_threads {
+            0 => std::thread::available_parallelism().map(|n| n.get()).unwrap_or(1),
+            n => n,
+        }
+    }
 }
 
 pub struct PlaylistParser {
@@ -67,30 +379,234 @@ pub struct PlaylistParser {
     channel_map: HashMap<String, usize>,
     groups: HashMap<String, Vec<usize>>,
     parallel_processing: bool,
+    options: ParseOptions,
+    search_opts: SearchOpts,
+    search_backend: Box<dyn ChannelSearch>,
+    search_index: SearchIndex,
+    /// Final URL of a downloaded playlist, after redirects; relative entries resolve against it
+    base_url: Option<Url>,
+    favorites: HashSet<String>,
+    favorite_boost: i64,
+    min_score: i64,
+    anchored: bool,
+    natural_sort: bool,
+    tail: Option<ParsedTail>,
+}
+
+/// How far a local playlist file has been parsed, so appended entries can be read alone
+#[derive(Debug, Clone)]
+struct ParsedTail {
+    path: PathBuf,
+    /// End of the last complete entry
+    offset: usize,
+    /// FNV-1a of the bytes before `offset`, to notice files rewritten rather than appended to
+    prefix_hash: u64,
+}
+
+impl ParsedTail {
+    fn new(path: &Path, bytes: &[u8], from: usize) -> Self {
+        let offset = complete_entries_end(bytes, from);
+        Self {
+            path: path.to_path_buf(),
+            offset,
+            prefix_hash: fnv1a(&bytes[..offset]),
+        }
+    }
+}
+
+/// Byte offset just past the last URL line in `bytes[from..]` that ends with a newline, or `from`
+fn complete_entries_end(bytes: &[u8], from: usize) -> usize {
+    let mut end = from;
+    let mut line_start = from;
+    for (idx, _) in bytes.iter().enumerate().skip(from).filter(|(_, byte)| **byte == b'\n') {
+        let line = bytes[line_start..idx].trim_ascii();
+        if !line.is_empty() && !line.starts_with(b"#") {
+            end = idx + 1;
+        }
+        line_start = idx + 1;
+    }
+    end
 }
 
 impl PlaylistParser {
     pub fn new(parallel_processing: bool) -> Self {
+        Self::with_options(parallel_processing, ParseOptions::default())
+    }
+
+    pub fn with_options(parallel_processing: bool, options: ParseOptions) -> Self {
         Self {
             channels: Vec::new(),
             channel_map: HashMap::new(),
             groups: HashMap::new(),
             parallel_processing,
+            options,
+            search_opts: SearchOpts::default(),
+            search_backend: Box::new(FuzzySearch),
+            search_index: SearchIndex::default(),
+            base_url: None,
+            favorites: HashSet::new(),
+            favorite_boost: 0,
+            min_score: 0,
+            anchored: false,
+            natural_sort: false,
+            tail: None,
         }
     }
 
+    /// Choose which metadata fields `search_scored` matches against
+    pub fn set_search_fields(&mut self, fields: Vec<SearchField>) {
+        self.search_opts.fields = if fields.is_empty() { vec![SearchField::Name] } else { fields };
+    }
+
     pub async fn parse_file<P: AsRef<Path>>(&mut self, path: P) -> Result<()> {
         let path = path.as_ref();
         info!("📂 Loading playlist: {}", path.display());
 
-        let content = fs::read_to_string(path)
+        let bytes = fs::read(path)
             .with_context(|| format!("Failed to read playlist file: {}", path.display()))?;
 
-        if self.parallel_processing {
+        // Only #EXTM3U playlists can be refreshed from their appended bytes; other formats reparse in full
+        let tail = is_extm3u(path, &bytes).then(|| ParsedTail::new(path, &bytes, 0));
+        self.parse_bytes(bytes, path).await?;
+        self.tail = tail;
+        Ok(())
+    }
+
+    /// Parse only the entries appended to `path` since it was last parsed, returning how many were added
+    ///
+    /// Returns `None` when the file wasn't the last one parsed, or was rewritten rather than
+    /// appended to; parse it in full then.
+    pub fn parse_appended<P: AsRef<Path>>(&mut self, path: P) -> Result<Option<usize>> {
+        let path = path.as_ref();
+        let Some(tail) = self.tail.as_ref().filter(|tail| tail.path == path) else {
+            return Ok(None);
+        };
+        if self.options.expected_sha256.is_some() {
+            return Ok(None);
+        }
+
+        let bytes = fs::read(path)
+            .with_context(|| format!("Failed to read playlist file: {}", path.display()))?;
+        if bytes.len() < tail.offset || fnv1a(&bytes[..tail.offset]) != tail.prefix_hash {
+            debug!("{} changed before the last parsed entry, parsing it in full", path.display());
+            return Ok(None);
+        }
+
+        let appended = ParsedTail::new(path, &bytes, tail.offset);
+        let content = std::str::from_utf8(&bytes[tail.offset..appended.offset])
+            .with_context(|| format!("Playlist file is not valid UTF-8: {}", path.display()))?;
+        let mut channels = parse_m3u_entries(content, self.base_url.as_ref(), self.options.name_source);
+        self.options.check_channel_cap(self.channels.len() + channels.len())?;
+        for channel in &mut channels {
+            self.options.prepare(channel)?;
+        }
+
+        let before = self.channels.len();
+        self.append_channels(channels);
+        self.tail = Some(appended);
+        info!("➕ Parsed {} appended channels from {}", self.channels.len() - before, path.display());
+        Ok(Some(self.channels.len() - before))
+    }
+
+    /// Parse raw playlist bytes, with `path` used for messages and format sniffing
+    pub async fn parse_bytes(&mut self, bytes: Vec<u8>, path: &Path) -> Result<()> {
+        self.tail = None;
+        if let Some(expected) = &self.options.expected_sha256 {
+            verify_sha256(&bytes, expected)?;
+        }
+
+        let content = String::from_utf8(bytes)
+            .with_context(|| format!("Playlist file is not valid UTF-8: {}", path.display()))?;
+        let content = strip_bom(content);
+
+        if sources::pls::is_pls(path, &content) {
+            info!("📻 Detected PLS playlist format");
+            self.channels = sources::pls::parse(&content);
+            return self.finish_parse();
+        }
+
+        if sources::hls::is_master(&content) {
+            if self.options.expand_hls {
+                info!("🎞️ Expanding HLS master playlist into variant channels");
+                let title = path.file_stem().map(|stem| stem.to_string_lossy().into_owned()).unwrap_or_default();
+                let source = path.to_string_lossy();
+                self.channels = sources::hls::parse_master(&content, &title, Some(&source));
+                return self.finish_parse();
+            }
+            warn!("⚠️ This looks like an HLS master playlist; use --expand-hls to list its variants");
+        }
+
+        self.parse_str(content).await
+    }
+
+    /// Turn HLS master playlists into one channel per variant stream
+    pub fn set_expand_hls(&mut self, expand: bool) {
+        self.options.expand_hls = expand;
+    }
+
+    /// Resolve relative channel URLs against `url`, usually where a download ended up after redirects
+    pub fn set_base_url(&mut self, url: Option<Url>) {
+        self.base_url = url;
+    }
+
+    /// URL relative channel URLs are resolved against, if the playlist was downloaded
+    #[cfg(test)]
+    pub fn base_url(&self) -> Option<&Url> {
+        self.base_url.as_ref()
+    }
+
+    /// Require the playlist file to match this SHA-256 digest before parsing
+    pub fn set_expected_sha256(&mut self, digest: Option<String>) {
+        self.options.expected_sha256 = digest;
+    }
+
+    /// Parse playlist content that has already been read into memory
+    pub async fn parse_str(&mut self, content: String) -> Result<()> {
+        self.parse_str_with_timings(content, false).await.map(|_| ())
+    }
+
+    /// Like `parse_str`, also returning how long each stage took when `timed` is set
+    pub async fn parse_str_with_timings(&mut self, content: String, timed: bool) -> Result<Option<ParseTimings>> {
+        let start = Instant::now();
+        let content = strip_bom(content);
+        let pls = sources::pls::is_pls(Path::new(""), &content);
+        let mut bare = false;
+        if !pls {
+            let first_line = content.lines().map(str::trim).find(|line| !line.is_empty()).unwrap_or("");
+            if !first_line.starts_with("#EXTM3U") {
+                warn!("⚠️ Playlist has no #EXTM3U header, parsing anyway");
+                bare = is_stream_url(first_line) && !content.contains("#EXTINF");
+            }
+        }
+        let read = start.elapsed();
+
+        if pls {
+            self.channels = sources::pls::parse(&content);
+        } else if bare {
+            info!("🔗 Detected bare URL list");
+            self.channels = parse_bare_urls(&content);
+        } else if self.parallel_processing {
             self.parse_parallel(content).await?;
         } else {
             self.parse_sequential(content)?;
         }
+        let parsed = start.elapsed();
+
+        self.finish_parse()?;
+        let total = start.elapsed();
+
+        Ok(timed.then(|| ParseTimings { read, regex: parsed - read, index_build: total - parsed, total }))
+    }
+
+    /// Post-process freshly parsed channels and rebuild the lookup indices
+    fn finish_parse(&mut self) -> Result<()> {
+        let channels = std::mem::take(&mut self.channels);
+        self.channels = channels.into_iter().filter(|channel| self.url_allowed(channel)).collect();
+        self.options.check_channel_cap(self.channels.len())?;
+
+        for channel in &mut self.channels {
+            self.options.prepare(channel)?;
+        }
 
         self.build_indices();
         Ok(())
@@ -98,7 +614,8 @@ impl PlaylistParser {
 
     async fn parse_parallel(&mut self, content: String) -> Result<()> {
         let start = Instant::now();
-        info!("🚀 Using parallel processing for maximum speed...");
+        let threads = self.options.thread_count();
+        info!("🚀 Using parallel processing across {} tasks...", threads);
 
         // Create progress bar
         let pb = ProgressBar::new_spinner();
@@ -109,72 +626,26 @@ impl PlaylistParser {
         );
         pb.set_message("Parsing playlist...");
 
-        let channels = task::spawn_blocking(move || {
-            let lines: Vec<&str> = content.lines().collect();
-            let mut channels = Vec::with_capacity(100_000);
-            
-            // Regex for parsing EXTINF metadata
-            let extinf_regex = Regex::new(
-                r#"#EXTINF:([^,]*),(?:.*tvg-name="([^"]*)")?(?:.*tvg-logo="([^"]*)")?(?:.*group-title="([^"]*)")?(?:.*tvg-language="([^"]*)")?(?:.*tvg-country="([^"]*)")?(?:.*tvg-id="([^"]*)")?(.*)$"#
-            ).unwrap();
-
-            let mut i = 1;
-            let total_lines = lines.len();
-
-            while i < total_lines {
-                if let Some(line) = lines.get(i) {
-                    if line.starts_with("#EXTINF:") {
-                        if let Some(captures) = extinf_regex.captures(line) {
-                            // Extract metadata
-                            let tvg_name = captures.get(2).map(|m| m.as_str().to_string());
-                            let logo = captures.get(3).map(|m| m.as_str().to_string());
-                            let group = captures.get(4).map(|m| m.as_str().to_string());
-                            let language = captures.get(5).map(|m| m.as_str().to_string());
-                            let country = captures.get(6).map(|m| m.as_str().to_string());
-                            let tvg_id = captures.get(7).map(|m| m.as_str().to_string());
-                            
-                            // Channel name is everything after the last comma
-                            let name_part = captures.get(8)
-                                .map(|m| m.as_str().trim())
-                                .unwrap_or("Unknown Channel");
-                            
-                            let channel_name = tvg_name.unwrap_or_else(|| name_part.to_string());
-
-                            // Get URL from previous line
-                            if let Some(url_line) = lines.get(i - 1) {
-                                if url_line.starts_with("http") {
-                                    channels.push(Channel::with_metadata(
-                                        channel_name,
-                                        url_line.trim().to_string(),
-                                        group,
-                                        logo,
-                                        language,
-                                        country,
-                                        tvg_id,
-                                    ));
-                                }
-                            }
-                        } else {
-                            // Fallback parsing for simple format
-                            if let Some(comma_pos) = line.find(',') {
-                                let name = line[comma_pos + 1..].trim();
-                                if let Some(url_line) = lines.get(i - 1) {
-                                    if url_line.starts_with("http") {
-                                        channels.push(Channel::new(
-                                            name.to_string(),
-                                            url_line.trim().to_string(),
-                                        ));
-                                    }
-                                }
-                            }
-                        }
-                    }
-                }
-                i += 1;
-            }
+        // Each chunk starts at an #EXTINF line so no entry is split between tasks
+        let content: Arc<str> = Arc::from(content);
+        let handles: Vec<_> = entry_chunks(&content, threads)
+            .into_iter()
+            .map(|range| {
+                let content = Arc::clone(&content);
+                let (base, name_source) = (self.base_url.clone(), self.options.name_source);
+                task::spawn_blocking(move || parse_m3u_entries(&content[range], base.as_ref(), name_source))
+            })
+            .collect();
 
-            channels
-        }).await?;
+        // Merge in chunk order to preserve playlist order
+        let mut channels = Vec::new();
+        for handle in handles {
+            channels.extend(handle.await?);
+            if let Err(e) = self.options.check_channel_cap(channels.len()) {
+                pb.abandon_with_message("❌ Too many channels");
+                return Err(e);
+            }
+        }
 
         pb.finish_with_message("✅ Parsing complete!");
 
@@ -196,40 +667,24 @@ impl PlaylistParser {
         let start = Instant::now();
         info!("📝 Using sequential processing...");
 
-        let lines: Vec<&str> = content.lines().collect();
-        let mut channels = Vec::with_capacity(50_000);
+        let chunks = entry_chunks(&content, content.len() / SEQUENTIAL_CHUNK_BYTES + 1);
+        let mut channels = Vec::new();
 
-        let pb = ProgressBar::new(lines.len() as u64);
+        let pb = ProgressBar::new(chunks.len() as u64);
         pb.set_style(
             ProgressStyle::default_bar()
                 .template("{bar:40.cyan/blue} {pos:>7}/{len:7} {msg}")
                 .unwrap(),
         );
 
-        let mut i = 1;
-        while i < lines.len() {
-            if let Some(line) = lines.get(i) {
-                if line.starts_with("#EXTINF:") {
-                    if let Some(comma_pos) = line.find(',') {
-                        let name = line[comma_pos + 1..].trim();
-                        if let Some(url_line) = lines.get(i - 1) {
-                            if url_line.starts_with("http") {
-                                channels.push(Channel::new(
-                                    name.to_string(),
-                                    url_line.trim().to_string(),
-                                ));
-                            }
-                        }
-                    }
-                }
+        for range in chunks {
+            channels.extend(parse_m3u_entries(&content[range], self.base_url.as_ref(), self.options.name_source));
+            if let Err(e) = self.options.check_channel_cap(channels.len()) {
+                pb.abandon_with_message("❌ Too many channels");
+                return Err(e);
             }
-            
-            if i % 1000 == 0 {
-                pb.set_position(i as u64);
-                pb.set_message(format!("Found {} channels", channels.len()));
-            }
-            
-            i += 1;
+            pb.inc(1);
+            pb.set_message(format!("Found {} channels", channels.len()));
         }
 
         pb.finish_with_message("✅ Parsing complete!");
@@ -241,26 +696,124 @@ impl PlaylistParser {
         Ok(())
     }
 
+    /// Whether the include/exclude group rules keep this channel
+    fn group_allowed(&self, channel: &Channel) -> bool {
+        let group = channel.group.as_deref().unwrap_or_default();
+        let matches = |patterns: &[GroupPattern]| patterns.iter().any(|pattern| pattern.matches(group));
+
+        (self.options.include_groups.is_empty() || matches(&self.options.include_groups))
+            && !matches(&self.options.exclude_groups)
+    }
+
+    /// Whether `strict_url` keeps this channel, warning about any it drops
+    fn url_allowed(&self, channel: &Channel) -> bool {
+        let keep = !self.options.strict_url || is_http_url(&channel.url);
+        if !keep {
+            warn!("⚠️ Dropping non-http channel '{}' (strict_url): {}", channel.name, channel.url);
+        }
+        keep
+    }
+
     fn build_indices(&mut self) {
         info!("🔗 Building search indices...");
-        
-        // Build channel name -> index map
-        for (idx, channel) in self.channels.iter().enumerate() {
-            self.channel_map.insert(channel.name.clone(), idx);
+
+        let before = self.channels.len();
+        let channels = std::mem::take(&mut self.channels);
+        self.channels = channels.into_iter().filter(|channel| self.group_allowed(channel)).collect();
+        if self.channels.len() < before {
+            info!("🚫 Filtered out {} channels by group rules", (before - self.channels.len()).to_string().bright_yellow());
         }
 
-        // Build group -> channel indices map
-        for (idx, channel) in self.channels.iter().enumerate() {
+        self.channel_map.clear();
+        self.groups.clear();
+        self.search_index.clear();
+        self.index_from(0);
+
+        debug!("Built indices for {} channels and {} groups", 
+               self.channels.len(), self.groups.len());
+    }
+
+    /// Add channel name, group and search lookups for `self.channels[start..]`
+    fn index_from(&mut self, start: usize) {
+        self.search_index.extend(&self.channels);
+        for (idx, channel) in self.channels.iter().enumerate().skip(start) {
+            self.channel_map.insert(channel.name.clone(), idx);
+
             if let Some(group) = &channel.group {
-                self.groups
-                    .entry(group.clone())
-                    .or_insert_with(Vec::new)
-                    .push(idx);
+                self.groups.entry(group.clone()).or_default().push(idx);
             }
         }
+    }
 
-        debug!("Built indices for {} channels and {} groups", 
-               self.channels.len(), self.groups.len());
+    /// Each logo URL used by the playlist, once, in first-use order
+    pub fn distinct_logos(&self) -> Vec<String> {
+        let mut seen = HashSet::new();
+        self.channels
+            .iter()
+            .filter_map(|channel| channel.logo.clone())
+            .filter(|logo| seen.insert(logo.clone()))
+            .collect()
+    }
+
+    /// Replace or strip logos as `fixes` maps them, returning how many channels changed
+    pub fn update_logos(&mut self, fixes: &HashMap<String, Option<String>>) -> usize {
+        let mut changed = 0;
+        for channel in &mut self.channels {
+            if let Some(fix) = channel.logo.as_ref().and_then(|logo| fixes.get(logo)) {
+                channel.logo = fix.clone();
+                changed += 1;
+            }
+        }
+        changed
+    }
+
+    /// Give every channel streaming from `url` a new display name, returning how many changed
+    pub fn rename_channels(&mut self, url: &str, name: &str) -> usize {
+        self.options.name_overrides.insert(url.to_string(), name.to_string());
+        let mut changed = 0;
+        for channel in self.channels.iter_mut().filter(|channel| channel.url == url) {
+            rename(channel, name);
+            changed += 1;
+        }
+        self.build_indices();
+        changed
+    }
+
+    /// Append channels, indexing only the new ones; group rules and `strict_url` still apply
+    pub fn append_channels(&mut self, new: Vec<Channel>) {
+        let start = self.channels.len();
+        let allowed: Vec<Channel> = new
+            .into_iter()
+            .filter(|channel| self.group_allowed(channel) && self.url_allowed(channel))
+            .collect();
+        self.channels.extend(allowed);
+        self.index_from(start);
+
+        debug!("Appended {} channels ({} total)", self.channels.len() - start, self.channels.len());
+    }
+
+    /// Export every channel in the format `path`'s extension names
+    pub fn export<P: AsRef<Path>>(&self, path: P) -> Result<ExportFormat> {
+        let path = path.as_ref();
+        let format = ExportFormat::from_path(path)?;
+        match format {
+            ExportFormat::M3u => self.export_m3u(path)?,
+            ExportFormat::Json => self.export_json(path)?,
+            ExportFormat::Csv => self.export_csv(path)?,
+        }
+        Ok(format)
+    }
+
+    pub fn export_m3u<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        write_m3u(&self.channels, path)
+    }
+
+    pub fn export_json<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        write_json(&self.channels, path)
+    }
+
+    pub fn export_csv<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        write_csv(&self.channels, path)
     }
 
     pub fn get_channels(&self) -> &[Channel] {
@@ -272,6 +825,17 @@ impl PlaylistParser {
             .and_then(|&idx| self.channels.get(idx))
     }
 
+    /// Counts, duplicates and missing or invalid metadata across every channel
+    pub fn quality_report(&self) -> QualityReport {
+        QualityReport::from_channels(&self.channels)
+    }
+
+    /// The playlist's spelling of `group`, matched ignoring case
+    pub fn find_group(&self, group: &str) -> Option<&str> {
+        let group = group.trim();
+        self.groups.keys().find(|name| name.eq_ignore_ascii_case(group)).map(String::as_str)
+    }
+
     pub fn get_channels_by_group(&self, group: &str) -> Vec<&Channel> {
         self.groups.get(group)
             .map(|indices| {
@@ -283,20 +847,89 @@ impl PlaylistParser {
     }
 
     pub fn search_channels(&self, query: &str) -> Vec<&Channel> {
-        use fuzzy_matcher::{FuzzyMatcher, skim::SkimMatcherV2};
-        
-        let matcher = SkimMatcherV2::default();
-        let mut matches: Vec<(i64, &Channel)> = self.channels
-            .iter()
-            .filter_map(|channel| {
-                matcher.fuzzy_match(&channel.name, query)
-                    .map(|score| (score, channel))
+        self.search_scored(query)
+            .into_iter()
+            .map(|(_, idx)| &self.channels[idx])
+            .collect()
+    }
+
+    /// Choose how search handles letter case
+    pub fn set_case_mode(&mut self, case_mode: CaseMode) {
+        self.search_opts.case_mode = case_mode;
+    }
+
+    /// Match queries with `backend` instead of the default fuzzy search
+    pub fn set_search_backend(&mut self, backend: Box<dyn ChannelSearch>) {
+        self.search_backend = backend;
+    }
+
+    /// Add `boost` to the search score of channels named in `favorites`
+    pub fn set_favorites(&mut self, favorites: impl IntoIterator<Item = String>, boost: i64) {
+        self.favorites = favorites.into_iter().collect();
+        self.favorite_boost = boost;
+    }
+
+    /// Discard matches whose score is below `min_score`
+    pub fn set_min_score(&mut self, min_score: i64) {
+        self.min_score = min_score;
+    }
+
+    /// Only match channels whose name starts with the query, ignoring case
+    pub fn set_anchored(&mut self, anchored: bool) {
+        self.anchored = anchored;
+    }
+
+    /// Lowercased names and prefix map reused across searches until the channels change
+    #[cfg(test)]
+    pub fn search_index(&self) -> &SearchIndex {
+        &self.search_index
+    }
+
+    /// Search with the configured backend, returning `(score, channel index)` pairs, best match first
+    pub fn search_scored(&self, query: &str) -> Vec<(i64, usize)> {
+        let anchored = self.anchored.then(|| self.search_index.with_prefix(&query.to_lowercase()));
+        let mut matches: Vec<(i64, usize)> = self.search_backend
+            .search_indexed(&self.channels, &self.search_index, query, &self.search_opts)
+            .into_iter()
+            .filter(|&(score, _)| score >= self.min_score)
+            .filter(|(_, idx)| anchored.as_ref().is_none_or(|prefixed| prefixed.binary_search(idx).is_ok()))
+            .map(|(score, idx)| {
+                let boost = if self.favorites.contains(&self.channels[idx].name) { self.favorite_boost } else { 0 };
+                (score + boost, idx)
             })
             .collect();
 
         // Sort by score (higher is better)
         matches.sort_by(|a, b| b.0.cmp(&a.0));
-        matches.into_iter().map(|(_, channel)| channel).collect()
+        matches
+    }
+
+    /// The best-scoring channel for `query` among those `keep` accepts, if any match
+    pub fn top_match(&self, query: &str, keep: impl Fn(&Channel) -> bool) -> Option<&Channel> {
+        self.search_scored(query)
+            .into_iter()
+            .map(|(_, idx)| &self.channels[idx])
+            .find(|channel| keep(channel))
+    }
+
+    /// Group names with their channel counts, sorted by name
+    pub fn group_summary(&self) -> Vec<(String, usize)> {
+        let mut summary: Vec<(String, usize)> = self.groups
+            .iter()
+            .map(|(group, indices)| (group.clone(), indices.len()))
+            .collect();
+
+        if self.natural_sort {
+            summary.sort_by(|(a, _), (b, _)| natural_cmp(a, b));
+        } else {
+            summary.sort_by_cached_key(|(group, _)| (group.to_lowercase(), group.clone()));
+        }
+        summary
+    }
+
+    /// Sort group names with numbers by value, so "Sports 2" lists before "Sports 10"
+    pub fn set_natural_sort(&mut self, natural: bool) {
+        self.natural_sort = natural;
     }
 
     pub fn get_statistics(&self) -> PlaylistStats {
@@ -324,11 +957,1100 @@ impl PlaylistParser {
     }
 }
 
-#[derive(Debug, Default)]
+/// Approximate amount of content handled between progress updates when parsing sequentially
+const SEQUENTIAL_CHUNK_BYTES: usize = 64 * 1024;
+
+/// Split content into at most `count` byte ranges that each start at an `#EXTINF` line
+fn entry_chunks(content: &str, count: usize) -> Vec<Range<usize>> {
+    let mut ranges = Vec::with_capacity(count);
+    let mut start = 0;
+
+    for k in 1..count {
+        let mut target = content.len() * k / count;
+        if target <= start {
+            continue;
+        }
+        while !content.is_char_boundary(target) {
+            target += 1;
+        }
+
+        match content[target..].find("\n#EXTINF") {
+            Some(offset) => {
+                let end = target + offset + 1;
+                ranges.push(start..end);
+                start = end;
+            }
+            None => break,
+        }
+    }
+
+    ranges.push(start..content.len());
+    ranges
+}
+
+/// Bucket a channel is listed under when browsing alphabetically
+///
+/// The first letter of the name, upper-cased, or `#` for names starting with a digit or
+/// without any letter. Accented and non-Latin letters keep their own bucket.
+pub fn initial(name: &str) -> char {
+    name.chars()
+        .find(|c| c.is_alphanumeric())
+        .filter(|c| c.is_alphabetic())
+        .and_then(|c| c.to_uppercase().next())
+        .unwrap_or('#')
+}
+
+/// Indices into `channels` grouped by `initial`, in channel order, for jumping to a letter like a contacts list
+pub fn index_by_initial(channels: &[Channel]) -> BTreeMap<char, Vec<usize>> {
+    let mut index: BTreeMap<char, Vec<usize>> = BTreeMap::new();
+    for (idx, channel) in channels.iter().enumerate() {
+        index.entry(initial(&channel.name)).or_default().push(idx);
+    }
+    index
+}
+
+/// Check playlist bytes against an expected SHA-256 hex digest
+pub fn verify_sha256(bytes: &[u8], expected: &str) -> Result<()> {
+    let computed = sha256_hex(bytes);
+    let expected = expected.trim().to_lowercase();
+
+    if computed != expected {
+        error!("SHA-256 mismatch: expected {}, computed {}", expected, computed);
+        anyhow::bail!("Playlist integrity check failed: expected SHA-256 {}, got {}", expected, computed);
+    }
+
+    info!("🔒 SHA-256 verified: {}", computed);
+    Ok(())
+}
+
+/// Render channels as an extended M3U playlist
+///
+/// Original names and `${VAR}` URL templates are written back, so exports don't leak secrets.
+pub fn to_m3u(channels: &[Channel]) -> String {
+    let mut out = String::from("#EXTM3U\n");
+
+    for channel in channels {
+        let mut attributes = String::new();
+        let fields = [
+            ("tvg-id", &channel.tvg_id),
+            ("tvg-logo", &channel.logo),
+            ("tvg-language", &channel.language),
+            ("tvg-country", &channel.country),
+            ("group-title", &channel.group),
+        ];
+        let catchup_days = channel.catchup.as_ref().and_then(|catchup| catchup.days).map(|days| days.to_string());
+        let catchup_fields = [
+            ("catchup", &channel.catchup.as_ref().map(|catchup| catchup.mode.clone())),
+            ("catchup-source", &channel.catchup.as_ref().and_then(|catchup| catchup.source.clone())),
+            ("catchup-days", &catchup_days),
+        ];
+        for (key, value) in fields.into_iter().chain(catchup_fields) {
+            if let Some(value) = value {
+                attributes.push_str(&format!(" {}=\"{}\"", key, value.replace('"', "'")));
+            }
+        }
+
+        let duration = channel.duration.map_or("-1".to_string(), |duration| duration.to_string());
+        let name = channel.raw_name.as_deref().unwrap_or(&channel.name);
+        out.push_str(&format!("#EXTINF:{}{},{}\n", duration, attributes, name));
+
+        for (header, value) in &channel.headers {
+            if let Some(line) = format_extvlcopt_header(header, value) {
+                out.push_str(&line);
+                out.push('\n');
+            }
+        }
+
+        out.push_str(channel.url_template.as_deref().unwrap_or(&channel.url));
+        out.push('\n');
+    }
+
+    out
+}
+
+/// Write channels to `path` as an extended M3U playlist
+pub fn write_m3u<P: AsRef<Path>>(channels: &[Channel], path: P) -> Result<()> {
+    let path = path.as_ref();
+    fs::write(path, to_m3u(channels))
+        .with_context(|| format!("Failed to write playlist: {}", path.display()))
+}
+
+/// Write channels to `path` as pretty-printed JSON
+pub fn write_json<P: AsRef<Path>>(channels: &[Channel], path: P) -> Result<()> {
+    let path = path.as_ref();
+    let json = serde_json::to_string_pretty(channels)?;
+    fs::write(path, json + "\n").with_context(|| format!("Failed to write JSON export: {}", path.display()))
+}
+
+/// Render channels as CSV with a header row
+pub fn to_csv(channels: &[Channel]) -> String {
+    let field = |value: &str| {
+        if value.contains([',', '"', '\n', '\r']) {
+            format!("\"{}\"", value.replace('"', "\"\""))
+        } else {
+            value.to_string()
+        }
+    };
+
+    let mut out = String::from("name,url,group,tvg_id,country,language,logo\n");
+    for channel in channels {
+        let row = [
+            Some(channel.name.as_str()),
+            Some(channel.url.as_str()),
+            channel.group.as_deref(),
+            channel.tvg_id.as_deref(),
+            channel.country.as_deref(),
+            channel.language.as_deref(),
+            channel.logo.as_deref(),
+        ]
+        .map(|value| field(value.unwrap_or_default()));
+        out.push_str(&row.join(","));
+        out.push('\n');
+    }
+    out
+}
+
+/// Write channels to `path` as CSV
+pub fn write_csv<P: AsRef<Path>>(channels: &[Channel], path: P) -> Result<()> {
+    let path = path.as_ref();
+    fs::write(path, to_csv(channels)).with_context(|| format!("Failed to write CSV export: {}", path.display()))
+}
+
+/// File formats `--export` can write, chosen by extension
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportFormat {
+    M3u,
+    Json,
+    Csv,
+}
+
+impl ExportFormat {
+    pub fn from_path(path: &Path) -> Result<Self> {
+        let extension = path.extension().and_then(|ext| ext.to_str()).unwrap_or_default();
+        match extension.to_ascii_lowercase().as_str() {
+            "m3u" | "m3u8" => Ok(Self::M3u),
+            "json" => Ok(Self::Json),
+            "csv" => Ok(Self::Csv),
+            _ => anyhow::bail!(
+                "Can't tell the export format of '{}': use a .m3u, .m3u8, .json or .csv file name",
+                path.display()
+            ),
+        }
+    }
+}
+
+/// Write channels to `path` in the format its extension names
+pub fn export_channels<P: AsRef<Path>>(channels: &[Channel], path: P) -> Result<ExportFormat> {
+    let path = path.as_ref();
+    let format = ExportFormat::from_path(path)?;
+    match format {
+        ExportFormat::M3u => write_m3u(channels, path)?,
+        ExportFormat::Json => write_json(channels, path)?,
+        ExportFormat::Csv => write_csv(channels, path)?,
+    }
+    Ok(format)
+}
+
+/// Replace `${VAR}` placeholders in a URL from `vars`, falling back to the environment
+pub fn expand_url_vars(url: &str, vars: &HashMap<String, String>) -> Result<String> {
+    let mut expanded = String::with_capacity(url.len());
+    let mut rest = url;
+
+    while let Some(start) = rest.find("${") {
+        let Some(len) = rest[start + 2..].find('}') else {
+            break;
+        };
+        let name = &rest[start + 2..start + 2 + len];
+
+        let value = match vars.get(name) {
+            Some(value) => value.clone(),
+            None => std::env::var(name).with_context(|| {
+                format!("Undefined variable '${{{}}}' in channel URL: {} (set it in url_vars or the environment)", name, url)
+            })?,
+        };
+
+        expanded.push_str(&rest[..start]);
+        expanded.push_str(&value);
+        rest = &rest[start + 2 + len + 1..];
+    }
+
+    expanded.push_str(rest);
+    Ok(expanded)
+}
+
+/// Parse M3U entries, pairing each `#EXTINF` line with the stream URL that follows it
+///
+/// Relative URLs are resolved against `base` when there is one and dropped otherwise.
+fn parse_m3u_entries(content: &str, base: Option<&Url>, name_source: NameSource) -> Vec<Channel> {
+    let mut channels = Vec::new();
+    let mut pending = None;
+
+    for line in content.lines() {
+        let line = line.trim();
+
+        if line.starts_with("#EXTINF:") {
+            pending = Some((parse_extinf_metadata(line), BTreeMap::new()));
+        } else if line.starts_with("#EXTVLCOPT:") {
+            if let (Some((_, headers)), Some((name, value))) = (pending.as_mut(), parse_extvlcopt_header(line)) {
+                headers.insert(name, value);
+            }
+        } else if line.is_empty() || line.starts_with('#') {
+            continue;
+        } else if let Some((metadata, headers)) = pending.take() {
+            let url = if is_stream_url(line) {
+                Some(line.to_string())
+            } else {
+                base.and_then(|base| base.join(line).ok()).map(String::from)
+            };
+            if let Some(url) = url {
+                let mut channel = Channel::from_extinf(metadata, url, name_source);
+                channel.headers = headers;
+                channels.push(channel);
+            }
+        }
+    }
+
+    channels
+}
+
+/// Whether `line` looks like a stream URL: any `scheme://` such as http, rtmp, rtsp, udp or file
+fn is_stream_url(line: &str) -> bool {
+    line.split_once("://").is_some_and(|(scheme, _)| {
+        scheme.starts_with(|c: char| c.is_ascii_alphabetic())
+            && scheme.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '+' | '-' | '.'))
+    })
+}
+
+/// Whether `url` is an `http://` or `https://` stream
+fn is_http_url(url: &str) -> bool {
+    url.trim()
+        .split_once("://")
+        .is_some_and(|(scheme, _)| scheme.eq_ignore_ascii_case("http") || scheme.eq_ignore_ascii_case("https"))
+}
+
+/// Drop a leading UTF-8 byte order mark left by Windows editors
+fn strip_bom(content: String) -> String {
+    match content.strip_prefix('\u{feff}') {
+        Some(stripped) => stripped.to_string(),
+        None => content,
+    }
+}
+
+/// Whether `bytes` is an `#EXTM3U` playlist of channels, the one format `parse_appended` can extend
+fn is_extm3u(path: &Path, bytes: &[u8]) -> bool {
+    let Ok(content) = std::str::from_utf8(bytes) else {
+        return false;
+    };
+    let content = content.trim_start_matches('\u{feff}');
+    content.trim_start().starts_with("#EXTM3U")
+        && !sources::pls::is_pls(path, content)
+        && !sources::hls::is_master(content)
+}
+
+/// Turn a plain list of stream URLs into channels named after their host
+fn parse_bare_urls(content: &str) -> Vec<Channel> {
+    content
+        .lines()
+        .map(str::trim)
+        .filter(|line| is_stream_url(line))
+        .map(|url| {
+            let name = extract_domain(url).unwrap_or_else(|| url.to_string());
+            Channel::new(name, url.to_string())
+        })
+        .collect()
+}
+
+/// Where one parse spent its time, from `parse_str_with_timings`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ParseTimings {
+    /// Stripping the BOM and sniffing the format
+    pub read: Duration,
+    /// Splitting entries and parsing `#EXTINF` attributes
+    pub regex: Duration,
+    /// Filtering, name cleanup and building the lookup and search indices
+    pub index_build: Duration,
+    pub total: Duration,
+}
+
+/// Timings from parsing the same playlist repeatedly in both modes
+#[derive(Debug)]
+pub struct ParseProfile {
+    pub runs: usize,
+    pub channels: usize,
+    pub sequential: Duration,
+    pub parallel: Duration,
+    /// Stage breakdown of the last sequential run
+    pub stages: ParseTimings,
+}
+
+impl ParseProfile {
+    /// Channels parsed per second, averaged over all runs
+    fn rate(&self, total: Duration) -> f64 {
+        (self.channels * self.runs) as f64 / total.as_secs_f64().max(f64::EPSILON)
+    }
+
+    pub fn show(&self) {
+        println!("{}", format!("⏱️ Parse profile ({} runs, {} channels)", self.runs, self.channels).bright_cyan().bold());
+        for (mode, total) in [("Sequential", self.sequential), ("Parallel", self.parallel)] {
+            println!(
+                "  {:<10} {:>10.2?} per run  {:>12.0} channels/sec",
+                mode,
+                total / self.runs.max(1) as u32,
+                self.rate(total)
+            );
+        }
+
+        let speedup = self.sequential.as_secs_f64() / self.parallel.as_secs_f64().max(f64::EPSILON);
+        println!("  {} {:.2}x", "Parallel speedup:".bright_white(), speedup);
+        println!(
+            "  {} read {:.2?}, parse {:.2?}, index {:.2?}",
+            "Sequential stages:".bright_white(),
+            self.stages.read,
+            self.stages.regex,
+            self.stages.index_build
+        );
+    }
+}
+
+/// Parse `content` `runs` times sequentially and then in parallel, timing each mode
+pub async fn profile_parse(content: &str, runs: usize, options: &ParseOptions) -> Result<ParseProfile> {
+    let runs = runs.max(1);
+    let mut channels = 0;
+    let mut timings = [Duration::ZERO; 2];
+    let mut stages = ParseTimings::default();
+
+    for (timing, parallel) in timings.iter_mut().zip([false, true]) {
+        for _ in 0..runs {
+            let mut parser = PlaylistParser::with_options(parallel, options.clone());
+            let start = Instant::now();
+            let run = parser.parse_str_with_timings(content.to_string(), !parallel).await?;
+            *timing += start.elapsed();
+            channels = parser.get_channels().len();
+            stages = run.unwrap_or(stages);
+        }
+    }
+
+    Ok(ParseProfile { runs, channels, sequential: timings[0], parallel: timings[1], stages })
+}
+
+/// Sorted maps keep `--stats --json` output identical across runs
+#[derive(Debug, Default, Serialize)]
 pub struct PlaylistStats {
     pub total_channels: usize,
     pub total_groups: usize,
-    pub channels_per_group: HashMap<String, usize>,
-    pub countries: HashMap<String, usize>,
-    pub languages: HashMap<String, usize>,
+    pub channels_per_group: BTreeMap<String, usize>,
+    pub countries: BTreeMap<String, usize>,
+    pub languages: BTreeMap<String, usize>,
+}
+
+/// How `--stats` orders groups, countries and languages
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum StatsSort {
+    /// Most channels first, showing the top 10
+    #[default]
+    Count,
+    /// Alphabetical, showing every entry
+    Name,
+}
+
+impl StatsSort {
+    /// Entries of a stats breakdown in this order, limited to the top 10 when sorting by count
+    ///
+    /// With `natural`, names (and count ties) are ordered with `natural_cmp`.
+    pub fn apply<'a>(&self, counts: &'a BTreeMap<String, usize>, natural: bool) -> Vec<(&'a str, usize)> {
+        let mut entries: Vec<_> = counts.iter().map(|(name, count)| (name.as_str(), *count)).collect();
+        if natural {
+            entries.sort_by(|(a, _), (b, _)| natural_cmp(a, b));
+        }
+        match self {
+            StatsSort::Count => {
+                entries.sort_by_key(|(_, count)| std::cmp::Reverse(*count));
+                entries.truncate(10);
+            }
+            StatsSort::Name if natural => {}
+            StatsSort::Name => entries.sort_by_cached_key(|(name, _)| (name.to_lowercase(), name.to_string())),
+        }
+        entries
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SAMPLE: &str = "#EXTM3U\n\
+        #EXTINF:-1 tvg-id=\"bbc1.uk\" tvg-name=\"BBC One\" group-title=\"UK\",BBC One HD\n\
+        http://example.com/bbc1\n\
+        #EXTINF:-1 group-title=\"UK\",ITV\n\
+        #EXTVLCOPT:http-user-agent=Mozilla\n\
+        http://example.com/itv\n\
+        #EXTINF:-1 tvg-country=\"FR\" tvg-language=\"French\" group-title=\"News, France\",France 24\n\
+        http://example.com/france24\n\
+        #EXTINF:-1,No URL\n\
+        #EXTINF:-1 group-title=\"Sports\",Sport One\n\
+        https://example.com/sport1\n\
+        #EXTINF:-1,Sport Two\n\
+        https://example.com/sport2\n";
+
+    fn as_json(channels: &[Channel]) -> String {
+        serde_json::to_string(channels).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_parse_m3u_entries() {
+        let mut parser = PlaylistParser::new(false);
+        parser.parse_str(SAMPLE.to_string()).await.unwrap();
+
+        let channels = parser.get_channels();
+        assert_eq!(channels.len(), 5);
+        assert_eq!(channels[0].name, "BBC One");
+        assert_eq!(channels[0].url, "http://example.com/bbc1");
+        assert_eq!(channels[0].tvg_id.as_deref(), Some("bbc1.uk"));
+        assert_eq!(channels[1].name, "ITV");
+        assert_eq!(channels[1].url, "http://example.com/itv");
+        assert_eq!(channels[1].headers.get("User-Agent").map(String::as_str), Some("Mozilla"));
+        assert!(channels[0].headers.is_empty());
+        assert_eq!(channels[2].name, "France 24");
+        assert_eq!(channels[2].group.as_deref(), Some("News, France"));
+        assert_eq!(channels[2].country.as_deref(), Some("FR"));
+        assert_eq!(channels[3].name, "Sport One");
+        assert_eq!(channels[4].group, None);
+        assert!(channels.iter().all(|channel| !channel.is_vod()));
+    }
+
+    #[tokio::test]
+    async fn test_profile_parse() {
+        let profile = profile_parse(SAMPLE, 2, &ParseOptions::default()).await.unwrap();
+        assert_eq!(profile.runs, 2);
+        assert_eq!(profile.channels, 5);
+        assert!(profile.sequential > Duration::ZERO);
+        assert!(profile.parallel > Duration::ZERO);
+        assert!(profile.stages.total > Duration::ZERO);
+    }
+
+    #[tokio::test]
+    async fn test_parse_timings() {
+        let content: String = std::iter::once("#EXTM3U\n".to_string())
+            .chain((0..5_000).map(|i| format!("#EXTINF:-1 group-title=\"Group {}\",Channel {}\nhttp://example.com/{}\n", i % 7, i, i)))
+            .collect();
+
+        for parallel in [false, true] {
+            let mut parser = PlaylistParser::new(parallel);
+            assert_eq!(parser.parse_str_with_timings(content.clone(), false).await.unwrap(), None);
+
+            let timings = parser.parse_str_with_timings(content.clone(), true).await.unwrap().unwrap();
+            assert_eq!(parser.get_channels().len(), 5_000);
+            assert!(timings.regex > Duration::ZERO);
+            assert!(timings.index_build > Duration::ZERO);
+            assert_eq!(timings.read + timings.regex + timings.index_build, timings.total);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_parse_duration() {
+        let content = "#EXTM3U\n#EXTINF:-1,Live\nhttp://example.com/live\n#EXTINF:7200,Movie\nhttp://example.com/movie.mp4\n";
+        let mut parser = PlaylistParser::new(false);
+        parser.parse_str(content.to_string()).await.unwrap();
+
+        let channels = parser.get_channels();
+        assert_eq!(channels[0].duration, None);
+        assert!(!channels[0].is_vod());
+        assert_eq!(channels[1].duration, Some(7200.0));
+        assert!(channels[1].is_vod());
+    }
+
+    #[tokio::test]
+    async fn test_parallel_matches_sequential() {
+        let content = SAMPLE.repeat(50);
+
+        let mut sequential = PlaylistParser::new(false);
+        sequential.parse_str(content.clone()).await.unwrap();
+
+        for threads in [1, 3, 8, 1000] {
+            let options = ParseOptions { parse_threads: threads, ..ParseOptions::default() };
+            let mut parallel = PlaylistParser::with_options(true, options);
+            parallel.parse_str(content.clone()).await.unwrap();
+
+            assert_eq!(parallel.get_channels().len(), 250);
+            assert_eq!(as_json(parallel.get_channels()), as_json(sequential.get_channels()));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_url_vars() {
+        let mut url_vars = HashMap::new();
+        url_vars.insert("TOKEN".to_string(), "s3cret".to_string());
+
+        let content = "#EXTM3U\n#EXTINF:-1,Tokenized\nhttp://example.com/live?token=${TOKEN}\n";
+        let options = ParseOptions { url_vars, ..ParseOptions::default() };
+        let mut parser = PlaylistParser::with_options(false, options);
+        parser.parse_str(content.to_string()).await.unwrap();
+
+        let channel = &parser.get_channels()[0];
+        assert_eq!(channel.url, "http://example.com/live?token=s3cret");
+        assert_eq!(channel.url_template.as_deref(), Some("http://example.com/live?token=${TOKEN}"));
+
+        let err = expand_url_vars("http://example.com/${RIPTV_TEST_UNDEFINED_VAR}", &HashMap::new()).unwrap_err();
+        assert!(err.to_string().contains("RIPTV_TEST_UNDEFINED_VAR"));
+    }
+
+    #[tokio::test]
+    async fn test_verify_sha256() {
+        let body = b"#EXTM3U\n#EXTINF:-1,Test\nhttp://example.com/test\n";
+        let digest = sha256_hex(body);
+        assert!(verify_sha256(body, &digest.to_uppercase()).is_ok());
+        assert!(verify_sha256(b"tampered", &digest).is_err());
+
+        let path = std::env::temp_dir().join(format!("riptv-sha-{}.m3u", std::process::id()));
+        fs::write(&path, body).unwrap();
+
+        let mut parser = PlaylistParser::new(false);
+        parser.set_expected_sha256(Some(sha256_hex(b"something else")));
+        assert!(parser.parse_file(&path).await.is_err());
+        assert!(parser.get_channels().is_empty());
+
+        parser.set_expected_sha256(Some(digest));
+        parser.parse_file(&path).await.unwrap();
+        assert_eq!(parser.get_channels().len(), 1);
+
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_group_summary() {
+        let mut parser = PlaylistParser::new(false);
+        parser.parse_str(SAMPLE.to_string()).await.unwrap();
+
+        assert_eq!(
+            parser.group_summary(),
+            vec![
+                ("News, France".to_string(), 1),
+                ("Sports".to_string(), 1),
+                ("UK".to_string(), 2),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_search_fields() {
+        let mut parser = PlaylistParser::new(false);
+        parser.parse_str(SAMPLE.to_string()).await.unwrap();
+
+        assert!(parser.search_scored("sports").is_empty());
+
+        parser.set_search_fields(vec![SearchField::Name, SearchField::Group]);
+        let results = parser.search_channels("sports");
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].group.as_deref(), Some("Sports"));
+    }
+
+    #[tokio::test]
+    async fn test_top_match() {
+        let mut parser = PlaylistParser::new(false);
+        parser.parse_str(SAMPLE.to_string()).await.unwrap();
+
+        let best = parser.search_scored("sport two")[0].1;
+        assert_eq!(parser.top_match("sport two", |_| true).unwrap().name, parser.get_channels()[best].name);
+        assert_eq!(parser.top_match("sport two", |_| true).unwrap().name, "Sport Two");
+        assert!(parser.top_match("zzzz", |_| true).is_none());
+    }
+
+    #[tokio::test]
+    async fn test_parse_appended() {
+        use std::io::Write;
+
+        let path = std::env::temp_dir().join(format!("riptv-append-{}.m3u", std::process::id()));
+        fs::write(&path, "#EXTM3U\n#EXTINF:-1,One\nhttp://example.com/1\n#EXTINF:-1,Two\nhttp://example.com/2\n").unwrap();
+        let mut parser = PlaylistParser::new(false);
+        assert_eq!(parser.parse_appended(&path).unwrap(), None);
+        parser.parse_file(&path).await.unwrap();
+        assert_eq!(parser.parse_appended(&path).unwrap(), Some(0));
+
+        // The entry without its URL yet is left for the next parse
+        let mut file = fs::OpenOptions::new().append(true).open(&path).unwrap();
+        file.write_all(b"#EXTINF:-1,Three\nhttp://example.com/3\n#EXTINF:-1,Four\n").unwrap();
+        assert_eq!(parser.parse_appended(&path).unwrap(), Some(1));
+        file.write_all(b"http://example.com/4\n").unwrap();
+        assert_eq!(parser.parse_appended(&path).unwrap(), Some(1));
+
+        let names: Vec<_> = parser.get_channels().iter().map(|channel| channel.name.as_str()).collect();
+        assert_eq!(names, ["One", "Two", "Three", "Four"]);
+        assert_eq!(parser.get_channel_by_name("Four").unwrap().url, "http://example.com/4");
+
+        // A rewritten file needs a full parse
+        fs::write(&path, "#EXTM3U\n#EXTINF:-1,Uno\nhttp://example.com/1\n#EXTINF:-1,Two\nhttp://example.com/2\n#EXTINF:-1,Five\nhttp://example.com/5\n").unwrap();
+        assert_eq!(parser.parse_appended(&path).unwrap(), None);
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_append_channels() {
+        let mut parser = PlaylistParser::new(false);
+        parser.parse_str(SAMPLE.to_string()).await.unwrap();
+
+        let mut extra = Channel::new("Sky News".to_string(), "http://example.com/sky".to_string());
+        extra.group = Some("UK".to_string());
+        let loose = Channel::new("Local".to_string(), "http://example.com/local".to_string());
+        parser.append_channels(vec![extra, loose]);
+
+        assert_eq!(parser.get_channels().len(), 7);
+        assert_eq!(parser.get_channel_by_name("ITV").unwrap().url, "http://example.com/itv");
+        assert_eq!(parser.get_channel_by_name("Sky News").unwrap().url, "http://example.com/sky");
+        assert_eq!(parser.get_channel_by_name("Local").unwrap().url, "http://example.com/local");
+
+        let uk: Vec<_> = parser.get_channels_by_group("UK").iter().map(|c| c.name.as_str()).collect();
+        assert_eq!(uk, ["BBC One", "ITV", "Sky News"]);
+
+        let mut rebuilt = PlaylistParser::new(false);
+        rebuilt.parse_str(SAMPLE.to_string()).await.unwrap();
+        rebuilt.channels.extend(parser.get_channels()[5..].to_vec());
+        rebuilt.build_indices();
+        assert_eq!(rebuilt.channel_map, parser.channel_map);
+        assert_eq!(rebuilt.groups, parser.groups);
+    }
+
+    #[tokio::test]
+    async fn test_export_by_extension() {
+        let mut parser = PlaylistParser::new(false);
+        parser.parse_str(SAMPLE.to_string()).await.unwrap();
+        let dir = std::env::temp_dir();
+        let id = std::process::id();
+
+        let cases = [
+            ("m3u", ExportFormat::M3u, "#EXTM3U"),
+            ("M3U8", ExportFormat::M3u, "#EXTM3U"),
+            ("json", ExportFormat::Json, "["),
+            ("csv", ExportFormat::Csv, "name,url,group"),
+        ];
+        for (extension, format, start) in cases {
+            let path = dir.join(format!("riptv-export-{}.{}", id, extension));
+            assert_eq!(parser.export(&path).unwrap(), format);
+            let written = fs::read_to_string(&path).unwrap();
+            fs::remove_file(&path).unwrap();
+            assert!(written.starts_with(start), "{} export starts with {:?}", extension, written);
+        }
+
+        let err = parser.export(dir.join(format!("riptv-export-{}.xml", id))).unwrap_err();
+        assert!(err.to_string().contains(".csv"));
+        assert!(ExportFormat::from_path(Path::new("noext")).is_err());
+    }
+
+    #[test]
+    fn test_to_csv_quotes_fields() {
+        let mut channel = Channel::new("News, Live".to_string(), "http://example.com/a".to_string());
+        channel.group = Some("Say \"hi\"".to_string());
+        let csv = to_csv(&[channel]);
+        assert_eq!(csv.lines().nth(1).unwrap(), "\"News, Live\",http://example.com/a,\"Say \"\"hi\"\"\",,,,");
+    }
+
+    #[test]
+    fn test_channel_equality() {
+        let bbc = Channel::new("BBC One".to_string(), "http://example.com/bbc1".to_string());
+        let mut renamed = Channel::new("BBC One HD".to_string(), "http://example.com/bbc1".to_string());
+        renamed.group = Some("UK".to_string());
+        let itv = Channel::new("BBC One".to_string(), "http://example.com/itv".to_string());
+
+        assert_eq!(bbc, renamed);
+        assert_ne!(bbc, itv);
+        assert_eq!(bbc.key(), "http://example.com/bbc1");
+
+        let mut set = HashSet::new();
+        assert!(set.insert(bbc));
+        assert!(!set.insert(renamed));
+        assert!(set.insert(itv));
+        assert_eq!(set.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_group_filters() {
+        let mut config = Config::default();
+        config.groups.exclude = vec!["sports".to_string()];
+        let mut parser = PlaylistParser::with_options(false, ParseOptions::from_config(&config));
+        parser.parse_str(SAMPLE.to_string()).await.unwrap();
+
+        let names: Vec<_> = parser.get_channels().iter().map(|c| c.name.as_str()).collect();
+        assert_eq!(names, ["BBC One", "ITV", "France 24", "Sport Two"]);
+        assert!(parser.get_channels_by_group("Sports").is_empty());
+
+        config.groups.exclude.clear();
+        config.groups.include = vec!["News*".to_string(), "UK".to_string()];
+        let mut parser = PlaylistParser::with_options(false, ParseOptions::from_config(&config));
+        parser.parse_str(SAMPLE.to_string()).await.unwrap();
+
+        let names: Vec<_> = parser.get_channels().iter().map(|c| c.name.as_str()).collect();
+        assert_eq!(names, ["BBC One", "ITV", "France 24"]);
+    }
+
+    #[tokio::test]
+    async fn test_search_index_reused() {
+        let content: String = std::iter::once("#EXTM3U\n".to_string())
+            .chain((0..20_000).map(|i| format!("#EXTINF:-1,{} Channel {}\nhttp://example.com/{}\n", ["BBC", "ITV", "Sky"][i % 3], i, i)))
+            .collect();
+        let mut parser = PlaylistParser::new(false);
+        parser.parse_str(content).await.unwrap();
+        parser.set_search_backend(crate::search::backend(false));
+        parser.set_anchored(true);
+
+        let index = parser.search_index();
+        assert_eq!(index.with_prefix("").len(), 20_000);
+        let names = index.name(0).as_ptr();
+
+        // Typing "bbc channel 1" one key at a time searches the same index every time
+        let query = "bbc channel 1";
+        for end in 1..=query.len() {
+            let matches = parser.search_scored(&query[..end]);
+            assert!(matches.iter().all(|&(_, idx)| parser.get_channels()[idx].name.to_lowercase().starts_with(&query[..end])));
+            assert!(!matches.is_empty());
+            assert_eq!(parser.search_index().name(0).as_ptr(), names);
+        }
+        assert_eq!(parser.search_scored("bbc").len(), parser.search_index().with_prefix("bbc").len());
+        assert_eq!(parser.search_scored("BBC Channel 0")[0].1, 0);
+
+        // Appending extends the index rather than rebuilding it
+        parser.append_channels(vec![Channel::new("BBC Extra".to_string(), "http://example.com/extra".to_string())]);
+        assert_eq!(parser.search_index().with_prefix("").len(), 20_001);
+        assert_eq!(parser.search_scored("bbc extra"), [(900, 20_000)]);
+    }
+
+    #[tokio::test]
+    async fn test_min_score() {
+        let mut parser = PlaylistParser::new(false);
+        parser.parse_str(SAMPLE.to_string()).await.unwrap();
+
+        let scored = parser.search_scored("st");
+        assert!(scored.len() >= 2);
+        let (best, worst) = (scored[0].0, scored[scored.len() - 1].0);
+        assert!(best > worst);
+
+        parser.set_min_score(best);
+        let kept = parser.search_scored("st");
+        assert!(kept.len() < scored.len());
+        assert!(kept.iter().all(|(score, _)| *score >= best));
+    }
+
+    #[tokio::test]
+    async fn test_favorite_boost() {
+        let mut parser = PlaylistParser::new(false);
+        parser
+            .parse_str("#EXTM3U\n#EXTINF:-1,Sport One\nhttp://a\n#EXTINF:-1,Sport Two\nhttp://b\n".to_string())
+            .await
+            .unwrap();
+        assert_eq!(parser.top_match("sport", |_| true).unwrap().name, "Sport One");
+
+        parser.set_favorites(vec!["Sport Two".to_string()], 50);
+        assert_eq!(parser.top_match("sport", |_| true).unwrap().name, "Sport Two");
+
+        parser.set_favorites(vec!["Sport Two".to_string()], 0);
+        assert_eq!(parser.top_match("sport", |_| true).unwrap().name, "Sport One");
+    }
+
+    #[tokio::test]
+    async fn test_smart_case() {
+        let mut parser = PlaylistParser::new(false);
+        parser.parse_str(SAMPLE.to_string()).await.unwrap();
+
+        // Ignoring case, "ITV" and "itv" find the same channel
+        assert_eq!(parser.search_channels("itv").len(), 1);
+
+        parser.set_case_mode(CaseMode::Smart);
+        assert_eq!(parser.search_channels("itv").len(), 1);
+        assert_eq!(parser.search_channels("ITV").len(), 1);
+        assert!(parser.search_channels("ONE").is_empty());
+        assert_eq!(parser.search_channels("One").len(), 2);
+
+        parser.set_case_mode(CaseMode::Respect);
+        assert!(parser.search_channels("itv").is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_anchored_search() {
+        let content = "#EXTM3U\n\
+            #EXTINF:-1,BBC One\nhttp://example.com/1\n\
+            #EXTINF:-1,The BBC Show\nhttp://example.com/2\n\
+            #EXTINF:-1,bbc news\nhttp://example.com/3\n\
+            #EXTINF:-1,Big Bear Channel\nhttp://example.com/4\n";
+        let mut parser = PlaylistParser::new(false);
+        parser.parse_str(content.to_string()).await.unwrap();
+        assert_eq!(parser.search_channels("BBC").len(), 4);
+
+        parser.set_anchored(true);
+        let mut names: Vec<_> = parser.search_channels("BBC").iter().map(|channel| channel.name.as_str()).collect();
+        names.sort();
+        assert_eq!(names, ["BBC One", "bbc news"]);
+        assert!(parser.search_channels("One").is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_headerless_bare_url_list() {
+        let content = "http://one.example.com/live.m3u8\n\nhttps://two.example.org:8080/stream\n";
+        let mut parser = PlaylistParser::new(false);
+        parser.parse_str(content.to_string()).await.unwrap();
+
+        let names: Vec<_> = parser.get_channels().iter().map(|channel| channel.name.as_str()).collect();
+        assert_eq!(names, ["one.example.com", "two.example.org"]);
+        assert_eq!(parser.get_channels()[1].url, "https://two.example.org:8080/stream");
+
+        // Headerless playlists with EXTINF entries still parse normally
+        let mut parser = PlaylistParser::new(false);
+        parser.parse_str(SAMPLE.trim_start_matches("#EXTM3U\n").to_string()).await.unwrap();
+        assert_eq!(parser.get_channels().len(), 5);
+    }
+
+    #[test]
+    fn test_default_name_cleanup_rules() {
+        let mut config = Config::default();
+        config.name_cleanup.enabled = true;
+        let rules = ParseOptions::from_config(&config).name_rules;
+
+        assert_eq!(clean_name("US| CNN International", &rules), "CNN International");
+        assert_eq!(clean_name("UK: BBC One HD", &rules), "BBC One");
+        assert_eq!(clean_name("[VIP] Sky Sports |FHD", &rules), "Sky Sports");
+        assert_eq!(clean_name("Discovery [4K] Channel", &rules), "Discovery Channel");
+        assert_eq!(clean_name("HD", &rules), "HD");
+    }
+
+    #[tokio::test]
+    async fn test_name_cleanup_keeps_raw_name() {
+        let mut config = Config::default();
+        config.name_cleanup.enabled = true;
+        let mut parser = PlaylistParser::with_options(false, ParseOptions::from_config(&config));
+        let content = "#EXTM3U\n#EXTINF:-1,UK| ITV HD\nhttp://example.com/itv\n#EXTINF:-1,CNN\nhttp://example.com/cnn\n";
+        parser.parse_str(content.to_string()).await.unwrap();
+
+        let channel = &parser.get_channels()[0];
+        assert_eq!(channel.name, "ITV");
+        assert_eq!(channel.raw_name.as_deref(), Some("UK| ITV HD"));
+        assert_eq!(parser.get_channels()[1].raw_name, None);
+    }
+
+    #[tokio::test]
+    async fn test_group_aliases_merge_groups() {
+        let mut config = Config::default();
+        config.group_aliases.insert("NEWS".to_string(), "News".to_string());
+        config.group_aliases.insert("News HD".to_string(), "News".to_string());
+        let mut parser = PlaylistParser::with_options(false, ParseOptions::from_config(&config));
+        let content = "#EXTM3U\n\
+            #EXTINF:-1 group-title=\"News\",BBC\nhttp://example.com/bbc\n\
+            #EXTINF:-1 group-title=\"NEWS\",CNN\nhttp://example.com/cnn\n\
+            #EXTINF:-1 group-title=\"News HD\",Sky\nhttp://example.com/sky\n\
+            #EXTINF:-1 group-title=\"Sports\",ESPN\nhttp://example.com/espn\n";
+        parser.parse_str(content.to_string()).await.unwrap();
+
+        let stats = parser.get_statistics();
+        assert_eq!(stats.total_groups, 2);
+        assert_eq!(stats.channels_per_group["News"], 3);
+        assert_eq!(stats.channels_per_group["Sports"], 1);
+        assert_eq!(parser.get_channels_by_group("News").len(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_name_overrides() {
+        let mut config = Config::default();
+        config.name_overrides.insert("http://example.com/bbc".to_string(), "Beeb".to_string());
+        let mut parser = PlaylistParser::with_options(false, ParseOptions::from_config(&config));
+        let content = "#EXTM3U\n\
+            #EXTINF:-1,BBC One HD\nhttp://example.com/bbc\n\
+            #EXTINF:-1,CNN\nhttp://example.com/cnn\n";
+        parser.parse_str(content.to_string()).await.unwrap();
+
+        let beeb = parser.get_channel_by_name("Beeb").unwrap();
+        assert_eq!(beeb.url, "http://example.com/bbc");
+        assert_eq!(beeb.raw_name.as_deref(), Some("BBC One HD"));
+        assert!(parser.get_channel_by_name("BBC One HD").is_none());
+        assert!(to_m3u(parser.get_channels()).contains(",BBC One HD\n"));
+
+        assert_eq!(parser.rename_channels("http://example.com/cnn", "News"), 1);
+        let news = parser.get_channel_by_name("News").unwrap();
+        assert_eq!(news.url, "http://example.com/cnn");
+        assert_eq!(news.raw_name.as_deref(), Some("CNN"));
+    }
+
+    #[tokio::test]
+    async fn test_max_channels_cap() {
+        let content: String = std::iter::once("#EXTM3U\n".to_string())
+            .chain((0..50).map(|i| format!("#EXTINF:-1,Channel {}\nhttp://example.com/{}\n", i, i)))
+            .collect();
+
+        for parallel in [false, true] {
+            let options = ParseOptions { max_channels: 10, ..ParseOptions::default() };
+            let mut parser = PlaylistParser::with_options(parallel, options);
+            let err = parser.parse_str(content.clone()).await.unwrap_err();
+            assert!(err.to_string().contains("more than 10 channels"));
+            assert!(parser.get_channels().is_empty());
+        }
+
+        let options = ParseOptions { max_channels: 50, ..ParseOptions::default() };
+        let mut parser = PlaylistParser::with_options(false, options);
+        parser.parse_str(content).await.unwrap();
+        assert_eq!(parser.get_channels().len(), 50);
+    }
+
+    #[tokio::test]
+    async fn test_name_source() {
+        let content = "#EXTM3U\n\
+            #EXTINF:-1 tvg-id=\"bbc1.uk\" tvg-name=\"BBC1_HD_UK\",BBC One HD\nhttp://example.com/bbc1\n\
+            #EXTINF:-1 tvg-id=\"\",Plain\nhttp://example.com/plain\n";
+
+        for (source, expected) in [
+            ("display", ["BBC One HD", "Plain"]),
+            ("tvg_name", ["BBC1_HD_UK", "Plain"]),
+            ("tvg_id", ["bbc1.uk", "Plain"]),
+        ] {
+            let config = Config { name_source: source.to_string(), ..Config::default() };
+            config.validate().unwrap();
+            let mut parser = PlaylistParser::with_options(false, ParseOptions::from_config(&config));
+            parser.parse_str(content.to_string()).await.unwrap();
+            let names: Vec<_> = parser.get_channels().iter().map(|channel| channel.name.as_str()).collect();
+            assert_eq!(names, expected, "name_source = {}", source);
+        }
+
+        let config = Config { name_source: "title".to_string(), ..Config::default() };
+        assert!(config.validate().is_err());
+    }
+
+    #[tokio::test]
+    async fn test_strict_url() {
+        let content = "#EXTM3U\n\
+            #EXTINF:-1,News\nhttps://example.com/news.m3u8\n\
+            #EXTINF:-1,Home Video\nfile:///home/user/video.ts\n\
+            #EXTINF:-1,Radio\nrtmp://example.com/live/radio\n\
+            #EXTINF:-1,Not A Url\nnot a url\n";
+
+        for parallel in [false, true] {
+            let mut parser = PlaylistParser::with_options(parallel, ParseOptions::default());
+            parser.parse_str(content.to_string()).await.unwrap();
+            let names: Vec<_> = parser.get_channels().iter().map(|channel| channel.name.as_str()).collect();
+            assert_eq!(names, ["News", "Home Video", "Radio"]);
+
+            let options = ParseOptions { strict_url: true, ..ParseOptions::default() };
+            let mut parser = PlaylistParser::with_options(parallel, options);
+            parser.parse_str(content.to_string()).await.unwrap();
+            let names: Vec<_> = parser.get_channels().iter().map(|channel| channel.name.as_str()).collect();
+            assert_eq!(names, ["News"]);
+        }
+
+        // Entries picked up by an incremental refresh are filtered too
+        use std::io::Write;
+        let path = std::env::temp_dir().join(format!("riptv-strict-append-{}.m3u", std::process::id()));
+        fs::write(&path, "#EXTM3U\n#EXTINF:-1,News\nhttps://example.com/news.m3u8\n").unwrap();
+        let options = ParseOptions { strict_url: true, ..ParseOptions::default() };
+        let mut parser = PlaylistParser::with_options(false, options);
+        parser.parse_file(&path).await.unwrap();
+        let mut file = fs::OpenOptions::new().append(true).open(&path).unwrap();
+        file.write_all(b"#EXTINF:-1,Home Video\nfile:///home/user/video.ts\n#EXTINF:-1,Radio\nrtmp://example.com/live/radio\n#EXTINF:-1,Sport\nhttp://example.com/sport\n").unwrap();
+        assert_eq!(parser.parse_appended(&path).unwrap(), Some(1));
+        let names: Vec<_> = parser.get_channels().iter().map(|channel| channel.name.as_str()).collect();
+        assert_eq!(names, ["News", "Sport"]);
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_stats_sort() {
+        let counts: BTreeMap<String, usize> =
+            [("sports", 2), ("News", 5), ("Movies", 9), ("kids", 1)].map(|(name, count)| (name.to_string(), count)).into();
+
+        assert_eq!(StatsSort::Name.apply(&counts, false), [("kids", 1), ("Movies", 9), ("News", 5), ("sports", 2)]);
+        assert_eq!(StatsSort::Count.apply(&counts, false), [("Movies", 9), ("News", 5), ("sports", 2), ("kids", 1)]);
+
+        let many: BTreeMap<String, usize> = (0..15).map(|i| (format!("Group {:02}", i), i)).collect();
+        assert_eq!(StatsSort::Count.apply(&many, false).len(), 10);
+        assert_eq!(StatsSort::Name.apply(&many, false).len(), 15);
+    }
+
+    #[tokio::test]
+    async fn test_natural_group_sort() {
+        let content = "#EXTM3U\n\
+            #EXTINF:-1 group-title=\"Sports 10\",A\nhttp://example.com/a\n\
+            #EXTINF:-1 group-title=\"Sports 2\",B\nhttp://example.com/b\n\
+            #EXTINF:-1 group-title=\"Sports 2\",C\nhttp://example.com/c\n\
+            #EXTINF:-1 group-title=\"Sports 1\",D\nhttp://example.com/d\n";
+        let mut parser = PlaylistParser::new(false);
+        parser.parse_str(content.to_string()).await.unwrap();
+        let groups = |parser: &PlaylistParser| parser.group_summary().into_iter().map(|(group, _)| group).collect::<Vec<_>>();
+
+        assert_eq!(groups(&parser), ["Sports 1", "Sports 10", "Sports 2"]);
+        parser.set_natural_sort(true);
+        assert_eq!(groups(&parser), ["Sports 1", "Sports 2", "Sports 10"]);
+
+        let stats = parser.get_statistics();
+        let names = |entries: Vec<(&str, usize)>| entries.into_iter().map(|(name, _)| name.to_string()).collect::<Vec<_>>();
+        assert_eq!(names(StatsSort::Name.apply(&stats.channels_per_group, true)), ["Sports 1", "Sports 2", "Sports 10"]);
+        assert_eq!(names(StatsSort::Count.apply(&stats.channels_per_group, true)), ["Sports 2", "Sports 1", "Sports 10"]);
+    }
+
+    #[tokio::test]
+    async fn test_to_m3u_round_trip() {
+        let mut parser = PlaylistParser::new(false);
+        parser.parse_str(SAMPLE.to_string()).await.unwrap();
+
+        let mut reparsed = PlaylistParser::new(false);
+        reparsed.parse_str(to_m3u(parser.get_channels())).await.unwrap();
+
+        assert_eq!(
+            serde_json::to_string(parser.get_channels()).unwrap(),
+            serde_json::to_string(reparsed.get_channels()).unwrap()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_bom_and_crlf() {
+        let windows = format!("\u{feff}{}", SAMPLE.replace('\n', "\r\n"));
+
+        for parallel in [false, true] {
+            let mut clean = PlaylistParser::new(parallel);
+            clean.parse_str(SAMPLE.to_string()).await.unwrap();
+
+            let mut parser = PlaylistParser::new(parallel);
+            parser.parse_str(windows.clone()).await.unwrap();
+
+            assert_eq!(
+                serde_json::to_string(parser.get_channels()).unwrap(),
+                serde_json::to_string(clean.get_channels()).unwrap()
+            );
+        }
+
+        let bare = "\u{feff}http://one.example.com/live\r\nhttp://two.example.com/live\r\n";
+        let mut parser = PlaylistParser::new(false);
+        parser.parse_str(bare.to_string()).await.unwrap();
+        assert_eq!(parser.get_channels()[1].url, "http://two.example.com/live");
+    }
+
+    #[tokio::test]
+    async fn test_statistics_serialize_stably() {
+        let mut parser = PlaylistParser::new(false);
+        parser.parse_str(SAMPLE.to_string()).await.unwrap();
+
+        let first = serde_json::to_string(&parser.get_statistics()).unwrap();
+        parser.parse_str(SAMPLE.to_string()).await.unwrap();
+        let second = serde_json::to_string(&parser.get_statistics()).unwrap();
+
+        assert_eq!(first, second);
+        assert!(first.contains(r#""channels_per_group":{"News, France":1,"Sports":1,"UK":2}"#));
+    }
+
+    #[tokio::test]
+    async fn test_index_by_initial() {
+        let names = ["bbc One", "ÉTV", "BBC Two", "élan", "Россия 1", "24 News", "★ Stars", "ß Kanal", "Arte"];
+        let playlist: String = names
+            .iter()
+            .enumerate()
+            .map(|(i, name)| format!("#EXTINF:-1,{}\nhttp://example.com/{}\n", name, i))
+            .collect();
+        let mut parser = PlaylistParser::new(false);
+        parser.parse_str(format!("#EXTM3U\n{}", playlist)).await.unwrap();
+
+        let index = index_by_initial(parser.get_channels());
+        let buckets: Vec<_> = index.iter().map(|(initial, indices)| (*initial, indices.clone())).collect();
+        assert_eq!(
+            buckets,
+            [
+                ('#', vec![5]),
+                ('A', vec![8]),
+                ('B', vec![0, 2]),
+                ('S', vec![6, 7]),
+                ('É', vec![1, 3]),
+                ('Р', vec![4]),
+            ]
+        );
+    }
 }
@@ -0,0 +1,218 @@
+use anyhow::{Context, Result};
+use colored::*;
+use indicatif::{ProgressBar, ProgressStyle};
+use std::collections::BTreeMap;
+use std::future::Future;
+use std::process::Stdio;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::process::Command;
+use tokio::sync::Semaphore;
+use tokio::task::JoinSet;
+
+use crate::playlist::Channel;
+use crate::utils::separator;
+
+/// How long one stream may take to probe before it's skipped as failed
+pub const PROBE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Streams probed at once
+const MAX_CONCURRENT_PROBES: usize = 8;
+
+/// First video stream of a channel, as reported by ffprobe
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StreamInfo {
+    pub codec: String,
+    pub width: u32,
+    pub height: u32,
+}
+
+impl StreamInfo {
+    /// Parse ffprobe's `codec_name,width,height` csv line
+    pub fn parse(output: &str) -> Option<Self> {
+        let mut fields = output.lines().next()?.trim().split(',');
+        Some(Self {
+            codec: fields.next()?.to_string(),
+            width: fields.next()?.parse().ok()?,
+            height: fields.next()?.parse().ok()?,
+        })
+    }
+
+    /// Resolution in the usual `1080p` shorthand
+    pub fn resolution(&self) -> String {
+        format!("{}p", self.height)
+    }
+}
+
+/// Outcome of probing one channel
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProbeResult {
+    pub name: String,
+    pub info: Result<StreamInfo, String>,
+}
+
+/// Results of a probe run, in playlist order
+#[derive(Debug, Default)]
+pub struct ProbeReport {
+    /// Channels that were meant to be probed
+    pub total: usize,
+    /// One per channel actually probed; fewer than `total` if the run was interrupted
+    pub results: Vec<ProbeResult>,
+}
+
+impl ProbeReport {
+    pub fn failed(&self) -> usize {
+        self.results.iter().filter(|result| result.info.is_err()).count()
+    }
+
+    /// `probed X of Y, Z failed`
+    pub fn summary(&self) -> String {
+        format!("probed {} of {}, {} failed", self.results.len(), self.total, self.failed())
+    }
+
+    /// How many probed channels have each video resolution
+    pub fn resolutions(&self) -> BTreeMap<String, usize> {
+        let mut counts = BTreeMap::new();
+        for info in self.results.iter().filter_map(|result| result.info.as_ref().ok()) {
+            *counts.entry(info.resolution()).or_insert(0) += 1;
+        }
+        counts
+    }
+
+    pub fn show(&self, width: usize) {
+        println!();
+        println!("{}", "🔬 Stream Probe".bright_cyan().bold());
+        println!("{}", separator('═', width).bright_blue());
+        println!("📊 {}", self.summary().bright_white());
+
+        for (resolution, count) in self.resolutions() {
+            println!("  🎞️ {} ({} channels)", resolution.bright_white(), count.to_string().bright_green());
+        }
+
+        let failures: Vec<_> = self.results.iter().filter_map(|result| Some((&result.name, result.info.as_ref().err()?))).collect();
+        if !failures.is_empty() {
+            println!("\n{}", format!("❌ Failed ({}):", failures.len()).bright_red().bold());
+            for (name, error) in failures {
+                println!("  📺 {} {}", name.bright_white(), error.bright_black());
+            }
+        }
+    }
+}
+
+/// Probe every channel's first video stream with ffprobe, a few at a time
+///
+/// Each probe is bounded by `timeout`; one that hangs or fails is counted and skipped.
+pub async fn probe_channels(channels: &[Channel], timeout: Duration, running: Arc<AtomicBool>) -> ProbeReport {
+    probe_with(channels, timeout, running, ffprobe).await
+}
+
+async fn probe_with<F, Fut>(channels: &[Channel], timeout: Duration, running: Arc<AtomicBool>, probe: F) -> ProbeReport
+where
+    F: Fn(String) -> Fut + Clone + Send + 'static,
+    Fut: Future<Output = Result<StreamInfo>> + Send,
+{
+    let pb = ProgressBar::new(channels.len() as u64);
+    pb.set_style(
+        ProgressStyle::default_bar()
+            .template("{spinner:.green} [{bar:30.cyan/blue}] {pos}/{len} probed ({elapsed})")
+            .unwrap()
+            .progress_chars("█▉ "),
+    );
+
+    let semaphore = Arc::new(Semaphore::new(MAX_CONCURRENT_PROBES));
+    let mut probes = JoinSet::new();
+    for (idx, channel) in channels.iter().enumerate() {
+        let semaphore = Arc::clone(&semaphore);
+        let running = Arc::clone(&running);
+        let probe = probe.clone();
+        let (name, url) = (channel.name.clone(), channel.url.trim().to_string());
+        probes.spawn(async move {
+            let _permit = semaphore.acquire_owned().await;
+            if !running.load(Ordering::Relaxed) {
+                return None;
+            }
+            let info = match tokio::time::timeout(timeout, probe(url)).await {
+                Ok(Ok(info)) => Ok(info),
+                Ok(Err(e)) => Err(format!("{:#}", e)),
+                Err(_) => Err(format!("timed out after {:?}", timeout)),
+            };
+            Some((idx, ProbeResult { name, info }))
+        });
+    }
+
+    let mut results = Vec::with_capacity(channels.len());
+    while let Some(finished) = probes.join_next().await {
+        if let Ok(Some(result)) = finished {
+            results.push(result);
+        }
+        pb.inc(1);
+    }
+    pb.finish_and_clear();
+
+    results.sort_by_key(|(idx, _)| *idx);
+    ProbeReport {
+        total: channels.len(),
+        results: results.into_iter().map(|(_, result)| result).collect(),
+    }
+}
+
+/// ffprobe the first video stream of `url`; killed if the probe is dropped on timeout
+async fn ffprobe(url: String) -> Result<StreamInfo> {
+    let output = Command::new("ffprobe")
+        .args(["-v", "error", "-select_streams", "v:0", "-show_entries", "stream=codec_name,width,height", "-of", "csv=p=0"])
+        .arg(&url)
+        .stdin(Stdio::null())
+        .kill_on_drop(true)
+        .output()
+        .await
+        .context("Failed to run ffprobe")?;
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        anyhow::bail!("{}", stderr.lines().next().unwrap_or("ffprobe failed").trim());
+    }
+    StreamInfo::parse(&String::from_utf8_lossy(&output.stdout)).context("No video stream found")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_stream_info() {
+        let info = StreamInfo::parse("h264,1920,1080\n").unwrap();
+        assert_eq!(info, StreamInfo { codec: "h264".to_string(), width: 1920, height: 1080 });
+        assert_eq!(info.resolution(), "1080p");
+        assert_eq!(StreamInfo::parse(""), None);
+        assert_eq!(StreamInfo::parse("h264,N/A,N/A"), None);
+    }
+
+    #[tokio::test]
+    async fn test_hanging_probe_is_skipped() {
+        let channels: Vec<Channel> = ["Fast", "Hangs", "Broken", "Also fast"]
+            .iter()
+            .map(|name| Channel::new(name.to_string(), format!("http://example.com/{}", name)))
+            .collect();
+
+        let probe = |url: String| async move {
+            if url.ends_with("Hangs") {
+                std::future::pending::<()>().await;
+            }
+            if url.ends_with("Broken") {
+                anyhow::bail!("Invalid data found when processing input");
+            }
+            Ok(StreamInfo { codec: "h264".to_string(), width: 1280, height: 720 })
+        };
+        let report = probe_with(&channels, Duration::from_millis(50), Arc::new(AtomicBool::new(true)), probe).await;
+
+        assert_eq!(report.summary(), "probed 4 of 4, 2 failed");
+        assert_eq!(report.results[1].info, Err("timed out after 50ms".to_string()));
+        assert!(report.results[3].info.is_ok());
+        assert_eq!(report.resolutions().into_iter().collect::<Vec<_>>(), [("720p".to_string(), 2)]);
+
+        // An interrupted run counts only what was probed
+        let stopped = probe_with(&channels, Duration::from_millis(50), Arc::new(AtomicBool::new(false)), probe).await;
+        assert_eq!(stopped.summary(), "probed 0 of 4, 0 failed");
+    }
+}
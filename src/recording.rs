@@ -0,0 +1,228 @@
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::process::Command;
+use tokio::task::JoinSet;
+use tracing::{debug, info, warn};
+
+use crate::catchup::unix_now;
+use crate::config::Config;
+use crate::epg::Programme;
+use crate::playlist::Channel;
+use crate::utils::sanitize_filename;
+
+/// Minutes a capture starts early and runs late when no padding is given
+pub const DEFAULT_PADDING_MINUTES: u64 = 2;
+
+/// An ffmpeg capture of one programme
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RecordingJob {
+    pub channel: String,
+    pub url: String,
+    pub title: String,
+    /// Capture window in seconds since the Unix epoch, padding included
+    pub start: i64,
+    pub stop: i64,
+    pub output: PathBuf,
+}
+
+impl RecordingJob {
+    /// Capture `programme` from `channel` with `padding_minutes` either side, saved under `dir`
+    ///
+    /// A programme that is already on starts recording now.
+    pub fn for_programme(channel: &Channel, programme: &Programme, padding_minutes: u64, now: i64, dir: &Path) -> Result<Self> {
+        let padding = padding_minutes as i64 * 60;
+        let stop = programme.stop + padding;
+        if stop <= now {
+            anyhow::bail!("'{}' on {} has already finished", programme.title, channel.name);
+        }
+        let start = (programme.start - padding).max(now);
+
+        let file_name = sanitize_filename(&format!("{} - {} - {}.ts", channel.name, programme.title, programme.start));
+        Ok(Self {
+            channel: channel.name.clone(),
+            url: channel.url.trim().to_string(),
+            title: programme.title.clone(),
+            start,
+            stop,
+            output: dir.join(file_name),
+        })
+    }
+
+    /// Length of the capture window in seconds
+    pub fn duration_secs(&self) -> i64 {
+        self.stop - self.start
+    }
+
+    /// ffmpeg copying the stream to `output` until the window ends, for a capture starting at `now`
+    pub fn ffmpeg_command(&self, now: i64) -> Command {
+        let remaining = self.stop - self.start.max(now);
+        let mut cmd = Command::new("ffmpeg");
+        cmd.args(["-hide_banner", "-loglevel", "error", "-y", "-i"])
+            .arg(&self.url)
+            .args(["-t", &remaining.to_string(), "-c", "copy"])
+            .arg(&self.output)
+            .stdin(Stdio::null())
+            .stdout(Stdio::null())
+            .kill_on_drop(true);
+        cmd
+    }
+}
+
+/// Scheduled recordings, persisted next to the config file
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Schedule {
+    jobs: Vec<RecordingJob>,
+
+    /// File the jobs were loaded from, used when saving
+    #[serde(skip)]
+    source_path: Option<PathBuf>,
+}
+
+impl Schedule {
+    /// Load the schedule kept beside the config file; in-memory only if the config has no file
+    pub fn for_config(config: &Config) -> Result<Self> {
+        match config.source_path.as_ref().and_then(|path| path.parent()) {
+            Some(dir) => Self::load(dir.join("recordings.json")),
+            None => Ok(Self::default()),
+        }
+    }
+
+    /// Load jobs from `path`, starting empty if the file doesn't exist yet
+    pub fn load(path: PathBuf) -> Result<Self> {
+        let mut schedule = if path.exists() {
+            let content = fs::read_to_string(&path)
+                .with_context(|| format!("Failed to read recording schedule: {}", path.display()))?;
+            serde_json::from_str::<Schedule>(&content)
+                .with_context(|| format!("Failed to parse recording schedule: {}", path.display()))?
+        } else {
+            Schedule::default()
+        };
+
+        schedule.source_path = Some(path);
+        Ok(schedule)
+    }
+
+    /// Write jobs back to the file they were loaded from, if any
+    pub fn save(&self) -> Result<()> {
+        let Some(path) = &self.source_path else {
+            return Ok(());
+        };
+
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)
+                .with_context(|| format!("Failed to create directory: {}", parent.display()))?;
+        }
+
+        let content = serde_json::to_string_pretty(self)
+            .context("Failed to serialize recording schedule")?;
+        fs::write(path, content)
+            .with_context(|| format!("Failed to write recording schedule: {}", path.display()))?;
+
+        debug!("Recording schedule saved to {}", path.display());
+        Ok(())
+    }
+
+    /// Add a job, dropping any whose window has already passed
+    pub fn add(&mut self, job: RecordingJob, now: i64) {
+        self.jobs.retain(|scheduled| scheduled.stop > now && *scheduled != job);
+        self.jobs.push(job);
+        self.jobs.sort_by_key(|job| job.start);
+    }
+
+    /// Jobs whose window hasn't ended yet, earliest first
+    pub fn pending(&self, now: i64) -> Vec<RecordingJob> {
+        self.jobs.iter().filter(|job| job.stop > now).cloned().collect()
+    }
+}
+
+/// Default folder for recordings: the user's videos folder, else the home folder
+pub fn default_dir() -> PathBuf {
+    dirs::video_dir()
+        .or_else(dirs::home_dir)
+        .unwrap_or_default()
+        .join("riptv")
+}
+
+/// Wait for each job's window on a tokio timer and capture it with ffmpeg
+///
+/// Captures stop when the returned set is dropped, so keep it alive for as long as they may run.
+pub fn spawn_scheduled(jobs: Vec<RecordingJob>, running: Arc<AtomicBool>) -> JoinSet<()> {
+    let mut tasks = JoinSet::new();
+    for job in jobs {
+        let running = Arc::clone(&running);
+        tasks.spawn(async move {
+            let wait = (job.start - unix_now()).max(0) as u64;
+            debug!("Recording '{}' on {} in {}s", job.title, job.channel, wait);
+            tokio::time::sleep(Duration::from_secs(wait)).await;
+            if running.load(Ordering::Relaxed) {
+                capture(&job).await;
+            }
+        });
+    }
+    tasks
+}
+
+async fn capture(job: &RecordingJob) {
+    let parent = job.output.parent().unwrap_or(Path::new("."));
+    if let Err(e) = fs::create_dir_all(parent) {
+        warn!("⚠️ Can't create {} for '{}': {}", parent.display(), job.title, e);
+        return;
+    }
+
+    info!("📼 Recording '{}' on {} to {}", job.title, job.channel, job.output.display());
+    match job.ffmpeg_command(unix_now()).status().await {
+        Ok(status) if status.success() => info!("✅ Finished recording '{}'", job.title),
+        Ok(status) => warn!("⚠️ Recording '{}' stopped early: ffmpeg exited with {}", job.title, status),
+        Err(e) => warn!("⚠️ Failed to start ffmpeg for '{}': {}", job.title, e),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::epg::Epg;
+
+    /// 2024-03-10 19:00:00 UTC
+    const NOW: i64 = 1_710_097_200;
+
+    fn guide() -> Epg {
+        Epg::parse_xmltv(
+            r#"<tv>
+                <programme start="20240310180000 +0000" stop="20240310190000 +0000" channel="bbc1.uk"><title>News</title></programme>
+                <programme start="20240310210000 +0100" stop="20240310223000 +0100" channel="bbc1.uk"><title>Match of the Day</title></programme>
+                <programme start="20240310200000 +0000" stop="20240310210000 +0000" channel="itv.uk"><title>Match of the Day</title></programme>
+            </tv>"#,
+        )
+    }
+
+    #[test]
+    fn test_programme_capture_window() {
+        let epg = guide();
+        let mut channel = Channel::new("BBC One".to_string(), "http://example.com/bbc1".to_string());
+        channel.tvg_id = Some("bbc1.uk".to_string());
+
+        let programme = epg.find_programme("bbc1.uk", " match of the day ", NOW).unwrap();
+        assert_eq!((programme.start, programme.stop), (NOW + 3_600, NOW + 9_000));
+
+        let job = RecordingJob::for_programme(&channel, programme, 2, NOW, Path::new("/recordings")).unwrap();
+        assert_eq!((job.start, job.stop), (NOW + 3_480, NOW + 9_120));
+        assert_eq!(job.duration_secs(), 5_640);
+        assert_eq!(job.output, Path::new("/recordings/BBC One - Match of the Day - 1710100800.ts"));
+
+        // Finished programmes aren't found; one still airing records from now
+        assert!(epg.find_programme("bbc1.uk", "News", NOW).is_none());
+        let news = epg.find_programme("bbc1.uk", "News", NOW - 600).unwrap();
+        let job = RecordingJob::for_programme(&channel, news, 2, NOW - 600, Path::new("/recordings")).unwrap();
+        assert_eq!((job.start, job.duration_secs()), (NOW - 600, 720));
+        assert!(RecordingJob::for_programme(&channel, news, 2, NOW + 120, Path::new("/recordings")).is_err());
+
+        let args: Vec<_> = job.ffmpeg_command(NOW).as_std().get_args().map(|arg| arg.to_string_lossy().into_owned()).collect();
+        assert!(args.windows(2).any(|pair| pair == ["-t", "120"]));
+    }
+}
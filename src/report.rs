@@ -0,0 +1,156 @@
+use anyhow::{Context, Result};
+use colored::*;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+
+use crate::playlist::Channel;
+use crate::utils::{is_valid_url, separator};
+
+/// A stream URL listed by more than one channel
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct DuplicateUrl {
+    pub url: String,
+    /// Names of the channels sharing it, in playlist order
+    pub channels: Vec<String>,
+}
+
+/// A channel whose URL can't be parsed
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct InvalidUrl {
+    pub name: String,
+    pub url: String,
+}
+
+/// Metadata diagnostics for a whole playlist, gathered in one pass for `riptv report`
+#[derive(Debug, Default, Serialize)]
+pub struct QualityReport {
+    pub total_channels: usize,
+    pub total_groups: usize,
+    pub groups: BTreeMap<String, usize>,
+    pub countries: BTreeMap<String, usize>,
+    pub languages: BTreeMap<String, usize>,
+    pub duplicate_urls: Vec<DuplicateUrl>,
+    pub missing_logo: Vec<String>,
+    pub missing_tvg_id: Vec<String>,
+    pub invalid_urls: Vec<InvalidUrl>,
+}
+
+impl QualityReport {
+    pub fn from_channels(channels: &[Channel]) -> Self {
+        let mut report = Self {
+            total_channels: channels.len(),
+            ..Self::default()
+        };
+        let mut by_url: BTreeMap<&str, Vec<String>> = BTreeMap::new();
+        let present = |value: &Option<String>| value.as_deref().is_some_and(|value| !value.trim().is_empty());
+
+        for channel in channels {
+            if let Some(group) = &channel.group {
+                *report.groups.entry(group.clone()).or_insert(0) += 1;
+            }
+            if let Some(country) = &channel.country {
+                *report.countries.entry(country.clone()).or_insert(0) += 1;
+            }
+            if let Some(language) = &channel.language {
+                *report.languages.entry(language.clone()).or_insert(0) += 1;
+            }
+            if !present(&channel.logo) {
+                report.missing_logo.push(channel.name.clone());
+            }
+            if !present(&channel.tvg_id) {
+                report.missing_tvg_id.push(channel.name.clone());
+            }
+
+            let url = channel.url.trim();
+            if !is_valid_url(url) {
+                report.invalid_urls.push(InvalidUrl { name: channel.name.clone(), url: url.to_string() });
+            }
+            by_url.entry(url).or_default().push(channel.name.clone());
+        }
+
+        report.total_groups = report.groups.len();
+        report.duplicate_urls = by_url
+            .into_iter()
+            .filter(|(_, names)| names.len() > 1)
+            .map(|(url, channels)| DuplicateUrl { url: url.to_string(), channels })
+            .collect();
+        report
+    }
+
+    pub fn show(&self, width: usize) {
+        println!("{}", "📋 Playlist Report".bright_cyan().bold());
+        println!("{}", separator('═', width).bright_blue());
+        println!("🎯 Total Channels: {}", self.total_channels.to_string().bright_green().bold());
+        println!("📁 Total Groups: {}", self.total_groups.to_string().bright_yellow().bold());
+        println!("🌍 Countries: {}", self.countries.len().to_string().bright_white());
+        println!("🗣️ Languages: {}", self.languages.len().to_string().bright_white());
+
+        let section = |title: &str, names: &[String]| {
+            let heading = format!("{} ({}):", title, names.len());
+            if names.is_empty() {
+                println!("\n✅ {}", heading.bright_green());
+                return;
+            }
+            println!("\n⚠️ {}", heading.bright_yellow().bold());
+            for name in names {
+                println!("  📺 {}", name.bright_white());
+            }
+        };
+
+        let duplicates: Vec<String> = self.duplicate_urls
+            .iter()
+            .map(|duplicate| format!("{} ({})", duplicate.url, duplicate.channels.join(", ")))
+            .collect();
+        let invalid: Vec<String> = self.invalid_urls
+            .iter()
+            .map(|invalid| format!("{} ({})", invalid.name, invalid.url))
+            .collect();
+
+        section("Duplicate URLs", &duplicates);
+        section("Invalid URLs", &invalid);
+        section("Missing logo", &self.missing_logo);
+        section("Missing tvg-id", &self.missing_tvg_id);
+    }
+
+    pub fn export<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        let path = path.as_ref();
+        let content = serde_json::to_string_pretty(self).context("Failed to serialize playlist report")? + "\n";
+        fs::write(path, content).with_context(|| format!("Failed to write playlist report: {}", path.display()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::playlist::PlaylistParser;
+
+    #[tokio::test]
+    async fn test_quality_report() {
+        let playlist = "#EXTM3U\n\
+            #EXTINF:-1 tvg-id=\"bbc1.uk\" tvg-logo=\"http://logos.example.com/bbc1.png\" tvg-country=\"UK\" tvg-language=\"English\" group-title=\"UK\",BBC One\n\
+            http://example.com/bbc1\n\
+            #EXTINF:-1 tvg-id=\"bbc1.uk\" tvg-country=\"UK\" group-title=\"UK\",BBC One HD\n\
+            http://example.com/bbc1\n\
+            #EXTINF:-1 tvg-logo=\"http://logos.example.com/cnn.png\" tvg-language=\"English\" group-title=\"News\",CNN\n\
+            http://example.com/cnn\n\
+            #EXTINF:-1 tvg-id=\" \",Broken\n\
+            http://[::1/stream\n";
+        let mut parser = PlaylistParser::new(false);
+        parser.parse_str(playlist.to_string()).await.unwrap();
+
+        let report = parser.quality_report();
+        assert_eq!(report.total_channels, 4);
+        assert_eq!(report.total_groups, 2);
+        assert_eq!(report.groups.into_iter().collect::<Vec<_>>(), [("News".to_string(), 1), ("UK".to_string(), 2)]);
+        assert_eq!(report.countries["UK"], 2);
+        assert_eq!(report.languages["English"], 2);
+        assert_eq!(report.duplicate_urls.len(), 1);
+        assert_eq!(report.duplicate_urls[0].url, "http://example.com/bbc1");
+        assert_eq!(report.duplicate_urls[0].channels, ["BBC One", "BBC One HD"]);
+        assert_eq!(report.missing_logo, ["BBC One HD", "Broken"]);
+        assert_eq!(report.missing_tvg_id, ["CNN", "Broken"]);
+        assert_eq!(report.invalid_urls.len(), 1);
+        assert_eq!(report.invalid_urls[0].name, "Broken");
+    }
+}
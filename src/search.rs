@@ -0,0 +1,227 @@
+use fuzzy_matcher::{skim::SkimMatcherV2, FuzzyMatcher};
+use std::collections::HashMap;
+
+use crate::playlist::{CaseMode, Channel, SearchField};
+
+/// What every search backend is told besides the query
+#[derive(Debug, Clone)]
+pub struct SearchOpts {
+    /// Metadata fields to match, each weighted by `SearchField::weight`
+    pub fields: Vec<SearchField>,
+    pub case_mode: CaseMode,
+}
+
+impl Default for SearchOpts {
+    fn default() -> Self {
+        Self {
+            fields: vec![SearchField::Name],
+            case_mode: CaseMode::default(),
+        }
+    }
+}
+
+/// Lowercased channel names and a first-letter map, built once per parse and reused by every query
+#[derive(Debug, Clone, Default)]
+pub struct SearchIndex {
+    names: Vec<String>,
+    prefixes: HashMap<char, Vec<usize>>,
+}
+
+impl SearchIndex {
+    pub fn clear(&mut self) {
+        self.names.clear();
+        self.prefixes.clear();
+    }
+
+    /// Index the channels past the ones already indexed
+    pub fn extend(&mut self, channels: &[Channel]) {
+        for (idx, channel) in channels.iter().enumerate().skip(self.names.len()) {
+            let name = channel.name.to_lowercase();
+            if let Some(first) = name.chars().next() {
+                self.prefixes.entry(first).or_default().push(idx);
+            }
+            self.names.push(name);
+        }
+    }
+
+    /// Lowercased name of the channel at `idx`
+    pub fn name(&self, idx: usize) -> &str {
+        &self.names[idx]
+    }
+
+    /// Channels whose lowercased name starts with `prefix`, in channel order
+    pub fn with_prefix(&self, prefix: &str) -> Vec<usize> {
+        let Some(first) = prefix.chars().next() else {
+            return (0..self.names.len()).collect();
+        };
+        self.prefixes
+            .get(&first)
+            .map(|indices| indices.iter().copied().filter(|&idx| self.names[idx].starts_with(prefix)).collect())
+            .unwrap_or_default()
+    }
+
+    /// Whether this index was built from exactly `channels`
+    fn covers(&self, channels: &[Channel]) -> bool {
+        self.names.len() == channels.len()
+    }
+}
+
+/// A way of matching a query against channels
+pub trait ChannelSearch: Send + Sync {
+    /// `(score, index into channels)` for each match, in channel order; higher scores are better
+    fn search(&self, channels: &[Channel], query: &str, opts: &SearchOpts) -> Vec<(i64, usize)>;
+
+    /// Like `search`, but may reuse `index` instead of recomputing per-channel data on every query
+    fn search_indexed(&self, channels: &[Channel], _index: &SearchIndex, query: &str, opts: &SearchOpts) -> Vec<(i64, usize)> {
+        self.search(channels, query, opts)
+    }
+}
+
+/// The backend `config.fuzzy_search` selects
+pub fn backend(fuzzy: bool) -> Box<dyn ChannelSearch> {
+    if fuzzy {
+        Box::new(FuzzySearch)
+    } else {
+        Box::new(SubstringSearch)
+    }
+}
+
+/// Best field score for one channel
+fn best_field_score<F>(channel: &Channel, opts: &SearchOpts, score: F) -> Option<i64>
+where
+    F: Fn(&str) -> Option<i64>,
+{
+    opts.fields
+        .iter()
+        .filter_map(|field| score(field.value(channel)?).map(|score| score * field.weight()))
+        .max()
+}
+
+/// Skim-style fuzzy matching, where letters may be skipped: "bbcne" finds "BBC One"
+#[derive(Debug, Clone, Copy, Default)]
+pub struct FuzzySearch;
+
+impl ChannelSearch for FuzzySearch {
+    fn search(&self, channels: &[Channel], query: &str, opts: &SearchOpts) -> Vec<(i64, usize)> {
+        let matcher = match opts.case_mode {
+            CaseMode::Ignore => SkimMatcherV2::default().ignore_case(),
+            CaseMode::Respect => SkimMatcherV2::default().respect_case(),
+            CaseMode::Smart => SkimMatcherV2::default().smart_case(),
+        };
+
+        channels
+            .iter()
+            .enumerate()
+            .filter_map(|(idx, channel)| {
+                best_field_score(channel, opts, |value| matcher.fuzzy_match(value, query)).map(|score| (score, idx))
+            })
+            .collect()
+    }
+}
+
+/// Plain substring matching; exact and prefix matches score above matches further in
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SubstringSearch;
+
+impl SubstringSearch {
+    fn score(value: &str, query: &str) -> Option<i64> {
+        if value == query {
+            Some(300)
+        } else if value.starts_with(query) {
+            Some(200)
+        } else if value.contains(query) {
+            Some(100)
+        } else {
+            None
+        }
+    }
+
+    fn matches(channels: &[Channel], index: Option<&SearchIndex>, query: &str, opts: &SearchOpts) -> Vec<(i64, usize)> {
+        let ignore_case = match opts.case_mode {
+            CaseMode::Ignore => true,
+            CaseMode::Respect => false,
+            CaseMode::Smart => !query.chars().any(char::is_uppercase),
+        };
+        let query = if ignore_case { query.to_lowercase() } else { query.to_string() };
+        let index = index.filter(|index| ignore_case && index.covers(channels));
+
+        channels
+            .iter()
+            .enumerate()
+            .filter_map(|(idx, channel)| {
+                opts.fields
+                    .iter()
+                    .filter_map(|&field| {
+                        let score = match index {
+                            Some(index) if field == SearchField::Name => Self::score(index.name(idx), &query),
+                            _ if ignore_case => Self::score(&field.value(channel)?.to_lowercase(), &query),
+                            _ => Self::score(field.value(channel)?, &query),
+                        };
+                        score.map(|score| score * field.weight())
+                    })
+                    .max()
+                    .map(|score| (score, idx))
+            })
+            .collect()
+    }
+}
+
+impl ChannelSearch for SubstringSearch {
+    fn search(&self, channels: &[Channel], query: &str, opts: &SearchOpts) -> Vec<(i64, usize)> {
+        Self::matches(channels, None, query, opts)
+    }
+
+    fn search_indexed(&self, channels: &[Channel], index: &SearchIndex, query: &str, opts: &SearchOpts) -> Vec<(i64, usize)> {
+        Self::matches(channels, Some(index), query, opts)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn channels() -> Vec<Channel> {
+        ["BBC One", "BBC Two", "The BBC Show", "ITV"]
+            .iter()
+            .map(|name| Channel::new(name.to_string(), format!("http://example.com/{}", name.len())))
+            .collect()
+    }
+
+    fn names<'a>(channels: &'a [Channel], matches: &[(i64, usize)]) -> Vec<&'a str> {
+        matches.iter().map(|&(_, idx)| channels[idx].name.as_str()).collect()
+    }
+
+    #[test]
+    fn test_fuzzy_backend() {
+        let channels = channels();
+        let backend: Box<dyn ChannelSearch> = backend(true);
+        let opts = SearchOpts::default();
+
+        let matches = backend.search(&channels, "bbcne", &opts);
+        assert_eq!(names(&channels, &matches), ["BBC One"]);
+        assert_eq!(backend.search(&channels, "itv", &opts).len(), 1);
+
+        let respect = SearchOpts { case_mode: CaseMode::Respect, ..SearchOpts::default() };
+        assert!(backend.search(&channels, "itv", &respect).is_empty());
+    }
+
+    #[test]
+    fn test_substring_backend() {
+        let channels = channels();
+        let backend: Box<dyn ChannelSearch> = backend(false);
+        let opts = SearchOpts::default();
+
+        // No fuzzy gaps: letters must appear together
+        assert!(backend.search(&channels, "bbcne", &opts).is_empty());
+
+        let matches = backend.search(&channels, "bbc", &opts);
+        assert_eq!(names(&channels, &matches), ["BBC One", "BBC Two", "The BBC Show"]);
+        let scores: Vec<i64> = matches.iter().map(|&(score, _)| score).collect();
+        assert_eq!(scores, [600, 600, 300]);
+        assert_eq!(backend.search(&channels, "itv", &opts), [(900, 3)]);
+
+        let smart = SearchOpts { case_mode: CaseMode::Smart, ..SearchOpts::default() };
+        assert_eq!(backend.search(&channels, "Bbc", &smart).len(), 0);
+        assert_eq!(backend.search(&channels, "BBC", &smart).len(), 3);
+    }
+}
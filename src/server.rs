@@ -0,0 +1,348 @@
+use anyhow::{Context, Result};
+use colored::*;
+use serde_json::{json, Value};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tracing::{debug, info, warn};
+
+use crate::player::IptvPlayer;
+use crate::playlist::Channel;
+
+/// Largest request (headers plus body) the server will accept
+const MAX_REQUEST_BYTES: usize = 64 * 1024;
+
+/// What the HTTP API needs from the player, so handlers can be tested with a mock
+pub trait RemoteControl {
+    fn channels(&self) -> &[Channel];
+
+    /// Indices of channels matching `query`, best match first
+    fn search(&self, query: &str) -> Vec<usize>;
+
+    /// Start playing the channel at `index` without waiting for playback to end
+    fn play(&mut self, index: usize) -> Result<()>;
+}
+
+impl RemoteControl for IptvPlayer {
+    fn channels(&self) -> &[Channel] {
+        self.parser().get_channels()
+    }
+
+    fn search(&self, query: &str) -> Vec<usize> {
+        self.parser()
+            .search_scored(query)
+            .into_iter()
+            .take(self.config().max_search_results)
+            .map(|(_, idx)| idx)
+            .collect()
+    }
+
+    fn play(&mut self, index: usize) -> Result<()> {
+        let channel = self.channels()[index].clone();
+        info!("🎬 Remote play: {}", channel.name.bright_green().bold());
+        self.start_player(&channel)
+    }
+}
+
+/// A parsed HTTP request
+#[derive(Debug)]
+pub struct Request {
+    pub method: String,
+    pub path: String,
+    pub query: Vec<(String, String)>,
+    pub body: Vec<u8>,
+}
+
+/// A JSON response with its HTTP status code
+#[derive(Debug)]
+pub struct Response {
+    pub status: u16,
+    pub body: Value,
+}
+
+impl Response {
+    fn ok(body: Value) -> Self {
+        Self { status: 200, body }
+    }
+
+    fn error(status: u16, message: &str) -> Self {
+        Self {
+            status,
+            body: json!({ "error": message }),
+        }
+    }
+}
+
+fn channel_json(index: usize, channel: &Channel) -> Value {
+    let mut value = serde_json::to_value(channel).unwrap_or_default();
+    value["index"] = json!(index);
+    value
+}
+
+/// Route a request to its handler
+///
+/// - `GET /channels` lists every channel
+/// - `GET /search?q=<query>` lists matching channels, best first
+/// - `POST /play` with `{"index": N}` starts playback of channel N
+pub fn handle_request<R: RemoteControl>(control: &mut R, request: &Request) -> Response {
+    match (request.method.as_str(), request.path.as_str()) {
+        ("GET", "/channels") => {
+            let channels = control.channels()
+                .iter()
+                .enumerate()
+                .map(|(idx, channel)| channel_json(idx, channel))
+                .collect();
+            Response::ok(Value::Array(channels))
+        }
+        ("GET", "/search") => {
+            let Some((_, query)) = request.query.iter().find(|(key, _)| key == "q") else {
+                return Response::error(400, "missing query parameter 'q'");
+            };
+
+            let results = control.search(query)
+                .into_iter()
+                .map(|idx| channel_json(idx, &control.channels()[idx]))
+                .collect();
+            Response::ok(Value::Array(results))
+        }
+        ("POST", "/play") => {
+            let index = serde_json::from_slice::<Value>(&request.body)
+                .ok()
+                .and_then(|body| body.get("index").and_then(Value::as_u64));
+            let Some(index) = index.map(|index| index as usize) else {
+                return Response::error(400, "expected a JSON body like {\"index\": 0}");
+            };
+
+            if index >= control.channels().len() {
+                return Response::error(404, &format!("no channel at index {}", index));
+            }
+
+            match control.play(index) {
+                Ok(()) => Response::ok(json!({ "playing": channel_json(index, &control.channels()[index]) })),
+                Err(e) => Response::error(500, &format!("failed to start playback: {}", e)),
+            }
+        }
+        (_, "/channels" | "/search" | "/play") => Response::error(405, "method not allowed"),
+        _ => Response::error(404, "not found"),
+    }
+}
+
+/// Parse a raw HTTP/1.1 request, returning `None` until the full request has arrived
+///
+/// Fails with a 400 response for a malformed head and a 413 when the declared body can never fit.
+pub fn parse_request(raw: &[u8]) -> Result<Option<Request>, Response> {
+    let Some(header_end) = raw.windows(4).position(|window| window == b"\r\n\r\n") else {
+        return Ok(None);
+    };
+    let bad_request = || Response::error(400, "malformed request");
+    let head = std::str::from_utf8(&raw[..header_end]).map_err(|_| bad_request())?;
+    let mut lines = head.lines();
+
+    let mut request_line = lines.next().unwrap_or_default().split_whitespace();
+    let (Some(method), Some(target)) = (request_line.next(), request_line.next()) else {
+        return Err(bad_request());
+    };
+
+    let content_length = match lines
+        .filter_map(|line| line.split_once(':'))
+        .find(|(name, _)| name.trim().eq_ignore_ascii_case("content-length"))
+    {
+        Some((_, value)) => value.trim().parse::<usize>().map_err(|_| bad_request())?,
+        None => 0,
+    };
+
+    let body_start = header_end + 4;
+    let body_end = match body_start.checked_add(content_length) {
+        Some(end) if content_length <= MAX_REQUEST_BYTES => end,
+        _ => return Err(Response::error(413, "request too large")),
+    };
+    if raw.len() < body_end {
+        return Ok(None);
+    }
+
+    let (path, query) = target.split_once('?').unwrap_or((target, ""));
+    Ok(Some(Request {
+        method: method.to_string(),
+        path: path.to_string(),
+        query: url::form_urlencoded::parse(query.as_bytes()).into_owned().collect(),
+        body: raw[body_start..body_end].to_vec(),
+    }))
+}
+
+fn reason_phrase(status: u16) -> &'static str {
+    match status {
+        200 => "OK",
+        400 => "Bad Request",
+        404 => "Not Found",
+        405 => "Method Not Allowed",
+        413 => "Payload Too Large",
+        _ => "Internal Server Error",
+    }
+}
+
+async fn handle_connection<R: RemoteControl>(control: &mut R, mut stream: TcpStream) -> Result<()> {
+    let mut raw = Vec::new();
+    let mut buf = [0u8; 4096];
+
+    let response = loop {
+        let read = tokio::time::timeout(Duration::from_secs(5), stream.read(&mut buf))
+            .await
+            .context("Timed out reading request")??;
+        if read == 0 {
+            return Ok(());
+        }
+
+        raw.extend_from_slice(&buf[..read]);
+        match parse_request(&raw) {
+            Ok(Some(request)) => {
+                debug!("{} {}", request.method, request.path);
+                break handle_request(control, &request);
+            }
+            Ok(None) => {}
+            Err(response) => break response,
+        }
+        if raw.len() > MAX_REQUEST_BYTES {
+            break Response::error(413, "request too large");
+        }
+    };
+
+    let body = response.body.to_string();
+    let head = format!(
+        "HTTP/1.1 {} {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+        response.status,
+        reason_phrase(response.status),
+        body.len()
+    );
+    stream.write_all(head.as_bytes()).await?;
+    stream.write_all(body.as_bytes()).await?;
+    stream.shutdown().await?;
+    Ok(())
+}
+
+/// Serve the remote control API until shutdown is requested
+pub async fn serve(player: &mut IptvPlayer, bind: &str, port: u16, running: Arc<AtomicBool>) -> Result<()> {
+    let listener = TcpListener::bind((bind, port))
+        .await
+        .with_context(|| format!("Failed to bind to {}:{}", bind, port))?;
+
+    println!("{}", format!("🌐 Remote control listening on http://{}:{}", bind, port).bright_cyan().bold());
+    println!("   GET  /channels");
+    println!("   GET  /search?q=<query>");
+    println!("   POST /play {{\"index\": N}}");
+
+    while running.load(Ordering::Relaxed) {
+        tokio::select! {
+            accepted = listener.accept() => {
+                let (stream, peer) = accepted?;
+                debug!("Connection from {}", peer);
+                if let Err(e) = handle_connection(player, stream).await {
+                    warn!("⚠️ Request from {} failed: {}", peer, e);
+                }
+            }
+            _ = tokio::time::sleep(Duration::from_millis(500)) => {}
+        }
+    }
+
+    player.cleanup().await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct MockPlayer {
+        channels: Vec<Channel>,
+        played: Vec<usize>,
+    }
+
+    impl RemoteControl for MockPlayer {
+        fn channels(&self) -> &[Channel] {
+            &self.channels
+        }
+
+        fn search(&self, query: &str) -> Vec<usize> {
+            (0..self.channels.len())
+                .filter(|&idx| self.channels[idx].name.contains(query))
+                .collect()
+        }
+
+        fn play(&mut self, index: usize) -> Result<()> {
+            self.played.push(index);
+            Ok(())
+        }
+    }
+
+    fn mock() -> MockPlayer {
+        MockPlayer {
+            channels: vec![
+                Channel::new("BBC One".to_string(), "http://example.com/bbc1".to_string()),
+                Channel::new("CNN".to_string(), "http://example.com/cnn".to_string()),
+            ],
+            played: Vec::new(),
+        }
+    }
+
+    fn request(raw: &str) -> Request {
+        parse_request(raw.as_bytes()).ok().flatten().unwrap()
+    }
+
+    #[test]
+    fn test_channels_and_search() {
+        let mut player = mock();
+
+        let response = handle_request(&mut player, &request("GET /channels HTTP/1.1\r\n\r\n"));
+        assert_eq!(response.status, 200);
+        assert_eq!(response.body[1]["name"], "CNN");
+        assert_eq!(response.body[1]["index"], 1);
+
+        let response = handle_request(&mut player, &request("GET /search?q=BBC%20One HTTP/1.1\r\n\r\n"));
+        assert_eq!(response.status, 200);
+        assert_eq!(response.body.as_array().unwrap().len(), 1);
+        assert_eq!(response.body[0]["url"], "http://example.com/bbc1");
+
+        let response = handle_request(&mut player, &request("GET /nope HTTP/1.1\r\n\r\n"));
+        assert_eq!(response.status, 404);
+    }
+
+    #[test]
+    fn test_play() {
+        let mut player = mock();
+
+        let raw = "POST /play HTTP/1.1\r\nContent-Length: 12\r\n\r\n{\"index\": 1}";
+        let response = handle_request(&mut player, &request(raw));
+        assert_eq!(response.status, 200);
+        assert_eq!(response.body["playing"]["name"], "CNN");
+        assert_eq!(player.played, vec![1]);
+
+        let raw = "POST /play HTTP/1.1\r\nContent-Length: 12\r\n\r\n{\"index\": 7}";
+        assert_eq!(handle_request(&mut player, &request(raw)).status, 404);
+
+        let response = handle_request(&mut player, &request("GET /play HTTP/1.1\r\n\r\n"));
+        assert_eq!(response.status, 405);
+        assert_eq!(player.played, vec![1]);
+    }
+
+    #[test]
+    fn test_parse_request_waits_for_body() {
+        assert!(parse_request(b"POST /play HTTP/1.1\r\nContent-Length: 12\r\n\r\n{\"ind").unwrap().is_none());
+        assert!(parse_request(b"GET /channels HTTP/1.1\r\n").unwrap().is_none());
+    }
+
+    #[test]
+    fn test_parse_request_rejects_huge_content_length() {
+        let oversized = format!("POST /play HTTP/1.1\r\nContent-Length: {}\r\n\r\n{{}}", MAX_REQUEST_BYTES + 1);
+        assert_eq!(parse_request(oversized.as_bytes()).unwrap_err().status, 413);
+
+        let overflowing = format!("POST /play HTTP/1.1\r\nContent-Length: {}\r\n\r\n{{}}", usize::MAX);
+        assert_eq!(parse_request(overflowing.as_bytes()).unwrap_err().status, 413);
+    }
+
+    #[test]
+    fn test_parse_request_rejects_malformed_head() {
+        assert_eq!(parse_request(b"GET\r\n\r\n").unwrap_err().status, 400);
+        assert_eq!(parse_request(b"GET /\xff HTTP/1.1\r\n\r\n").unwrap_err().status, 400);
+        assert_eq!(parse_request(b"POST /play HTTP/1.1\r\nContent-Length: lots\r\n\r\n").unwrap_err().status, 400);
+    }
+}
@@ -0,0 +1,144 @@
+use std::path::Path;
+use url::Url;
+
+use crate::playlist::Channel;
+
+/// Check whether a playlist is an HLS master playlist listing variant streams
+pub fn is_master(content: &str) -> bool {
+    content.lines().any(|line| line.trim_start().starts_with("#EXT-X-STREAM-INF:"))
+}
+
+/// A variant stream's `#EXT-X-STREAM-INF` attributes
+#[derive(Debug, Default, PartialEq)]
+struct Variant {
+    bandwidth: Option<u64>,
+    resolution: Option<String>,
+    name: Option<String>,
+}
+
+impl Variant {
+    fn parse(attributes: &str) -> Self {
+        let mut variant = Self::default();
+
+        for (key, value) in split_attributes(attributes) {
+            match key.to_ascii_uppercase().as_str() {
+                "BANDWIDTH" => variant.bandwidth = value.parse().ok(),
+                "RESOLUTION" => variant.resolution = Some(value),
+                "NAME" => variant.name = Some(value),
+                _ => {}
+            }
+        }
+
+        variant
+    }
+
+    /// Human-readable label such as `1280x720 2.5 Mbps`
+    fn label(&self) -> String {
+        let bandwidth = self.bandwidth.map(|bps| format!("{:.1} Mbps", bps as f64 / 1_000_000.0));
+        match (&self.resolution, bandwidth) {
+            (Some(resolution), Some(bandwidth)) => format!("{} {}", resolution, bandwidth),
+            (Some(resolution), None) => resolution.clone(),
+            (None, Some(bandwidth)) => bandwidth,
+            (None, None) => "variant".to_string(),
+        }
+    }
+}
+
+/// Split `KEY=value,KEY="quoted, value"` attribute lists
+fn split_attributes(attributes: &str) -> Vec<(String, String)> {
+    let mut pairs = Vec::new();
+    let mut rest = attributes.trim();
+
+    while let Some((key, after)) = rest.split_once('=') {
+        let (value, remainder) = match after.strip_prefix('"') {
+            Some(quoted) => match quoted.split_once('"') {
+                Some((value, remainder)) => (value, remainder),
+                None => (quoted, ""),
+            },
+            None => after.split_once(',').map_or((after, ""), |(value, remainder)| (value, remainder)),
+        };
+
+        pairs.push((key.trim().to_string(), value.trim().to_string()));
+        rest = remainder.trim_start_matches(',').trim_start();
+    }
+
+    pairs
+}
+
+/// Resolve a variant URI against the master playlist's URL or file path
+fn resolve_uri(uri: &str, source: Option<&str>) -> String {
+    if Url::parse(uri).is_ok() {
+        return uri.to_string();
+    }
+
+    match source {
+        Some(source) if source.starts_with("http://") || source.starts_with("https://") => Url::parse(source)
+            .and_then(|base| base.join(uri))
+            .map(|url| url.to_string())
+            .unwrap_or_else(|_| uri.to_string()),
+        Some(source) => Path::new(source)
+            .parent()
+            .map(|dir| dir.join(uri).to_string_lossy().into_owned())
+            .unwrap_or_else(|| uri.to_string()),
+        None => uri.to_string(),
+    }
+}
+
+/// Expand a master playlist into one channel per variant, grouped under `title`
+pub fn parse_master(content: &str, title: &str, source: Option<&str>) -> Vec<Channel> {
+    let mut channels = Vec::new();
+    let mut pending = None;
+
+    for line in content.lines() {
+        let line = line.trim();
+
+        if let Some(attributes) = line.strip_prefix("#EXT-X-STREAM-INF:") {
+            pending = Some(Variant::parse(attributes));
+        } else if line.is_empty() || line.starts_with('#') {
+            continue;
+        } else if let Some(variant) = pending.take() {
+            let name = format!("{} {}", variant.name.as_deref().unwrap_or(title), variant.label());
+            let mut channel = Channel::new(name, resolve_uri(line, source));
+            channel.group = Some(title.to_string());
+            channels.push(channel);
+        }
+    }
+
+    channels
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MASTER: &str = "#EXTM3U\n\
+        #EXT-X-VERSION:3\n\
+        #EXT-X-STREAM-INF:BANDWIDTH=800000,RESOLUTION=640x360,CODECS=\"avc1.4d401e,mp4a.40.2\"\n\
+        low/index.m3u8\n\
+        #EXT-X-STREAM-INF:BANDWIDTH=2500000,RESOLUTION=1280x720\n\
+        mid/index.m3u8\n\
+        #EXT-X-STREAM-INF:BANDWIDTH=5000000,RESOLUTION=1920x1080,NAME=\"Full HD\"\n\
+        http://cdn.example.com/hi/index.m3u8\n";
+
+    #[test]
+    fn test_expand_master_playlist() {
+        assert!(is_master(MASTER));
+        assert!(!is_master("#EXTM3U\n#EXTINF:-1,CNN\nhttp://example.com/cnn\n"));
+
+        let channels = parse_master(MASTER, "master", Some("http://example.com/live/master.m3u8"));
+        assert_eq!(channels.len(), 3);
+
+        assert_eq!(channels[0].name, "master 640x360 0.8 Mbps");
+        assert_eq!(channels[0].url, "http://example.com/live/low/index.m3u8");
+        assert_eq!(channels[1].name, "master 1280x720 2.5 Mbps");
+        assert_eq!(channels[2].name, "Full HD 1920x1080 5.0 Mbps");
+        assert_eq!(channels[2].url, "http://cdn.example.com/hi/index.m3u8");
+        assert!(channels.iter().all(|channel| channel.group.as_deref() == Some("master")));
+    }
+
+    #[test]
+    fn test_resolve_uri_against_file() {
+        assert_eq!(resolve_uri("low.m3u8", Some("/srv/tv/master.m3u8")), "/srv/tv/low.m3u8");
+        assert_eq!(resolve_uri("low.m3u8", None), "low.m3u8");
+    }
+}
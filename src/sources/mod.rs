@@ -0,0 +1,4 @@
+//! Parsers for playlist formats other than M3U
+
+pub mod hls;
+pub mod pls;
@@ -0,0 +1,88 @@
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use crate::playlist::Channel;
+use crate::utils::extract_domain;
+
+/// Check whether a playlist should be parsed as PLS, by extension or by its `[playlist]` header
+pub fn is_pls(path: &Path, content: &str) -> bool {
+    let by_extension = path
+        .extension()
+        .map(|ext| ext.eq_ignore_ascii_case("pls"))
+        .unwrap_or(false);
+
+    by_extension
+        || content
+            .trim_start()
+            .get(..10)
+            .map(|head| head.eq_ignore_ascii_case("[playlist]"))
+            .unwrap_or(false)
+}
+
+/// Parse an INI-style PLS playlist (`File1=`, `Title1=`, ...) into channels
+pub fn parse(content: &str) -> Vec<Channel> {
+    // Entries are numbered and their keys may appear in any order
+    let mut entries: BTreeMap<u32, (Option<String>, Option<String>)> = BTreeMap::new();
+
+    for line in content.lines() {
+        let line = line.trim();
+        let Some((key, value)) = line.split_once('=') else {
+            continue;
+        };
+
+        let key = key.trim().to_ascii_lowercase();
+        let value = value.trim().to_string();
+
+        if let Some(index) = key.strip_prefix("file").and_then(|n| n.parse().ok()) {
+            entries.entry(index).or_default().0 = Some(value);
+        } else if let Some(index) = key.strip_prefix("title").and_then(|n| n.parse().ok()) {
+            entries.entry(index).or_default().1 = Some(value);
+        }
+    }
+
+    entries
+        .into_values()
+        .filter_map(|(file, title)| {
+            let url = file.filter(|url| !url.is_empty())?;
+            let name = title
+                .filter(|title| !title.is_empty())
+                .or_else(|| extract_domain(&url))
+                .unwrap_or_else(|| url.clone());
+
+            Some(Channel::new(name, url))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_pls() {
+        let content = "[playlist]\n\
+            File1=http://radio.example.com/one\n\
+            Title1=Station One\n\
+            Length1=-1\n\
+            title2=Station Two\n\
+            file2=http://radio.example.com/two\n\
+            File3=http://stream.example.org:8000/live\n\
+            NumberOfEntries=3\n\
+            Version=2\n";
+
+        let channels = parse(content);
+        assert_eq!(channels.len(), 3);
+        assert_eq!(channels[0].name, "Station One");
+        assert_eq!(channels[0].url, "http://radio.example.com/one");
+        assert_eq!(channels[1].name, "Station Two");
+        assert_eq!(channels[1].url, "http://radio.example.com/two");
+        assert_eq!(channels[2].name, "stream.example.org");
+    }
+
+    #[test]
+    fn test_is_pls() {
+        assert!(is_pls(Path::new("radio.PLS"), ""));
+        assert!(is_pls(Path::new("radio.txt"), "\n[Playlist]\nFile1=x"));
+        assert!(!is_pls(Path::new("tv.m3u"), "#EXTM3U\n"));
+    }
+}
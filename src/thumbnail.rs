@@ -0,0 +1,99 @@
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use tracing::warn;
+
+use crate::utils::{cache_dir, fnv1a, is_command_available};
+
+/// Grabs frames from streams
+const GRABBER: &str = "ffmpeg";
+/// Draws the grabbed frame in the terminal
+const VIEWER: &str = "chafa";
+
+/// Where grabbed frames are kept, if `preview_thumbnail` is on and both tools are installed
+pub fn thumbnail_dir(enabled: bool) -> Option<PathBuf> {
+    if !enabled {
+        return None;
+    }
+    if !is_command_available(GRABBER) || !is_command_available(VIEWER) {
+        warn!("⚠️ preview_thumbnail needs {} and {}, using the text preview", GRABBER, VIEWER);
+        return None;
+    }
+
+    let dir = cache_dir()?.join("thumbnails");
+    match std::fs::create_dir_all(&dir) {
+        Ok(()) => Some(dir),
+        Err(e) => {
+            warn!("⚠️ Failed to create thumbnail cache {}: {}", dir.display(), e);
+            None
+        }
+    }
+}
+
+/// Cached frame for a stream URL, named by the URL's FNV-1a hash
+pub fn cache_path(dir: &Path, url: &str) -> PathBuf {
+    dir.join(format!("{:016x}.jpg", fnv1a(url.as_bytes())))
+}
+
+/// Grab a single frame from `url` into `output`
+pub fn grab_command(url: &str, output: &Path) -> Command {
+    let mut cmd = Command::new(GRABBER);
+    cmd.args(["-y", "-loglevel", "error", "-i", url, "-frames:v", "1", "-vf", "scale=320:-1"]);
+    cmd.arg(output);
+    cmd
+}
+
+/// Shell line skim runs for the preview: grab the frame unless cached, draw it, then print `caption`
+pub fn preview_command(url: &str, output: &Path, caption: &str) -> String {
+    let grab = grab_command(url, output);
+    let grab = std::iter::once(grab.get_program())
+        .chain(grab.get_args())
+        .map(|arg| shell_quote(&arg.to_string_lossy()))
+        .collect::<Vec<_>>()
+        .join(" ");
+    let output = shell_quote(&output.to_string_lossy());
+
+    format!(
+        "{{ test -s {output} || timeout 10 {grab} </dev/null; }} 2>/dev/null; \
+         {VIEWER} --size=40x20 {output} 2>/dev/null || echo 'No thumbnail available'; \
+         printf '%s\\n' {caption}",
+        caption = shell_quote(caption),
+    )
+}
+
+fn shell_quote(value: &str) -> String {
+    format!("'{}'", value.replace('\'', r"'\''"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cache_path_per_url() {
+        let dir = Path::new("/cache/riptv/thumbnails");
+        let first = cache_path(dir, "http://example.com/bbc.m3u8");
+        assert_eq!(first, cache_path(dir, "http://example.com/bbc.m3u8"));
+        assert_ne!(first, cache_path(dir, "http://example.com/itv.m3u8"));
+        assert_eq!(first.parent(), Some(dir));
+        assert_eq!(first.extension().unwrap(), "jpg");
+        assert_eq!(first.file_stem().unwrap().len(), 16);
+    }
+
+    #[test]
+    fn test_grab_command() {
+        let output = Path::new("/cache/frame.jpg");
+        let command = grab_command("http://example.com/live.ts", output);
+        let args: Vec<_> = command.get_args().map(|arg| arg.to_string_lossy().into_owned()).collect();
+        assert_eq!(command.get_program(), "ffmpeg");
+        assert_eq!(
+            args,
+            ["-y", "-loglevel", "error", "-i", "http://example.com/live.ts", "-frames:v", "1", "-vf", "scale=320:-1", "/cache/frame.jpg"]
+        );
+
+        let preview = preview_command("http://example.com/it's.ts", output, "BBC One");
+        assert!(preview.starts_with("{ test -s '/cache/frame.jpg' || timeout 10 'ffmpeg' "));
+        assert!(preview.contains(r"'http://example.com/it'\''s.ts'"));
+        assert!(preview.contains("chafa --size=40x20 '/cache/frame.jpg'"));
+        assert!(preview.ends_with("printf '%s\\n' 'BBC One'"));
+    }
+}
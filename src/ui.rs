@@ -1,26 +1,140 @@
-use anyhow::Result;
+use anyhow::{Context, Result};
 use colored::*;
 use skim::prelude::*;
 use std::borrow::Cow;
-use std::io::Cursor;
-use tracing::debug;
+use std::collections::HashMap;
+use std::io::{self, IsTerminal, Write};
+use std::future::Future;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+use tokio::sync::{oneshot, watch};
+use tracing::{debug, info, warn};
 
+use crate::catchup;
 use crate::config::Config;
-use crate::playlist::Channel;
-use crate::utils::terminal;
+use crate::playlist::{self, CaseMode, Channel};
+use crate::thumbnail;
+use crate::utils::{browser_command, extract_domain, fnv1a, format_duration, terminal};
+
+/// Colors cycled through to tell groups apart in the channel list
+const DARK_PALETTE: &[Color] = &[
+    Color::BrightCyan,
+    Color::BrightGreen,
+    Color::BrightYellow,
+    Color::BrightMagenta,
+    Color::BrightBlue,
+    Color::BrightRed,
+    Color::Cyan,
+    Color::Green,
+    Color::Yellow,
+    Color::Magenta,
+];
+
+const LIGHT_PALETTE: &[Color] = &[
+    Color::Blue,
+    Color::Green,
+    Color::Magenta,
+    Color::Red,
+    Color::Cyan,
+    Color::Yellow,
+];
+
+/// Map a group name to a stable palette color for the given color scheme
+///
+/// Uses FNV-1a so the mapping doesn't change between runs or Rust versions.
+/// Returns `None` for schemes without colors (e.g. "none").
+pub fn group_color(group: &str, color_scheme: &str) -> Option<Color> {
+    let palette = match color_scheme {
+        "dark" => DARK_PALETTE,
+        "light" => LIGHT_PALETTE,
+        _ => return None,
+    };
+
+    let hash = fnv1a(group.as_bytes());
+
+    Some(palette[(hash % palette.len() as u64) as usize])
+}
 
 #[derive(Debug, Clone)]
 pub struct ChannelItem {
     pub channel: Channel,
     pub display_text: String,
+    pub group_color: Option<Color>,
+    /// Cached frame shown above the text preview, when thumbnails are on
+    pub thumbnail: Option<PathBuf>,
+    /// A section title row rather than a channel
+    pub header: bool,
+    /// User tags from `config.channel_tags`
+    pub tags: Vec<String>,
+}
+
+impl ChannelItem {
+    fn header(title: &str) -> Arc<Self> {
+        Arc::new(Self {
+            channel: Channel::new(title.to_string(), String::new()),
+            display_text: format!("── {} ──", title),
+            group_color: None,
+            thumbnail: None,
+            header: true,
+            tags: Vec::new(),
+        })
+    }
+
+    /// Command that opens the channel's URL in the default browser instead of the player
+    pub fn browser_command(&self) -> std::process::Command {
+        browser_command(self.channel.url.trim())
+    }
 }
 
 impl SkimItem for ChannelItem {
-fn text(&self) -> Cow<'_, str> {
+    fn text(&self) -> Cow<'_, str> {
+        // Headers have nothing to match, so they drop out as soon as a query is typed
+        if self.header {
+            return Cow::Borrowed("");
+        }
+        Cow::Borrowed(&self.display_text)
+    }
+
+    fn output(&self) -> Cow<'_, str> {
         Cow::Borrowed(&self.display_text)
     }
 
+    fn display<'a>(&'a self, context: DisplayContext<'a>) -> AnsiString<'a> {
+        if self.header {
+            return AnsiString::parse(&self.display_text.bright_black().bold().to_string());
+        }
+
+        let mut text = match (&self.channel.group, self.group_color) {
+            (Some(group), Some(color)) if self.display_text.starts_with('[') => {
+                let tag = format!("[{}]", group).color(color).to_string();
+                AnsiString::parse(&format!("{}{}", tag, &self.display_text[group.len() + 2..]))
+            }
+            _ => AnsiString::from(self.display_text.as_str()),
+        };
+
+        let highlights = match context.matches {
+            Matches::CharIndices(indices) => indices
+                .iter()
+                .map(|&idx| (context.highlight_attr, (idx as u32, idx as u32 + 1)))
+                .collect(),
+            Matches::CharRange(start, end) => vec![(context.highlight_attr, (start as u32, end as u32))],
+            Matches::ByteRange(start, end) => {
+                let ch_start = context.text[..start].chars().count();
+                let ch_end = ch_start + context.text[start..end].chars().count();
+                vec![(context.highlight_attr, (ch_start as u32, ch_end as u32))]
+            }
+            Matches::None => vec![],
+        };
+        text.override_attrs(highlights);
+        text
+    }
+
     fn preview(&self, _context: PreviewContext) -> ItemPreview {
+        if self.header {
+            return ItemPreview::Text(String::new());
+        }
+
         let mut preview = String::new();
 
         preview.push_str(&format!("🎬 {}\n", self.channel.name.bright_cyan().bold()));
@@ -30,6 +144,11 @@ fn text(&self) -> Cow<'_, str> {
             preview.push_str(&format!("📁 Group: {}\n", group.bright_blue()));
         }
 
+        if let Some(duration) = self.channel.duration {
+            let length = format_duration(Duration::from_secs_f64(duration));
+            preview.push_str(&format!("🎞️ VOD: {}\n", length.bright_cyan()));
+        }
+
         if let Some(country) = &self.channel.country {
             preview.push_str(&format!("🌍 Country: {}\n", country.bright_green()));
         }
@@ -42,55 +161,390 @@ fn text(&self) -> Cow<'_, str> {
             preview.push_str(&format!("🖼️ Logo: {}\n", logo.bright_magenta()));
         }
 
+        if !self.tags.is_empty() {
+            preview.push_str(&format!("🏷️ Tags: {}\n", self.tags.join(", ").bright_yellow()));
+        }
+
         preview.push_str("\n📋 Controls:\n");
         preview.push_str("  Enter - Play channel\n");
         preview.push_str("  Esc   - Exit\n");
         preview.push_str("  Tab   - Toggle preview\n");
+        preview.push_str("  Ctrl+G - Browse this group / all channels\n");
+        preview.push_str("  Ctrl+O - Full channel info\n");
+        preview.push_str("  Alt+O - Open in browser\n");
+        preview.push_str("  Ctrl+Alt+R - Reload the playlist\n");
+        preview.push_str("  F2 - Rename channel\n");
+        preview.push_str("  Alt+T - Edit tags\n");
+        preview.push_str("  Alt+E - Recent errors\n");
+        if self.channel.catchup.is_some() {
+            preview.push_str("  Ctrl+T - Watch from an earlier time (catchup)\n");
+        }
         preview.push_str("  Ctrl+C - Quit");
 
-        ItemPreview::Text(preview)
+        match &self.thumbnail {
+            Some(path) => ItemPreview::Command(thumbnail::preview_command(&self.channel.url, path, &preview)),
+            None => ItemPreview::Text(preview),
+        }
+    }
+}
+
+/// A channel row that reports each redraw, so the idle timer can tell the user is still browsing
+///
+/// Skim only redraws after a key, a query change or a resize, and redrawing calls `display`
+/// for every visible row.
+struct WatchedItem {
+    item: Arc<ChannelItem>,
+    activity: Arc<watch::Sender<()>>,
+}
+
+impl SkimItem for WatchedItem {
+    fn text(&self) -> Cow<'_, str> {
+        self.item.text()
+    }
+
+    fn output(&self) -> Cow<'_, str> {
+        self.item.output()
+    }
+
+    fn display<'a>(&'a self, context: DisplayContext<'a>) -> AnsiString<'a> {
+        self.activity.send_replace(());
+        self.item.display(context)
+    }
+
+    fn preview(&self, context: PreviewContext) -> ItemPreview {
+        self.item.preview(context)
     }
 }
 
 pub struct ChannelSelector {
-    channels: Vec<ChannelItem>,
+    channels: Vec<Arc<ChannelItem>>,
     config: Config,
+    thumbnail_dir: Option<PathBuf>,
+    running: Option<Arc<AtomicBool>>,
+    /// Group the list is scoped to; kept between selections until the scope key widens it
+    scope: Option<String>,
 }
 
 impl ChannelSelector {
     pub fn new(channels: Vec<Channel>, config: &Config) -> Self {
-        let channel_items: Vec<ChannelItem> = channels
+        let thumbnail_dir = thumbnail::thumbnail_dir(config.ui.preview_thumbnail);
+        Self {
+            channels: Self::items(channels, config, thumbnail_dir.as_deref()),
+            config: config.clone(),
+            thumbnail_dir,
+            running: None,
+            scope: None,
+        }
+    }
+
+    /// Give up on the selection once `running` is cleared by a shutdown signal
+    pub fn set_shutdown_flag(&mut self, running: Arc<AtomicBool>) {
+        self.running = Some(running);
+    }
+
+    /// Use updated `config.channel_tags` for the previews of the next `replace_channels`
+    pub fn set_channel_tags(&mut self, tags: HashMap<String, Vec<String>>) {
+        self.config.channel_tags = tags;
+    }
+
+    /// Open the list scoped to `group`, e.g. `config.ui.default_group`
+    pub fn set_scope(&mut self, group: Option<String>) {
+        self.scope = group;
+    }
+
+    /// Items the next selection starts with
+    pub fn initial_items(&self) -> Vec<Arc<ChannelItem>> {
+        self.scoped_items(self.scope.as_deref())
+    }
+
+    /// Swap in a freshly loaded channel list, e.g. after the refresh key
+    pub fn replace_channels(&mut self, channels: Vec<Channel>) {
+        self.channels = Self::items(channels, &self.config, self.thumbnail_dir.as_deref());
+    }
+
+    fn items(channels: Vec<Channel>, config: &Config, thumbnail_dir: Option<&Path>) -> Vec<Arc<ChannelItem>> {
+        if !config.ui.section_headers {
+            return Self::channel_items(channels, config, thumbnail_dir);
+        }
+
+        let mut items = Vec::new();
+        for (title, section) in sections(channels, config) {
+            if section.is_empty() {
+                continue;
+            }
+            let header = ChannelItem::header(title);
+            // Skim lists bottom-up unless reversed, so the header goes last to sit above its section
+            if config.ui.reverse_list {
+                items.push(header);
+                items.extend(Self::channel_items(section, config, thumbnail_dir));
+            } else {
+                items.extend(Self::channel_items(section, config, thumbnail_dir));
+                items.push(header);
+            }
+        }
+        items
+    }
+
+    fn channel_items(channels: Vec<Channel>, config: &Config, thumbnail_dir: Option<&Path>) -> Vec<Arc<ChannelItem>> {
+        channels
             .into_iter()
             .map(|channel| {
-                let display_text = match &channel.group {
-                    Some(group) => format!("[{}] {}", group, channel.name),
-                    None => channel.name.clone(),
+                let (display_text, group_color) = match &channel.group {
+                    Some(group) if config.ui.show_groups => (
+                        format!("[{}] {}", group, channel.name),
+                        group_color(group, &config.ui.color_scheme),
+                    ),
+                    _ => (channel.name.clone(), None),
                 };
 
-                ChannelItem {
+                Arc::new(ChannelItem {
+                    thumbnail: thumbnail_dir.map(|dir| thumbnail::cache_path(dir, &channel.url)),
+                    tags: config.tags_for(&channel.url).to_vec(),
                     channel,
                     display_text,
-                }
+                    group_color,
+                    header: false,
+                })
             })
-            .collect();
+            .collect()
+    }
 
-        Self {
-            channels: channel_items,
-            config: config.clone(),
+    pub async fn select_channel(&mut self) -> Result<Selection> {
+        debug!("Starting channel selection with {} channels", self.channels.len());
+
+        if !io::stdin().is_terminal() || !io::stdout().is_terminal() {
+            debug!("No TTY available, using plain selector");
+            return self.select_plain().map(Selection::from);
+        }
+
+        let guard = terminal::TerminalGuard::new();
+        match self.run_selection(false).await {
+            Ok(SessionEnd::Chosen(selected)) => Ok(Selection::from(selected.into_iter().next())),
+            Ok(SessionEnd::Action(action)) => Ok(action),
+            Err(e) => {
+                guard.restore();
+                warn!("⚠️ Interactive selector failed ({}), falling back to plain selector", e);
+                self.select_plain().map(Selection::from)
+            }
         }
     }
 
-    pub async fn select_channel(&mut self) -> Result<Option<Channel>> {
-        debug!("Starting channel selection with {} channels", self.channels.len());
+    /// Let the user mark several channels with Tab and confirm them with Enter
+    pub async fn select_many(&mut self) -> Result<Vec<Channel>> {
+        if !io::stdin().is_terminal() || !io::stdout().is_terminal() {
+            anyhow::bail!("Selecting channels for export needs an interactive terminal");
+        }
+
+        let _guard = terminal::TerminalGuard::new();
+        match self.run_selection(true).await? {
+            SessionEnd::Chosen(selected) => Ok(selected),
+            SessionEnd::Action(_) => Ok(Vec::new()),
+        }
+    }
+
+    /// Numbered-list selector for terminals skim can't drive
+    ///
+    /// Typing a number plays that channel, other text filters the list and `q` quits.
+    fn select_plain(&self) -> Result<Option<Channel>> {
+        let mut filter = String::new();
+
+        loop {
+            let matches: Vec<&Channel> = self.channels
+                .iter()
+                .filter(|item| !item.header)
+                .map(|item| &item.channel)
+                .filter(|channel| channel.name.to_lowercase().contains(&filter.to_lowercase()))
+                .collect();
+
+            for (i, channel) in matches.iter().enumerate().take(self.config.ui.page_size) {
+                println!("{}. {}", format!("{:3}", i + 1).bright_blue(), channel.display_name());
+            }
+            if matches.len() > self.config.ui.page_size {
+                println!("{}", format!("... and {} more, type text to filter", matches.len() - self.config.ui.page_size).bright_yellow());
+            }
+
+            print!("{}", "Channel number, filter text or q to quit: ".bright_cyan());
+            io::stdout().flush()?;
+
+            let mut input = String::new();
+            if io::stdin().read_line(&mut input)? == 0 {
+                return Ok(None);
+            }
+
+            match parse_plain_selection(&input, matches.len().min(self.config.ui.page_size)) {
+                PlainSelection::Pick(index) => return Ok(Some(matches[index].clone())),
+                PlainSelection::Quit => return Ok(None),
+                PlainSelection::Filter(text) => filter = text,
+                PlainSelection::Invalid => println!("{}", "❌ No channel with that number".bright_red()),
+            }
+        }
+    }
+
+    /// Run skim until channels are chosen, or a key asks the player to act
+    async fn run_selection(&mut self, multi: bool) -> Result<SessionEnd> {
+        let config = self.config.clone();
+        let idle_timeout = self.config.ui.idle_timeout_secs.map(Duration::from_secs);
+        let mut letter = if config.ui.browse_alpha { prompt_letter(&self.initial_items())? } else { None };
+        let mut query = String::new();
+        let mut show_help = false;
+
+        loop {
+            let items = with_initial(self.scoped_items(self.scope.as_deref()), letter);
+            let (skim_config, skim_query) = (config.clone(), query.clone());
+
+            // Skim blocks its thread; run it on a plain thread so the idle timer can race it
+            // and an abandoned selector never holds up runtime shutdown
+            let skim_items = items.clone();
+            let (tx, rx) = oneshot::channel();
+            let (activity, redraws) = watch::channel(());
+            std::thread::spawn(move || {
+                let _ = tx.send(Self::run_skim(&skim_items, &skim_config, multi, &skim_query, show_help, activity));
+            });
+
+            let waited = tokio::select! {
+                waited = wait_with_idle_timeout(rx, idle_timeout, redraws) => waited,
+                _ = shutdown_requested(self.running.clone()) => {
+                    info!("🛑 Shutdown requested, closing the channel list");
+                    #[cfg(unix)]
+                    terminal::reset_terminal_unix();
+                    return Ok(SessionEnd::Chosen(Vec::new()));
+                }
+            };
+            let outcome = match waited {
+                Some(result) => result.context("Channel selector stopped unexpectedly")??,
+                None => {
+                    info!("⏱️ No selection for {}s, exiting", idle_timeout.unwrap_or_default().as_secs());
+                    // Skim's thread still holds the tty in raw mode
+                    #[cfg(unix)]
+                    terminal::reset_terminal_unix();
+                    return Ok(SessionEnd::Chosen(Vec::new()));
+                }
+            };
+
+            match outcome {
+                SkimOutcome::Selected(texts) => {
+                    let chosen = resolve_items(&items, &texts);
+                    // Only a section header was picked; show the list again
+                    if chosen.is_empty() && !texts.is_empty() {
+                        continue;
+                    }
+                    return Ok(SessionEnd::Chosen(chosen));
+                }
+                SkimOutcome::Refresh => return Ok(SessionEnd::Action(Selection::Refresh)),
+                SkimOutcome::ShowErrors => return Ok(SessionEnd::Action(Selection::ShowErrors)),
+                SkimOutcome::OpenBrowser { highlighted, query: current_query } => {
+                    if let Some(item) = highlighted.and_then(|text| find_item(&items, &text)) {
+                        open_in_browser(&item);
+                    }
+                    query = current_query;
+                }
+                SkimOutcome::Rename { highlighted, query: current_query } => {
+                    let channel = highlighted.and_then(|text| resolve_items(&items, &[text]).pop());
+                    let name = channel.as_ref().map(prompt_rename).transpose()?.flatten();
+                    if let (Some(channel), Some(name)) = (channel, name) {
+                        return Ok(SessionEnd::Action(Selection::Rename { url: channel.url, name }));
+                    }
+                    query = current_query;
+                }
+                SkimOutcome::EditTags { highlighted, query: current_query } => {
+                    let item = highlighted.and_then(|text| find_item(&items, &text));
+                    let edits = item.as_deref().map(prompt_tags).transpose()?.unwrap_or_default();
+                    if let (Some(item), false) = (item, edits.is_empty()) {
+                        return Ok(SessionEnd::Action(Selection::EditTags { url: item.channel.url.clone(), edits }));
+                    }
+                    query = current_query;
+                }
+                SkimOutcome::ToggleScope { highlighted, query: current_query } => {
+                    let highlighted = highlighted.and_then(|text| resolve_items(&items, &[text]).pop());
+                    self.scope = toggle_group_scope(self.scope.take(), highlighted.as_ref());
+                    query = current_query;
+                    debug!("Group scope: {:?}", self.scope);
+                }
+                SkimOutcome::ToggleHelp { query: current_query } => {
+                    show_help = !show_help;
+                    query = current_query;
+                }
+                SkimOutcome::JumpToLetter { query: current_query } => {
+                    letter = prompt_letter(&self.scoped_items(self.scope.as_deref()))?;
+                    query = current_query;
+                    debug!("Letter scope: {:?}", letter);
+                }
+                SkimOutcome::ShowInfo { highlighted, query: current_query } => {
+                    if let Some(channel) = highlighted.and_then(|text| resolve_items(&items, &[text]).pop()) {
+                        println!("{}", info_panel(&channel));
+                        print!("{}", "Press Enter to return to the list".bright_cyan());
+                        io::stdout().flush()?;
+                        io::stdin().read_line(&mut String::new())?;
+                    }
+                    query = current_query;
+                }
+                SkimOutcome::Catchup { highlighted, query: current_query } => {
+                    let channel = highlighted.and_then(|text| resolve_items(&items, &[text]).pop());
+                    if let Some(catchup) = channel.map(|channel| prompt_catchup(&channel)).transpose()?.flatten() {
+                        return Ok(SessionEnd::Chosen(vec![catchup]));
+                    }
+                    query = current_query;
+                }
+            }
+        }
+    }
 
-        terminal::init_terminal();
-        let result = self.run_selection().await;
-        terminal::restore_terminal();
+    /// Items to show when the list is scoped to `group`, or every item when unscoped
+    ///
+    /// Scoped rows drop the `[Group]` prefix, since every row shares it.
+    fn scoped_items(&self, group: Option<&str>) -> Vec<Arc<ChannelItem>> {
+        match group {
+            Some(group) => self.channels
+                .iter()
+                .filter(|item| item.channel.group.as_deref() == Some(group))
+                .map(|item| {
+                    Arc::new(ChannelItem {
+                        display_text: item.channel.name.clone(),
+                        group_color: None,
+                        ..ChannelItem::clone(item)
+                    })
+                })
+                .collect(),
+            None => self.channels.clone(),
+        }
+    }
+
+    /// Skim options for the channel list; the preview pane is left out entirely when `ui.show_preview` is off
+    fn skim_options<'a>(config: &Config, multi: bool, query: &'a str, header: &'a str, bind: &'a [String]) -> Result<SkimOptions<'a>> {
+        let case = match config.search.case_mode() {
+            CaseMode::Ignore => CaseMatching::Ignore,
+            CaseMode::Respect => CaseMatching::Respect,
+            CaseMode::Smart => CaseMatching::Smart,
+        };
 
-        result
+        let mut builder = SkimOptionsBuilder::default();
+        builder
+            .height(Some("70%"))
+            .multi(multi)
+            .prompt(Some("⚡ RIPTV > "))
+            .header(Some(header))
+            .bind(bind.iter().map(String::as_str).collect())
+            .reverse(config.ui.reverse_list)
+            .case(case)
+            .query(Some(query))
+            .expect(Some(action_keys(multi).iter().map(|&(key, _)| key).collect::<Vec<_>>().join(",")));
+        if config.ui.show_preview {
+            builder.preview(Some("")).preview_window(Some("right:50%:wrap"));
+        }
+        Ok(builder.build()?)
     }
 
-    async fn run_selection(&mut self) -> Result<Option<Channel>> {
+    /// Run skim and return the display texts of the chosen channels, or a scope toggle
+    ///
+    /// With `show_help` the header lists every key binding instead of the logo.
+    fn run_skim(
+        channels: &[Arc<ChannelItem>],
+        config: &Config,
+        multi: bool,
+        query: &str,
+        show_help: bool,
+        activity: watch::Sender<()>,
+    ) -> Result<SkimOutcome> {
         let logo_header = r#"
 ██████╗ ██╗██████╗ ████████╗██╗   ██╗
 ██╔══██╗██║██╔══██╗╚══██╔══╝██║   ██║
@@ -102,37 +556,20 @@ impl ChannelSelector {
 🦀 Written in Rust for Maximum Performance
 Use arrows or Ctrl-J/K to navigate channels
 Press Tab for preview, Enter to play, Esc to quit
+Ctrl-G shows only the highlighted channel's group, Ctrl-O its full info, Alt-O opens it in a browser
+Ctrl-Alt-R reloads the playlist from its source, F2 renames a channel, Alt-T edits its tags, Alt-E lists recent errors
+Alt-A jumps to the channels starting with a letter, F1 lists every key
 "#;
+        let help = help_text(config, multi);
+        let bind = skim_binds(config, multi);
+        let options = Self::skim_options(config, multi, query, if show_help { &help } else { logo_header }, &bind)?;
 
-        let options = SkimOptionsBuilder::default()
-            .height(Some("70%"))
-            .multi(false)
-            .prompt(Some("⚡ RIPTV > "))
-            .preview(Some(""))
-            .preview_window(Some("right:50%:wrap"))
-            .header(Some(logo_header))
-            .bind(vec![
-                "ctrl-j:down",
-                "ctrl-k:up",
-                "ctrl-d:half-page-down",
-                "ctrl-u:half-page-up",
-                "ctrl-f:page-down",
-                "ctrl-b:page-up",
-                "alt-enter:accept",
-                "ctrl-c:abort",
-            ])
-            .reverse(true)
-            .build()?;
-
-        let input = self
-            .channels
-            .iter()
-            .map(|item| item.display_text.as_str())
-            .collect::<Vec<_>>()
-            .join("\n");
-
-        let item_reader = SkimItemReader::default();
-        let items = item_reader.of_bufread(Cursor::new(input));
+        let (tx, items): (SkimItemSender, SkimItemReceiver) = unbounded();
+        let activity = Arc::new(activity);
+        for item in channels {
+            let _ = tx.send(Arc::new(WatchedItem { item: item.clone(), activity: activity.clone() }));
+        }
+        drop(tx);
 
         let output = Skim::run_with(&options, Some(items));
 
@@ -140,35 +577,609 @@ Press Tab for preview, Enter to play, Esc to quit
             Some(output) => {
                 if output.is_abort {
                     debug!("User aborted selection");
-                    return Ok(None);
+                    return Ok(SkimOutcome::Selected(Vec::new()));
                 }
 
-                if let Some(selected_item) = output.selected_items.first() {
-                    let selected_text = selected_item.output();
-                    debug!("User selected: {}", selected_text);
+                let selected: Vec<String> = output.selected_items
+                    .iter()
+                    .map(|item| item.output().into_owned())
+                    .collect();
 
-                    for item in &self.channels {
-                        if item.display_text == selected_text {
-                            return Ok(Some(item.channel.clone()));
-                        }
-                    }
+                if output.final_key == Key::Ctrl('g') {
+                    return Ok(SkimOutcome::ToggleScope {
+                        highlighted: selected.into_iter().next(),
+                        query: output.query,
+                    });
+                }
+
+                if output.final_key == Key::Ctrl('o') {
+                    return Ok(SkimOutcome::ShowInfo {
+                        highlighted: selected.into_iter().next(),
+                        query: output.query,
+                    });
+                }
+
+                if output.final_key == Key::CtrlAlt('r') {
+                    return Ok(SkimOutcome::Refresh);
+                }
+
+                if output.final_key == Key::Alt('o') {
+                    return Ok(SkimOutcome::OpenBrowser {
+                        highlighted: selected.into_iter().next(),
+                        query: output.query,
+                    });
+                }
+
+                if output.final_key == Key::F(1) {
+                    return Ok(SkimOutcome::ToggleHelp { query: output.query });
+                }
+
+                if output.final_key == Key::Alt('a') {
+                    return Ok(SkimOutcome::JumpToLetter { query: output.query });
+                }
+
+                if output.final_key == Key::Alt('e') {
+                    return Ok(SkimOutcome::ShowErrors);
+                }
+
+                if output.final_key == Key::Alt('t') {
+                    return Ok(SkimOutcome::EditTags {
+                        highlighted: selected.into_iter().next(),
+                        query: output.query,
+                    });
+                }
+
+                if output.final_key == Key::F(2) {
+                    return Ok(SkimOutcome::Rename {
+                        highlighted: selected.into_iter().next(),
+                        query: output.query,
+                    });
                 }
 
-                Ok(None)
+                if output.final_key == Key::Ctrl('t') {
+                    return Ok(SkimOutcome::Catchup {
+                        highlighted: selected.into_iter().next(),
+                        query: output.query,
+                    });
+                }
+
+                debug!("User selected: {:?}", selected);
+                Ok(SkimOutcome::Selected(selected))
             }
             None => {
                 debug!("No selection made");
-                Ok(None)
+                Ok(SkimOutcome::Selected(Vec::new()))
+            }
+        }
+    }
+}
+
+/// Key that scopes the list to the highlighted channel's group, and back
+const GROUP_SCOPE_KEY: &str = "ctrl-g";
+
+/// Key that shows the full info panel for the highlighted channel
+const INFO_KEY: &str = "ctrl-o";
+
+/// Key that asks for a start time and plays the highlighted channel's catchup archive
+const CATCHUP_KEY: &str = "ctrl-t";
+
+/// Key that reloads the playlist from its source and rebuilds the list
+const REFRESH_KEY: &str = "ctrl-alt-r";
+
+/// Key that asks for a new display name for the highlighted channel
+const RENAME_KEY: &str = "f2";
+
+/// Key that asks for tags to add to or remove from the highlighted channel
+const TAGS_KEY: &str = "alt-t";
+
+/// Key that lists the session's recent errors
+const ERRORS_KEY: &str = "alt-e";
+
+/// Key that opens the highlighted channel's URL in the default browser
+const BROWSER_KEY: &str = "alt-o";
+
+/// Key that asks for a letter and lists only the channels starting with it
+const LETTER_KEY: &str = "alt-a";
+
+/// Key that swaps the header between the logo and the key binding list
+const HELP_KEY: &str = "f1";
+
+/// Navigation keys bound in skim before `ui.key_bindings` is applied
+const BASE_BINDINGS: &[(&str, &str)] = &[
+    ("ctrl-j", "down"),
+    ("ctrl-k", "up"),
+    ("ctrl-d", "half-page-down"),
+    ("ctrl-u", "half-page-up"),
+    ("ctrl-f", "page-down"),
+    ("ctrl-b", "page-up"),
+    ("alt-enter", "accept"),
+    ("ctrl-c", "abort"),
+    ("home", "first"),
+    ("end", "last"),
+];
+
+/// Rows moved by `first`/`last`; skim clamps the cursor, so this reaches the end of any list
+const JUMP_ROWS: u32 = 100_000_000;
+
+/// riptv's own selector keys and what they do; refresh, rename, tags and errors need a single pick
+fn action_keys(multi: bool) -> Vec<(&'static str, &'static str)> {
+    let mut keys = vec![
+        (GROUP_SCOPE_KEY, "Show only the highlighted channel's group, and back"),
+        (INFO_KEY, "Full info for the highlighted channel"),
+        (CATCHUP_KEY, "Play the highlighted channel's catchup archive"),
+        (BROWSER_KEY, "Open the highlighted channel in a browser"),
+        (LETTER_KEY, "Jump to the channels starting with a letter"),
+        (HELP_KEY, "Show or hide this key list"),
+    ];
+    if !multi {
+        keys.extend([
+            (REFRESH_KEY, "Reload the playlist from its source"),
+            (RENAME_KEY, "Rename the highlighted channel"),
+            (TAGS_KEY, "Edit the highlighted channel's tags"),
+            (ERRORS_KEY, "List recent errors"),
+        ]);
+    }
+    keys
+}
+
+/// Skim action for a `ui.key_bindings` entry
+fn binding_action(name: &str) -> Option<&'static str> {
+    match name {
+        "up" => Some("up"),
+        "down" => Some("down"),
+        "page_up" => Some("page-up"),
+        "page_down" => Some("page-down"),
+        "select" => Some("accept"),
+        "quit" => Some("abort"),
+        "preview" => Some("toggle-preview"),
+        "first" => Some("first"),
+        "last" => Some("last"),
+        _ => None,
+    }
+}
+
+/// `key:action` binds for skim, with `first`/`last` spelled as long moves
+///
+/// Skim 0.10 has no first/last actions. `up` always moves toward the top of the screen, and the
+/// first channel is at the top only when the list is reversed.
+fn skim_binds(config: &Config, multi: bool) -> Vec<String> {
+    let reverse = config.ui.reverse_list;
+    effective_bindings(config, multi)
+        .into_iter()
+        .map(|(key, action)| match action {
+            "first" | "last" => {
+                let toward_top = (action == "first") == reverse;
+                format!("{}:{}({})", key, if toward_top { "up" } else { "down" }, JUMP_ROWS)
             }
+            _ => format!("{}:{}", key, action),
+        })
+        .collect()
+}
+
+/// Skim bindings in effect: the built-in navigation keys overlaid with `ui.key_bindings`
+///
+/// Single-character keys are left alone so they can still be typed into the search, and so is
+/// Tab when it marks channels.
+fn effective_bindings(config: &Config, multi: bool) -> Vec<(String, &'static str)> {
+    let mut bindings: Vec<(String, &'static str)> = BASE_BINDINGS.iter().map(|&(key, action)| (key.to_string(), action)).collect();
+    let mut custom: Vec<_> = config.ui.key_bindings.iter().collect();
+    custom.sort();
+
+    for (name, keys) in custom {
+        let Some(action) = binding_action(name) else {
+            debug!("Ignoring key binding for unknown action: {}", name);
+            continue;
+        };
+        for key in keys.split(',').map(str::trim).filter(|key| !key.is_empty()) {
+            if key.chars().count() == 1 || (multi && key == "tab") {
+                debug!("Not binding {} to {}, it's needed for typing or marking", key, action);
+                continue;
+            }
+            bindings.retain(|(bound, _)| bound != key);
+            bindings.push((key.to_string(), action));
         }
     }
+    bindings
 }
 
-impl Drop for ChannelSelector {
-    fn drop(&mut self) {
-        debug!("ChannelSelector being dropped, ensuring terminal cleanup");
-        terminal::ensure_clean_terminal();
+/// Every key the selector responds to, for the help header
+fn help_text(config: &Config, multi: bool) -> String {
+    let describe = |action: &str| match action {
+        "up" => "Move up",
+        "down" => "Move down",
+        "half-page-up" => "Half a page up",
+        "half-page-down" => "Half a page down",
+        "page-up" => "Page up",
+        "page-down" => "Page down",
+        "accept" => "Play the highlighted channel",
+        "abort" => "Quit",
+        "toggle-preview" => "Show or hide the preview",
+        "first" => "Jump to the first channel",
+        "last" => "Jump to the last channel",
+        _ => "",
+    };
+
+    let mut text = format!("⌨️ Key bindings ({} to hide)\n", HELP_KEY);
+    for (key, description) in action_keys(multi) {
+        text.push_str(&format!("  {:<12} {}\n", key, description));
+    }
+    for (key, action) in effective_bindings(config, multi) {
+        text.push_str(&format!("  {:<12} {}\n", key, describe(action)));
     }
+    text
+}
+
+/// What the user asked for in the channel selector
+#[derive(Debug)]
+pub enum Selection {
+    Play(Box<Channel>),
+    /// The refresh key was pressed; reload the playlist and call `replace_channels`
+    Refresh,
+    /// The rename key was pressed; save `name` as the display name for the channel at `url`
+    Rename { url: String, name: String },
+    /// The tags key was pressed; apply `edits` to the tags of the channel at `url`
+    EditTags { url: String, edits: Vec<TagEdit> },
+    /// The errors key was pressed; show the recent errors and select again
+    ShowErrors,
+    Quit,
+}
+
+/// How `run_selection` ended
+enum SessionEnd {
+    Chosen(Vec<Channel>),
+    Action(Selection),
+}
+
+impl From<Option<Channel>> for Selection {
+    fn from(channel: Option<Channel>) -> Self {
+        channel.map_or(Selection::Quit, |channel| Selection::Play(Box::new(channel)))
+    }
+}
+
+/// How a single skim session ended
+enum SkimOutcome {
+    /// Display texts of the chosen channels; empty when the user quit
+    Selected(Vec<String>),
+    /// The group scope key was pressed
+    ToggleScope {
+        highlighted: Option<String>,
+        query: String,
+    },
+    /// The info key was pressed
+    ShowInfo {
+        highlighted: Option<String>,
+        query: String,
+    },
+    /// The catchup key was pressed
+    Catchup {
+        highlighted: Option<String>,
+        query: String,
+    },
+    /// The refresh key was pressed
+    Refresh,
+    /// The errors key was pressed
+    ShowErrors,
+    /// The browser key was pressed
+    OpenBrowser {
+        highlighted: Option<String>,
+        query: String,
+    },
+    /// The letter key was pressed
+    JumpToLetter { query: String },
+    /// The help key was pressed
+    ToggleHelp { query: String },
+    /// The rename key was pressed
+    Rename {
+        highlighted: Option<String>,
+        query: String,
+    },
+    /// The tags key was pressed
+    EditTags {
+        highlighted: Option<String>,
+        query: String,
+    },
+}
+
+/// The channel row skim shows as `text`
+fn find_item(items: &[Arc<ChannelItem>], text: &str) -> Option<Arc<ChannelItem>> {
+    items.iter().find(|item| !item.header && item.display_text == text).cloned()
+}
+
+/// Channels behind the display texts skim returned, in selection order; headers map to nothing
+fn resolve_items(items: &[Arc<ChannelItem>], texts: &[String]) -> Vec<Channel> {
+    // Channels sharing a display text resolve to the first one listed
+    let mut by_text: HashMap<&str, &Channel> = HashMap::new();
+    for item in items.iter().filter(|item| !item.header) {
+        by_text.entry(item.display_text.as_str()).or_insert(&item.channel);
+    }
+
+    texts
+        .iter()
+        .filter_map(|text| by_text.get(text.as_str()).map(|channel| (*channel).clone()))
+        .collect()
+}
+
+/// Launch the browser for a channel without waiting for it
+fn open_in_browser(item: &ChannelItem) {
+    let mut cmd = item.browser_command();
+    cmd.stdin(std::process::Stdio::null())
+        .stdout(std::process::Stdio::null())
+        .stderr(std::process::Stdio::null());
+    match cmd.spawn() {
+        Ok(_) => info!("🌐 Opened {} in the browser", item.channel.name),
+        Err(e) => warn!("⚠️ Failed to open {} in the browser: {}", item.channel.url, e),
+    }
+}
+
+/// Split channels into favorites, recently played and the rest, each under its header title
+fn sections(channels: Vec<Channel>, config: &Config) -> [(&'static str, Vec<Channel>); 3] {
+    let recency: HashMap<&str, usize> = config.recent_channels
+        .iter()
+        .enumerate()
+        .map(|(rank, name)| (name.as_str(), rank))
+        .collect();
+
+    let (mut favorites, mut recent, mut rest) = (Vec::new(), Vec::new(), Vec::new());
+    for channel in channels {
+        if config.favorite_channels.contains(&channel.name) {
+            favorites.push(channel);
+        } else if recency.contains_key(channel.name.as_str()) {
+            recent.push(channel);
+        } else {
+            rest.push(channel);
+        }
+    }
+    recent.sort_by_key(|channel| recency[channel.name.as_str()]);
+
+    [("Favorites", favorites), ("Recent", recent), ("All Channels", rest)]
+}
+
+/// Only the channels listed under `letter` by `playlist::initial`, or every item when `None`
+fn with_initial(items: Vec<Arc<ChannelItem>>, letter: Option<char>) -> Vec<Arc<ChannelItem>> {
+    match letter {
+        Some(letter) => items
+            .into_iter()
+            .filter(|item| !item.header && playlist::initial(&item.channel.name) == letter)
+            .collect(),
+        None => items,
+    }
+}
+
+/// The bucket a typed letter refers to; digits mean `#`
+fn parse_letter(input: &str) -> Option<char> {
+    let c = input.trim().chars().next()?;
+    if c.is_ascii_digit() || c == '#' {
+        return Some('#');
+    }
+    c.to_uppercase().next()
+}
+
+/// Show the letters with channels under them and ask which to jump to; empty input lists everything
+fn prompt_letter(items: &[Arc<ChannelItem>]) -> Result<Option<char>> {
+    let channels: Vec<Channel> = items.iter().filter(|item| !item.header).map(|item| item.channel.clone()).collect();
+    let index = playlist::index_by_initial(&channels);
+    let letters: Vec<String> = index
+        .iter()
+        .map(|(letter, indices)| format!("{}{}", letter.to_string().bright_white().bold(), format!("({})", indices.len()).bright_black()))
+        .collect();
+    println!("🔤 {}", letters.join(" "));
+
+    loop {
+        print!("{}", "Jump to letter (empty for all channels): ".bright_cyan());
+        io::stdout().flush()?;
+
+        let mut input = String::new();
+        if io::stdin().read_line(&mut input)? == 0 {
+            return Ok(None);
+        }
+        match parse_letter(&input) {
+            None => return Ok(None),
+            Some(letter) if index.contains_key(&letter) => return Ok(Some(letter)),
+            Some(letter) => println!("{}", format!("❌ No channels under {}", letter).bright_red()),
+        }
+    }
+}
+
+/// One change typed at the tags prompt
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TagEdit {
+    Add(String),
+    Remove(String),
+}
+
+/// Read `+tag`/`-tag` words (a bare word adds) separated by spaces or commas
+pub fn parse_tag_edits(input: &str) -> Vec<TagEdit> {
+    input
+        .split(|c: char| c.is_whitespace() || c == ',')
+        .map(|word| match word.strip_prefix('-') {
+            Some(tag) => TagEdit::Remove(tag.to_string()),
+            None => TagEdit::Add(word.strip_prefix('+').unwrap_or(word).to_string()),
+        })
+        .filter(|edit| match edit {
+            TagEdit::Add(tag) | TagEdit::Remove(tag) => !tag.is_empty(),
+        })
+        .collect()
+}
+
+/// Show the channel's tags and ask for changes; empty input cancels
+fn prompt_tags(item: &ChannelItem) -> Result<Vec<TagEdit>> {
+    let current = if item.tags.is_empty() { "none".to_string() } else { item.tags.join(", ") };
+    println!("{}", format!("🏷️ Tags for {}: {}", item.channel.name, current).bright_cyan());
+    print!("{}", "Add with +tag, remove with -tag (empty to cancel): ".bright_cyan());
+    io::stdout().flush()?;
+
+    let mut input = String::new();
+    io::stdin().read_line(&mut input)?;
+    Ok(parse_tag_edits(&input))
+}
+
+/// Ask for a new display name; empty input cancels
+fn prompt_rename(channel: &Channel) -> Result<Option<String>> {
+    print!("{}", format!("✏️ Rename {} to (empty to cancel): ", channel.name).bright_cyan());
+    io::stdout().flush()?;
+
+    let mut input = String::new();
+    io::stdin().read_line(&mut input)?;
+    let name = input.trim();
+    Ok((!name.is_empty() && name != channel.name).then(|| name.to_string()))
+}
+
+/// Ask when to start watching `channel` and return a copy pointing at its catchup stream
+///
+/// Returns `None` (after explaining why) when the user gives up or the time can't be used.
+fn prompt_catchup(channel: &Channel) -> Result<Option<Channel>> {
+    if channel.catchup.is_none() {
+        println!("{}", format!("❌ {} has no catchup archive", channel.name).bright_red());
+        print!("{}", "Press Enter to return to the list".bright_cyan());
+        io::stdout().flush()?;
+        io::stdin().read_line(&mut String::new())?;
+        return Ok(None);
+    }
+
+    loop {
+        print!("{}", format!("⏪ Watch {} from (-2h, -30m, 20:00, empty to cancel): ", channel.name).bright_cyan());
+        io::stdout().flush()?;
+
+        let mut input = String::new();
+        if io::stdin().read_line(&mut input)? == 0 || input.trim().is_empty() {
+            return Ok(None);
+        }
+
+        let now = catchup::unix_now();
+        let url = catchup::parse_catchup_time(&input, now, catchup::local_utc_offset())
+            .and_then(|start| catchup::catchup_url(channel, start, now));
+        match url {
+            Ok(url) => {
+                let mut catchup = channel.clone();
+                catchup.name = format!("{} ⏪ {}", channel.name, input.trim());
+                catchup.url = url;
+                catchup.url_template = None;
+                return Ok(Some(catchup));
+            }
+            Err(e) => println!("{}", format!("❌ {}", e).bright_red()),
+        }
+    }
+}
+
+/// Every known detail of a channel, shown by the info key
+pub fn info_panel(channel: &Channel) -> String {
+    let mut lines = vec![format!("🎬 {}", channel.name.bright_cyan().bold())];
+    let mut field = |label: &str, value: &str| lines.push(format!("  {:<12} {}", label, value));
+
+    if let Some(raw_name) = &channel.raw_name {
+        field("Raw name", raw_name);
+    }
+    field("URL", &channel.url);
+    if let Some(template) = &channel.url_template {
+        field("Template", template);
+    }
+    if let Some(domain) = extract_domain(&channel.url) {
+        field("Domain", &domain);
+    }
+    match channel.duration {
+        Some(duration) => field("Type", &format!("VOD ({})", format_duration(Duration::from_secs_f64(duration)))),
+        None => field("Type", "Live"),
+    }
+    for (label, value) in [
+        ("Group", &channel.group),
+        ("Country", &channel.country),
+        ("Language", &channel.language),
+        ("tvg-id", &channel.tvg_id),
+        ("Logo", &channel.logo),
+    ] {
+        if let Some(value) = value {
+            field(label, value);
+        }
+    }
+    for (name, value) in &channel.headers {
+        field("Header", &format!("{}: {}", name, value));
+    }
+    if let Some(catchup) = &channel.catchup {
+        let days = catchup.days.map(|days| format!(", {} days", days)).unwrap_or_default();
+        field("Catchup", &format!("{}{}", catchup.mode, days));
+    }
+
+    lines.join("\n")
+}
+
+/// Next group scope after the scope key is pressed
+///
+/// Scoping narrows to the highlighted channel's group; pressing again restores the full list.
+pub fn toggle_group_scope(scope: Option<String>, highlighted: Option<&Channel>) -> Option<String> {
+    match scope {
+        Some(_) => None,
+        None => highlighted.and_then(|channel| channel.group.clone()),
+    }
+}
+
+/// What the user asked for at the plain selector prompt
+#[derive(Debug, PartialEq)]
+pub enum PlainSelection {
+    /// Zero-based index into the listed channels
+    Pick(usize),
+    Quit,
+    Filter(String),
+    Invalid,
+}
+
+/// Interpret a line typed at the plain selector, given how many channels are listed
+pub fn parse_plain_selection(input: &str, listed: usize) -> PlainSelection {
+    let input = input.trim();
+
+    if input.eq_ignore_ascii_case("q") || input.eq_ignore_ascii_case("quit") {
+        return PlainSelection::Quit;
+    }
+
+    match input.parse::<usize>() {
+        Ok(number) if (1..=listed).contains(&number) => PlainSelection::Pick(number - 1),
+        Ok(_) => PlainSelection::Invalid,
+        Err(_) => PlainSelection::Filter(input.to_string()),
+    }
+}
+
+/// Wait for `selection`, giving up once `timeout` passes without a change on `activity`
+pub async fn wait_with_idle_timeout<F: Future>(
+    selection: F,
+    timeout: Option<Duration>,
+    mut activity: watch::Receiver<()>,
+) -> Option<F::Output> {
+    let Some(timeout) = timeout else {
+        return Some(selection.await);
+    };
+
+    tokio::pin!(selection);
+    let mut deadline = tokio::time::Instant::now() + timeout;
+    let mut watching = true;
+    loop {
+        tokio::select! {
+            output = &mut selection => return Some(output),
+            changed = activity.changed(), if watching => match changed {
+                Ok(()) => deadline = tokio::time::Instant::now() + timeout,
+                // No more activity can arrive; keep counting down from the last one
+                Err(_) => watching = false,
+            },
+            _ = tokio::time::sleep_until(deadline) => return None,
+        }
+    }
+}
+
+/// Resolve once `running` is cleared; never resolves without a flag
+pub async fn shutdown_requested(running: Option<Arc<AtomicBool>>) {
+    let Some(running) = running else {
+        return std::future::pending().await;
+    };
+    while running.load(Ordering::Relaxed) {
+        tokio::time::sleep(Duration::from_millis(100)).await;
+    }
+}
+
+/// Wait for Enter on a plain thread, so a shutdown signal can still interrupt the wait
+pub async fn wait_for_enter() {
+    let (tx, rx) = oneshot::channel();
+    std::thread::spawn(move || {
+        let _ = tx.send(io::stdin().read_line(&mut String::new()));
+    });
+    let _ = rx.await;
 }
 
 // -----------------------------------
@@ -198,6 +1209,37 @@ pub fn show_welcome_message() {
     println!();
 }
 
+/// Numbered prompt for choosing one of the discovered playlists
+pub fn pick_playlist(playlists: &[PathBuf]) -> Result<Option<PathBuf>> {
+    if playlists.is_empty() {
+        println!("{}", "❌ No playlists found. Use --playlist or add playlist_dirs to the config.".bright_red());
+        return Ok(None);
+    }
+
+    println!("{}", "📋 Available Playlists:".bright_cyan().bold());
+    for (i, path) in playlists.iter().enumerate() {
+        println!("{}. {}", format!("{:3}", i + 1).bright_blue(), path.display());
+    }
+
+    loop {
+        print!("{}", "Playlist number or q to quit: ".bright_cyan());
+        io::stdout().flush()?;
+
+        let mut input = String::new();
+        if io::stdin().read_line(&mut input)? == 0 {
+            return Ok(None);
+        }
+
+        match parse_plain_selection(&input, playlists.len()) {
+            PlainSelection::Pick(index) => return Ok(Some(playlists[index].clone())),
+            PlainSelection::Quit => return Ok(None),
+            PlainSelection::Filter(_) | PlainSelection::Invalid => {
+                println!("{}", "❌ No playlist with that number".bright_red())
+            }
+        }
+    }
+}
+
 pub fn show_loading_animation(message: &str) {
     use std::io::{self, Write};
     use std::thread;
@@ -219,20 +1261,21 @@ pub fn show_loading_animation(message: &str) {
     println!("\r{} ✅", message);
 }
 
-pub fn confirm_action(message: &str) -> bool {
+/// Ask a yes/no question on the terminal; anything but y/yes declines
+pub fn confirm_action(message: &str) -> Result<bool> {
     use std::io::{self, Write};
 
     print!("{} [y/N]: ", message);
-    io::stdout().flush().unwrap();
+    io::stdout().flush()?;
 
     let mut input = String::new();
-    match io::stdin().read_line(&mut input) {
-        Ok(_) => {
-            let input = input.trim().to_lowercase();
-            input == "y" || input == "yes"
-        }
-        Err(_) => false,
-    }
+    io::stdin().read_line(&mut input)?;
+    Ok(is_confirmation(&input))
+}
+
+fn is_confirmation(input: &str) -> bool {
+    let input = input.trim().to_lowercase();
+    input == "y" || input == "yes"
 }
 
 pub fn display_error(error: &str) {
@@ -250,3 +1293,338 @@ pub fn display_success(message: &str) {
 pub fn display_info(message: &str) {
     println!("{} {}", "ℹ️ Info:".bright_blue().bold(), message);
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_group_color_is_stable() {
+        let first = group_color("News", "dark");
+        assert!(first.is_some());
+        assert_eq!(first, group_color("News", "dark"));
+        assert_eq!(group_color("Sports", "light"), group_color("Sports", "light"));
+        assert_eq!(group_color("News", "none"), None);
+    }
+
+    #[tokio::test]
+    async fn test_idle_timeout() {
+        let idle = Some(Duration::from_millis(20));
+        let quiet = || watch::channel(()).1;
+        assert_eq!(wait_with_idle_timeout(std::future::pending::<()>(), idle, quiet()).await, None);
+        assert_eq!(wait_with_idle_timeout(async { 7 }, idle, quiet()).await, Some(7));
+        assert_eq!(wait_with_idle_timeout(async { 7 }, None, quiet()).await, Some(7));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_idle_timeout_reset_by_activity() {
+        let idle = Some(Duration::from_millis(20));
+        let typing = || {
+            let (activity, redraws) = watch::channel(());
+            tokio::spawn(async move {
+                for _ in 0..5 {
+                    tokio::time::sleep(Duration::from_millis(15)).await;
+                    activity.send_replace(());
+                }
+            });
+            redraws
+        };
+
+        // A pick after 60ms of steady typing is not cut off at 20ms
+        let selection = async {
+            tokio::time::sleep(Duration::from_millis(60)).await;
+            7
+        };
+        assert_eq!(wait_with_idle_timeout(selection, idle, typing()).await, Some(7));
+
+        // Typing stops at 75ms, so the timer gives up 20ms later
+        let started = tokio::time::Instant::now();
+        assert_eq!(wait_with_idle_timeout(std::future::pending::<()>(), idle, typing()).await, None);
+        assert_eq!(started.elapsed(), Duration::from_millis(95));
+    }
+
+    #[test]
+    fn test_browser_command_from_item() {
+        let mut channel = Channel::new("Clip".to_string(), " https://www.youtube.com/watch?v=abc ".to_string());
+        channel.group = Some("Web".to_string());
+        let selector = ChannelSelector::new(vec![channel], &Config::default());
+
+        let item = find_item(&selector.channels, "[Web] Clip").unwrap();
+        let command = item.browser_command();
+        let args: Vec<_> = command.get_args().map(|arg| arg.to_string_lossy().into_owned()).collect();
+        assert_eq!(args.last().map(String::as_str), Some("https://www.youtube.com/watch?v=abc"));
+        #[cfg(target_os = "linux")]
+        assert_eq!(command.get_program(), "xdg-open");
+
+        assert!(find_item(&selector.channels, "Clip").is_none());
+    }
+
+    #[test]
+    fn test_show_groups_controls_tag() {
+        let mut channel = Channel::new("BBC One".to_string(), "http://example.com/bbc".to_string());
+        channel.group = Some("UK".to_string());
+
+        let mut config = Config::default();
+        let selector = ChannelSelector::new(vec![channel.clone()], &config);
+        assert_eq!(selector.channels[0].display_text, "[UK] BBC One");
+        assert_eq!(selector.channels[0].group_color, group_color("UK", "dark"));
+
+        config.ui.show_groups = false;
+        let selector = ChannelSelector::new(vec![channel], &config);
+        assert_eq!(selector.channels[0].display_text, "BBC One");
+        assert_eq!(selector.channels[0].group_color, None);
+    }
+
+    #[test]
+    fn test_parse_plain_selection() {
+        assert_eq!(parse_plain_selection("2\n", 3), PlainSelection::Pick(1));
+        assert_eq!(parse_plain_selection(" 1 ", 3), PlainSelection::Pick(0));
+        assert_eq!(parse_plain_selection("4", 3), PlainSelection::Invalid);
+        assert_eq!(parse_plain_selection("0", 3), PlainSelection::Invalid);
+        assert_eq!(parse_plain_selection("q", 3), PlainSelection::Quit);
+        assert_eq!(parse_plain_selection("bbc\n", 3), PlainSelection::Filter("bbc".to_string()));
+        assert_eq!(parse_plain_selection("", 3), PlainSelection::Filter(String::new()));
+    }
+
+    #[test]
+    fn test_section_headers() {
+        let channels: Vec<Channel> = ["BBC", "CNN", "ITV", "Sky"]
+            .iter()
+            .map(|name| Channel::new(name.to_string(), format!("http://example.com/{}", name)))
+            .collect();
+        let mut config = Config {
+            favorite_channels: vec!["ITV".to_string()],
+            recent_channels: vec!["Sky".to_string(), "BBC".to_string()],
+            ..Config::default()
+        };
+        config.ui.show_groups = false;
+
+        let plain = ChannelSelector::new(channels.clone(), &config);
+        assert!(plain.channels.iter().all(|item| !item.header));
+
+        config.ui.section_headers = true;
+        let selector = ChannelSelector::new(channels, &config);
+        let rows: Vec<_> = selector.channels.iter().map(|item| item.display_text.as_str()).collect();
+        assert_eq!(rows, ["── Favorites ──", "ITV", "── Recent ──", "Sky", "BBC", "── All Channels ──", "CNN"]);
+
+        // Headers match no query and resolve to no channel when picked
+        let header = &selector.channels[0];
+        assert!(header.header);
+        assert_eq!(header.text(), "");
+        assert_eq!(header.output(), "── Favorites ──");
+        let picked = resolve_items(&selector.channels, &["── Recent ──".to_string(), "Sky".to_string()]);
+        assert_eq!(picked.iter().map(|channel| channel.name.as_str()).collect::<Vec<_>>(), ["Sky"]);
+        assert!(find_item(&selector.channels, "── Favorites ──").is_none());
+
+        config.ui.reverse_list = false;
+        let bottom_up = ChannelSelector::new(resolve_items(&selector.channels, &["ITV".to_string()]), &config);
+        let rows: Vec<_> = bottom_up.channels.iter().map(|item| item.display_text.as_str()).collect();
+        assert_eq!(rows, ["ITV", "── Favorites ──"]);
+    }
+
+    #[test]
+    fn test_resolve_display_texts() {
+        let channels = vec![
+            Channel::new("BBC One".to_string(), "http://example.com/bbc1".to_string()),
+            Channel::with_metadata(
+                "CNN".to_string(),
+                "http://example.com/cnn".to_string(),
+                Some("News".to_string()),
+                None,
+                None,
+                None,
+                None,
+            ),
+            Channel::new("ITV".to_string(), "http://example.com/itv".to_string()),
+        ];
+        let selector = ChannelSelector::new(channels, &Config::default());
+
+        let texts = vec!["ITV".to_string(), "[News] CNN".to_string(), "Missing".to_string()];
+        let urls: Vec<_> = resolve_items(&selector.channels, &texts)
+            .into_iter()
+            .map(|channel| channel.url)
+            .collect();
+
+        assert_eq!(urls, ["http://example.com/itv", "http://example.com/cnn"]);
+
+        let twins = vec![
+            Channel::new("Sky News".to_string(), "http://example.com/sky-1".to_string()),
+            Channel::new("Sky News".to_string(), "http://example.com/sky-2".to_string()),
+        ];
+        let selector = ChannelSelector::new(twins, &Config::default());
+        let picked = resolve_items(&selector.channels, &["Sky News".to_string()]);
+        assert_eq!(picked.iter().map(|channel| channel.url.as_str()).collect::<Vec<_>>(), ["http://example.com/sky-1"]);
+    }
+
+    #[test]
+    fn test_group_scope_transitions() {
+        let channels = vec![
+            Channel::with_metadata("CNN".to_string(), "http://example.com/cnn".to_string(), Some("News".to_string()), None, None, None, None),
+            Channel::with_metadata("BBC News".to_string(), "http://example.com/bbcnews".to_string(), Some("News".to_string()), None, None, None, None),
+            Channel::with_metadata("ESPN".to_string(), "http://example.com/espn".to_string(), Some("Sports".to_string()), None, None, None, None),
+            Channel::new("Local".to_string(), "http://example.com/local".to_string()),
+        ];
+        let selector = ChannelSelector::new(channels.clone(), &Config::default());
+        let names = |scope: Option<&str>| -> Vec<String> {
+            selector.scoped_items(scope).iter().map(|item| item.channel.name.clone()).collect()
+        };
+
+        // Scoping from a News channel narrows to its group
+        let scope = toggle_group_scope(None, Some(&channels[1]));
+        assert_eq!(scope.as_deref(), Some("News"));
+        assert_eq!(names(scope.as_deref()), ["CNN", "BBC News"]);
+
+        // Pressing again restores the full list
+        let scope = toggle_group_scope(scope, Some(&channels[0]));
+        assert_eq!(scope, None);
+        assert_eq!(names(scope.as_deref()).len(), 4);
+
+        // Scoped rows drop the redundant group prefix but still resolve to their channels
+        let scoped = selector.scoped_items(Some("News"));
+        let texts: Vec<_> = scoped.iter().map(|item| item.display_text.clone()).collect();
+        assert_eq!(texts, ["CNN", "BBC News"]);
+        assert_eq!(resolve_items(&scoped, &texts[1..]).pop().unwrap().url, "http://example.com/bbcnews");
+        assert_eq!(selector.scoped_items(None)[0].display_text, "[News] CNN");
+
+        // Channels without a group can't be scoped
+        assert_eq!(toggle_group_scope(None, Some(&channels[3])), None);
+        assert_eq!(toggle_group_scope(None, None), None);
+    }
+
+    #[test]
+    fn test_letter_scope() {
+        let channels = ["ITV", "BBC One", "itv2", "Élan", "5 Star"]
+            .iter()
+            .map(|name| Channel::new(name.to_string(), format!("http://example.com/{}", name.len())))
+            .collect();
+        let selector = ChannelSelector::new(channels, &Config::default());
+        let names = |letter: Option<char>| -> Vec<String> {
+            with_initial(selector.scoped_items(None), letter).iter().map(|item| item.channel.name.clone()).collect()
+        };
+
+        assert_eq!(names(parse_letter("i")), ["ITV", "itv2"]);
+        assert_eq!(names(parse_letter("é")), ["Élan"]);
+        assert_eq!(names(parse_letter("7")), ["5 Star"]);
+        assert_eq!(parse_letter("  "), None);
+        assert_eq!(names(None).len(), 5);
+    }
+
+    #[test]
+    fn test_help_lists_custom_bindings() {
+        let mut config = Config::default();
+        let help = help_text(&config, false);
+        assert!(help.contains("ctrl-j       Move down"));
+        assert!(help.contains("f2           Rename the highlighted channel"));
+        assert!(!help_text(&config, true).contains("f2 "));
+
+        config.ui.key_bindings.insert("down".to_string(), "ctrl-n,x".to_string());
+        config.ui.key_bindings.insert("page_down".to_string(), "ctrl-j".to_string());
+        let help = help_text(&config, false);
+        assert!(help.contains("ctrl-n       Move down"));
+        assert!(help.contains("ctrl-j       Page down"));
+        assert!(!help.contains("ctrl-j       Move down"));
+        // Printable keys stay free for typing into the search
+        assert!(!help.contains("  x "));
+
+        let bindings = effective_bindings(&config, true);
+        assert!(bindings.contains(&("ctrl-n".to_string(), "down")));
+        assert!(!bindings.iter().any(|(key, _)| key == "tab"));
+        assert!(effective_bindings(&config, false).contains(&("tab".to_string(), "toggle-preview")));
+    }
+
+    #[test]
+    fn test_jump_and_page_binds() {
+        let mut config = Config::default();
+        let bindings = effective_bindings(&config, false);
+        assert!(bindings.contains(&("home".to_string(), "first")));
+        assert!(bindings.contains(&("end".to_string(), "last")));
+        assert!(bindings.contains(&("page-up".to_string(), "page-up")));
+        assert!(bindings.contains(&("ctrl-f".to_string(), "page-down")));
+
+        config.ui.reverse_list = true;
+        let binds = skim_binds(&config, false);
+        assert!(binds.contains(&"home:up(100000000)".to_string()));
+        assert!(binds.contains(&"end:down(100000000)".to_string()));
+        assert!(binds.contains(&"page-down:page-down".to_string()));
+
+        // Bottom-up lists put the first channel at the bottom
+        config.ui.reverse_list = false;
+        config.ui.key_bindings.insert("first".to_string(), "alt-g".to_string());
+        let binds = skim_binds(&config, false);
+        assert!(binds.contains(&"home:down(100000000)".to_string()));
+        assert!(binds.contains(&"alt-g:down(100000000)".to_string()));
+        assert!(help_text(&config, false).contains("end          Jump to the last channel"));
+    }
+
+    #[test]
+    fn test_preview_can_be_disabled() {
+        let mut config = Config::default();
+        let options = ChannelSelector::skim_options(&config, false, "", "header", &[]).unwrap();
+        assert_eq!(options.preview, Some(""));
+        assert_eq!(options.preview_window, Some("right:50%:wrap"));
+
+        config.ui.show_preview = false;
+        let options = ChannelSelector::skim_options(&config, false, "bbc", "header", &[]).unwrap();
+        // Skim only builds a preview pane when `preview` is set; its default window is never used
+        assert_eq!(options.preview, None);
+        assert_ne!(options.preview_window, Some("right:50%:wrap"));
+        assert_eq!(options.query, Some("bbc"));
+    }
+
+    #[test]
+    fn test_tag_edits_and_preview() {
+        assert_eq!(
+            parse_tag_edits(" sports, +live -old  -\n"),
+            [TagEdit::Add("sports".to_string()), TagEdit::Add("live".to_string()), TagEdit::Remove("old".to_string())]
+        );
+        assert!(parse_tag_edits("  \n").is_empty());
+
+        let mut config = Config::default();
+        config.add_tag("http://example.com/espn", "sports");
+        let selector = ChannelSelector::new(vec![Channel::new("ESPN".to_string(), "http://example.com/espn".to_string())], &config);
+        let context = PreviewContext {
+            query: "",
+            cmd_query: "",
+            width: 80,
+            height: 24,
+            current_index: 0,
+            current_selection: "ESPN",
+            selected_indices: &[],
+            selections: &[],
+        };
+        let ItemPreview::Text(preview) = selector.channels[0].preview(context) else {
+            panic!("expected a text preview");
+        };
+        assert!(preview.contains("Tags: "));
+        assert!(preview.contains("sports"));
+    }
+
+    #[test]
+    fn test_info_panel() {
+        let mut channel = Channel::new("ITV".to_string(), "http://streams.example.com/itv".to_string());
+        channel.raw_name = Some("UK| ITV HD".to_string());
+        channel.group = Some("UK".to_string());
+        channel.tvg_id = Some("itv.uk".to_string());
+        channel.headers.insert("Referer".to_string(), "https://example.com/".to_string());
+
+        let panel = info_panel(&channel);
+        assert!(panel.contains("ITV"));
+        assert!(panel.contains("UK| ITV HD"));
+        assert!(panel.contains("streams.example.com"));
+        assert!(panel.contains("Live"));
+        assert!(panel.contains("itv.uk"));
+        assert!(panel.contains("Referer: https://example.com/"));
+        assert!(!panel.contains("Country"));
+
+        channel.duration = Some(5400.0);
+        assert!(info_panel(&channel).contains("VOD (1h 30m 0s)"));
+    }
+
+    #[test]
+    fn test_confirmation() {
+        assert!(is_confirmation("y\n"));
+        assert!(is_confirmation(" YES \n"));
+        assert!(!is_confirmation("\n"));
+        assert!(!is_confirmation("nope\n"));
+    }
+}
@@ -3,6 +3,7 @@ use std::time::Duration;
 /// Terminal control escape sequences and utilities
 pub mod terminal {
     use std::io::{self, Write};
+    use std::sync::atomic::{AtomicBool, Ordering};
     
     /// Terminal escape sequences
     pub const ENTER_ALTERNATE_SCREEN: &str = "\x1B[?1049h";
@@ -40,6 +41,37 @@ pub mod terminal {
     pub fn restore_terminal() {
         ensure_clean_terminal();
     }
+
+    /// Enters TUI mode on creation and restores the terminal exactly once
+    ///
+    /// Restoring happens on drop unless `restore` was already called.
+    pub struct TerminalGuard {
+        restored: AtomicBool,
+    }
+
+    impl TerminalGuard {
+        pub fn new() -> Self {
+            init_terminal();
+            Self {
+                restored: AtomicBool::new(false),
+            }
+        }
+
+        /// Restore the terminal now; returns `false` if it was already restored
+        pub fn restore(&self) -> bool {
+            if self.restored.swap(true, Ordering::SeqCst) {
+                return false;
+            }
+            restore_terminal();
+            true
+        }
+    }
+
+    impl Drop for TerminalGuard {
+        fn drop(&mut self) {
+            self.restore();
+        }
+    }
     
     /// Emergency terminal reset (call this in panic handlers)
     pub fn emergency_terminal_reset() {
@@ -60,11 +92,15 @@ pub mod terminal {
     
     #[cfg(unix)]
     pub fn reset_terminal_unix() {
-        use std::process::Command;
+        use std::process::{Command, Stdio};
         
-        // Use system commands as a fallback
-        let _ = Command::new("reset").output();
-        let _ = Command::new("stty").arg("sane").output();
+        // Use system commands as a fallback; stty needs the terminal as its stdin
+        let _ = Command::new("stty")
+            .arg("sane")
+            .stdin(Stdio::inherit())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .status();
     }
     
     /// Comprehensive terminal cleanup
@@ -79,6 +115,27 @@ pub mod terminal {
     }
 }
 
+/// Current terminal width in columns, or `fallback` when stdout is not a terminal
+pub fn terminal_width(fallback: usize) -> usize {
+    console::Term::stdout()
+        .size_checked()
+        .map(|(_, cols)| cols as usize)
+        .unwrap_or(fallback)
+}
+
+/// Widest a separator grows on very wide terminals
+const MAX_SEPARATOR_WIDTH: usize = 100;
+
+/// Separator width: the configured override, else the terminal width capped at a sane maximum
+pub fn separator_width(configured: Option<usize>, fallback: usize) -> usize {
+    configured.unwrap_or_else(|| terminal_width(fallback).min(MAX_SEPARATOR_WIDTH))
+}
+
+/// Horizontal rule of `ch` spanning `width` columns
+pub fn separator(ch: char, width: usize) -> String {
+    ch.to_string().repeat(width)
+}
+
 /// Format duration in a human-readable format
 pub fn format_duration(duration: Duration) -> String {
     let total_seconds = duration.as_secs();
@@ -113,6 +170,11 @@ pub fn format_file_size(bytes: u64) -> String {
     }
 }
 
+/// riptv's folder under the user cache directory
+pub fn cache_dir() -> Option<std::path::PathBuf> {
+    dirs::cache_dir().map(|dir| dir.join("riptv"))
+}
+
 /// Sanitize channel name for filename usage
 pub fn sanitize_filename(name: &str) -> String {
     name.chars()
@@ -173,41 +235,70 @@ pub fn string_similarity(a: &str, b: &str) -> f64 {
     matches as f64 / min_len as f64
 }
 
+/// Parse an `#EXTVLCOPT:http-*=value` line into an HTTP header name and value
+pub fn parse_extvlcopt_header(line: &str) -> Option<(String, String)> {
+    let (option, value) = line.strip_prefix("#EXTVLCOPT:")?.split_once('=')?;
+    let value = value.trim();
+    if value.is_empty() {
+        return None;
+    }
+
+    let name = match option.trim().to_lowercase().as_str() {
+        "http-user-agent" => "User-Agent",
+        "http-referrer" | "http-referer" => "Referer",
+        "http-origin" => "Origin",
+        "http-cookie" => "Cookie",
+        _ => return None,
+    };
+
+    Some((name.to_string(), value.to_string()))
+}
+
+/// Render an HTTP header as an `#EXTVLCOPT` line, if VLC has an option for it
+pub fn format_extvlcopt_header(name: &str, value: &str) -> Option<String> {
+    let option = match name.to_lowercase().as_str() {
+        "user-agent" => "http-user-agent",
+        "referer" => "http-referrer",
+        "origin" => "http-origin",
+        "cookie" => "http-cookie",
+        _ => return None,
+    };
+
+    Some(format!("#EXTVLCOPT:{}={}", option, value))
+}
+
 /// Parse M3U metadata from EXTINF line
 pub fn parse_extinf_metadata(extinf_line: &str) -> ExtinfMetadata {
-    let mut metadata = ExtinfMetadata::default();
+    // Duration comes first: "#EXTINF:-1 ..." for live streams, seconds for VOD
+    let duration = extinf_line
+        .strip_prefix("#EXTINF:")
+        .and_then(|rest| rest.split(|c: char| c.is_whitespace() || c == ',').next())
+        .and_then(|duration| duration.parse::<f64>().ok())
+        .filter(|duration| *duration > 0.0);
+    let mut metadata = ExtinfMetadata {
+        duration,
+        ..Default::default()
+    };
     
-    // Extract basic info after comma
-    if let Some(comma_pos) = extinf_line.find(',') {
+    // Extract basic info after the first comma outside of quoted attributes
+    if let Some(comma_pos) = find_unquoted_comma(extinf_line) {
         let after_comma = &extinf_line[comma_pos + 1..];
+        let attributes = &extinf_line[..comma_pos];
         
         // Look for various attributes
-        if let Some(tvg_name) = extract_attribute(extinf_line, "tvg-name") {
-            metadata.tvg_name = Some(tvg_name);
-        }
-        
-        if let Some(tvg_logo) = extract_attribute(extinf_line, "tvg-logo") {
-            metadata.tvg_logo = Some(tvg_logo);
-        }
-        
-        if let Some(group_title) = extract_attribute(extinf_line, "group-title") {
-            metadata.group_title = Some(group_title);
-        }
-        
-        if let Some(tvg_language) = extract_attribute(extinf_line, "tvg-language") {
-            metadata.tvg_language = Some(tvg_language);
-        }
-        
-        if let Some(tvg_country) = extract_attribute(extinf_line, "tvg-country") {
-            metadata.tvg_country = Some(tvg_country);
-        }
-        
-        if let Some(tvg_id) = extract_attribute(extinf_line, "tvg-id") {
-            metadata.tvg_id = Some(tvg_id);
-        }
+        metadata.tvg_name = extract_attribute(attributes, "tvg-name");
+        metadata.tvg_logo = extract_attribute(attributes, "tvg-logo");
+        metadata.group_title = extract_attribute(attributes, "group-title");
+        metadata.tvg_language = extract_attribute(attributes, "tvg-language");
+        metadata.tvg_country = extract_attribute(attributes, "tvg-country");
+        metadata.tvg_id = extract_attribute(attributes, "tvg-id");
+        metadata.catchup = extract_attribute(attributes, "catchup");
+        metadata.catchup_source = extract_attribute(attributes, "catchup-source");
+        metadata.catchup_days = extract_attribute(attributes, "catchup-days");
         
         // Channel name is everything after attributes
-        metadata.channel_name = after_comma.trim().to_string();
+        metadata.display_name = after_comma.trim().to_string();
+        metadata.channel_name = metadata.display_name.clone();
         
         // If we have tvg-name, prefer that
         if let Some(ref tvg_name) = metadata.tvg_name {
@@ -220,25 +311,124 @@ pub fn parse_extinf_metadata(extinf_line: &str) -> ExtinfMetadata {
     metadata
 }
 
+fn find_unquoted_comma(line: &str) -> Option<usize> {
+    let mut in_quotes = false;
+    for (pos, c) in line.char_indices() {
+        match c {
+            '"' => in_quotes = !in_quotes,
+            ',' if !in_quotes => return Some(pos),
+            _ => {}
+        }
+    }
+    None
+}
+
 fn extract_attribute(line: &str, attr_name: &str) -> Option<String> {
-    let pattern = format!(r#"{}="([^"]*)""#, attr_name);
-    if let Ok(re) = regex::Regex::new(&pattern) {
-        if let Some(captures) = re.captures(line) {
-            return captures.get(1).map(|m| m.as_str().to_string());
+    // Plain string search instead of a regex per attribute: this runs for every channel
+    let pattern = format!("{}=\"", attr_name);
+    let mut search_from = 0;
+
+    while let Some(found) = line[search_from..].find(&pattern) {
+        let start = search_from + found;
+        let value_start = start + pattern.len();
+
+        // Make sure we matched a whole attribute name, not a suffix of a longer one
+        let preceded_by_boundary = line[..start]
+            .chars()
+            .next_back()
+            .map(|c| c.is_whitespace() || c == ':')
+            .unwrap_or(true);
+
+        if preceded_by_boundary {
+            return line[value_start..]
+                .find('"')
+                .map(|end| line[value_start..value_start + end].to_string());
         }
+        search_from = value_start;
     }
     None
 }
 
 #[derive(Debug, Default)]
 pub struct ExtinfMetadata {
+    /// `tvg-name` when set, otherwise the display text
     pub channel_name: String,
+    /// Text after the comma
+    pub display_name: String,
+    pub duration: Option<f64>,
     pub tvg_name: Option<String>,
     pub tvg_logo: Option<String>,
     pub group_title: Option<String>,
     pub tvg_language: Option<String>,
     pub tvg_country: Option<String>,
     pub tvg_id: Option<String>,
+    pub catchup: Option<String>,
+    pub catchup_source: Option<String>,
+    pub catchup_days: Option<String>,
+}
+
+/// SHA-256 round constants
+const SHA256_K: [u32; 64] = [
+    0x428a2f98, 0x71374491, 0xb5c0fbcf, 0xe9b5dba5, 0x3956c25b, 0x59f111f1, 0x923f82a4, 0xab1c5ed5,
+    0xd807aa98, 0x12835b01, 0x243185be, 0x550c7dc3, 0x72be5d74, 0x80deb1fe, 0x9bdc06a7, 0xc19bf174,
+    0xe49b69c1, 0xefbe4786, 0x0fc19dc6, 0x240ca1cc, 0x2de92c6f, 0x4a7484aa, 0x5cb0a9dc, 0x76f988da,
+    0x983e5152, 0xa831c66d, 0xb00327c8, 0xbf597fc7, 0xc6e00bf3, 0xd5a79147, 0x06ca6351, 0x14292967,
+    0x27b70a85, 0x2e1b2138, 0x4d2c6dfc, 0x53380d13, 0x650a7354, 0x766a0abb, 0x81c2c92e, 0x92722c85,
+    0xa2bfe8a1, 0xa81a664b, 0xc24b8b70, 0xc76c51a3, 0xd192e819, 0xd6990624, 0xf40e3585, 0x106aa070,
+    0x19a4c116, 0x1e376c08, 0x2748774c, 0x34b0bcb5, 0x391c0cb3, 0x4ed8aa4a, 0x5b9cca4f, 0x682e6ff3,
+    0x748f82ee, 0x78a5636f, 0x84c87814, 0x8cc70208, 0x90befffa, 0xa4506ceb, 0xbef9a3f7, 0xc67178f2,
+];
+
+/// Compute the SHA-256 digest of `data` as lowercase hex
+pub fn sha256_hex(data: &[u8]) -> String {
+    let mut state: [u32; 8] = [
+        0x6a09e667, 0xbb67ae85, 0x3c6ef372, 0xa54ff53a, 0x510e527f, 0x9b05688c, 0x1f83d9ab, 0x5be0cd19,
+    ];
+
+    // Pad with 0x80, zeros, then the message length in bits to a multiple of 64 bytes
+    let mut message = data.to_vec();
+    message.push(0x80);
+    while message.len() % 64 != 56 {
+        message.push(0);
+    }
+    message.extend_from_slice(&((data.len() as u64).wrapping_mul(8)).to_be_bytes());
+
+    for block in message.chunks_exact(64) {
+        let mut w = [0u32; 64];
+        for (i, word) in block.chunks_exact(4).enumerate() {
+            w[i] = u32::from_be_bytes([word[0], word[1], word[2], word[3]]);
+        }
+        for i in 16..64 {
+            let s0 = w[i - 15].rotate_right(7) ^ w[i - 15].rotate_right(18) ^ (w[i - 15] >> 3);
+            let s1 = w[i - 2].rotate_right(17) ^ w[i - 2].rotate_right(19) ^ (w[i - 2] >> 10);
+            w[i] = w[i - 16].wrapping_add(s0).wrapping_add(w[i - 7]).wrapping_add(s1);
+        }
+
+        let [mut a, mut b, mut c, mut d, mut e, mut f, mut g, mut h] = state;
+        for i in 0..64 {
+            let s1 = e.rotate_right(6) ^ e.rotate_right(11) ^ e.rotate_right(25);
+            let ch = (e & f) ^ (!e & g);
+            let t1 = h.wrapping_add(s1).wrapping_add(ch).wrapping_add(SHA256_K[i]).wrapping_add(w[i]);
+            let s0 = a.rotate_right(2) ^ a.rotate_right(13) ^ a.rotate_right(22);
+            let maj = (a & b) ^ (a & c) ^ (b & c);
+            let t2 = s0.wrapping_add(maj);
+
+            h = g;
+            g = f;
+            f = e;
+            e = d.wrapping_add(t1);
+            d = c;
+            c = b;
+            b = a;
+            a = t1.wrapping_add(t2);
+        }
+
+        for (word, value) in state.iter_mut().zip([a, b, c, d, e, f, g, h]) {
+            *word = word.wrapping_add(value);
+        }
+    }
+
+    state.iter().map(|word| format!("{:08x}", word)).collect()
 }
 
 /// Create progress callback for long operations
@@ -257,32 +447,203 @@ where
     }
 }
 
-/// Simple retry mechanism
-pub async fn retry_async<F, Fut, T, E>(
+/// Retry `operation` with exponential backoff
+///
+/// Waits `delay` after the first failure and doubles it each time. Errors for which
+/// `retryable` returns false are returned immediately.
+pub async fn retry_async<F, Fut, T, E, R>(
     mut operation: F,
     max_attempts: u32,
     delay: Duration,
+    retryable: R,
 ) -> Result<T, E>
 where
     F: FnMut() -> Fut,
     Fut: std::future::Future<Output = Result<T, E>>,
     E: std::fmt::Debug,
+    R: Fn(&E) -> bool,
 {
-    let mut last_error = None;
-    
-    for attempt in 1..=max_attempts {
+    let max_attempts = max_attempts.max(1);
+    let mut delay = delay;
+
+    for attempt in 1.. {
         match operation().await {
             Ok(result) => return Ok(result),
-            Err(e) => {
-                last_error = Some(e);
-                if attempt < max_attempts {
-                    tokio::time::sleep(delay).await;
-                }
+            Err(e) if attempt < max_attempts && retryable(&e) => {
+                tracing::debug!("Attempt {}/{} failed: {:?}, retrying in {:?}", attempt, max_attempts, e, delay);
+                tokio::time::sleep(delay).await;
+                delay *= 2;
             }
+            Err(e) => return Err(e),
         }
     }
-    
-    Err(last_error.unwrap())
+
+    unreachable!("retry loop only exits by returning")
+}
+
+/// Expand a leading `~` to `home`, leaving the path alone without one
+pub fn expand_tilde_with(path: &str, home: Option<&std::path::Path>) -> std::path::PathBuf {
+    match (path.strip_prefix('~'), home) {
+        (Some(""), Some(home)) => home.to_path_buf(),
+        (Some(rest), Some(home)) if rest.starts_with('/') || rest.starts_with('\\') => {
+            home.join(&rest[1..])
+        }
+        _ => std::path::PathBuf::from(path),
+    }
+}
+
+/// FNV-1a hash, stable across runs and Rust versions
+pub fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf29ce484222325u64, |hash, &byte| {
+        (hash ^ byte as u64).wrapping_mul(0x100000001b3)
+    })
+}
+
+/// Compare ignoring case, with digit runs compared by value so "Sports 2" comes before "Sports 10"
+pub fn natural_cmp(a: &str, b: &str) -> std::cmp::Ordering {
+    use std::cmp::Ordering;
+
+    let (mut left, mut right) = (a.chars().peekable(), b.chars().peekable());
+    loop {
+        let ordering = match (left.peek().copied(), right.peek().copied()) {
+            (None, None) => return a.cmp(b),
+            (None, Some(_)) => return Ordering::Less,
+            (Some(_), None) => return Ordering::Greater,
+            (Some(x), Some(y)) if x.is_ascii_digit() && y.is_ascii_digit() => {
+                let (x, y) = (digit_run(&mut left), digit_run(&mut right));
+                let (x, y) = (x.trim_start_matches('0'), y.trim_start_matches('0'));
+                x.len().cmp(&y.len()).then_with(|| x.cmp(y))
+            }
+            (Some(x), Some(y)) => {
+                left.next();
+                right.next();
+                x.to_lowercase().cmp(y.to_lowercase())
+            }
+        };
+        if ordering != Ordering::Equal {
+            return ordering;
+        }
+    }
+}
+
+/// Consume the digits at the front of `chars`
+fn digit_run(chars: &mut std::iter::Peekable<std::str::Chars>) -> String {
+    let mut digits = String::new();
+    while let Some(digit) = chars.next_if(char::is_ascii_digit) {
+        digits.push(digit);
+    }
+    digits
+}
+
+/// Levenshtein distance between `a` and `b`, ignoring case
+pub fn edit_distance(a: &str, b: &str) -> usize {
+    let a: Vec<char> = a.to_lowercase().chars().collect();
+    let b: Vec<char> = b.to_lowercase().chars().collect();
+    let mut row: Vec<usize> = (0..=b.len()).collect();
+
+    for (i, &ca) in a.iter().enumerate() {
+        let mut diagonal = row[0];
+        row[0] = i + 1;
+        for (j, &cb) in b.iter().enumerate() {
+            let above = row[j + 1];
+            row[j + 1] = (above + 1).min(row[j] + 1).min(diagonal + usize::from(ca != cb));
+            diagonal = above;
+        }
+    }
+    row[b.len()]
+}
+
+/// Up to `limit` of `candidates` closest to `wanted`; ones containing it come first
+pub fn nearest<'a>(wanted: &str, candidates: &'a [String], limit: usize) -> Vec<&'a str> {
+    let wanted_lower = wanted.to_lowercase();
+    let mut ranked: Vec<&str> = candidates.iter().map(String::as_str).collect();
+    ranked.sort_by_cached_key(|candidate| {
+        let contains = candidate.to_lowercase().contains(&wanted_lower);
+        (!contains, edit_distance(wanted, candidate), candidate.to_lowercase())
+    });
+    ranked.truncate(limit);
+    ranked
+}
+
+/// Small SplitMix64 generator, enough for picking channels at random
+#[derive(Debug, Clone)]
+pub struct Rng(u64);
+
+impl Rng {
+    /// A generator that always produces the same sequence for `seed`
+    #[cfg(test)]
+    pub fn seeded(seed: u64) -> Self {
+        Self(seed)
+    }
+
+    /// A generator seeded from the clock and process id
+    pub fn from_entropy() -> Self {
+        let nanos = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|elapsed| elapsed.as_nanos() as u64)
+            .unwrap_or_default();
+        Self(nanos ^ ((std::process::id() as u64) << 32))
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e3779b97f4a7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+        z ^ (z >> 31)
+    }
+
+    /// A number in `0..bound`; `bound` must be non-zero
+    pub fn below(&mut self, bound: u64) -> u64 {
+        self.next_u64() % bound
+    }
+}
+
+/// Index picked with probability proportional to its weight, or `None` if every weight is zero
+pub fn pick_weighted(weights: &[u64], rng: &mut Rng) -> Option<usize> {
+    let total: u64 = weights.iter().sum();
+    if total == 0 {
+        return None;
+    }
+
+    let mut target = rng.below(total);
+    weights.iter().position(|&weight| {
+        if target < weight {
+            return true;
+        }
+        target -= weight;
+        false
+    })
+}
+
+/// Check whether a command can be found on the `PATH`
+pub fn is_command_available(command: &str) -> bool {
+    use std::process::Command;
+
+    ["which", "where"].iter().any(|finder| {
+        Command::new(finder)
+            .arg(command)
+            .output()
+            .map(|output| output.status.success())
+            .unwrap_or(false)
+    })
+}
+
+/// Command that opens `url` in the system's default browser
+pub fn browser_command(url: &str) -> std::process::Command {
+    #[cfg(target_os = "macos")]
+    let mut cmd = std::process::Command::new("open");
+    #[cfg(windows)]
+    let mut cmd = {
+        let mut cmd = std::process::Command::new("cmd");
+        cmd.args(["/C", "start", ""]);
+        cmd
+    };
+    #[cfg(not(any(target_os = "macos", windows)))]
+    let mut cmd = std::process::Command::new("xdg-open");
+
+    cmd.arg(url);
+    cmd
 }
 
 /// Get system information for debugging
@@ -307,6 +668,30 @@ pub struct SystemInfo {
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_natural_cmp() {
+        use std::cmp::Ordering;
+
+        assert_eq!(natural_cmp("Sports 2", "Sports 10"), Ordering::Less);
+        assert_eq!(natural_cmp("sports 10", "Sports 9"), Ordering::Greater);
+        assert_eq!(natural_cmp("Channel 007", "Channel 7b"), Ordering::Less);
+        assert_eq!(natural_cmp("News", "news 1"), Ordering::Less);
+
+        let mut groups = vec!["Sports 10", "sports 2", "Movies", "Sports 1"];
+        groups.sort_by(|a, b| natural_cmp(a, b));
+        assert_eq!(groups, ["Movies", "Sports 1", "sports 2", "Sports 10"]);
+    }
+
+    #[test]
+    fn test_nearest() {
+        assert_eq!(edit_distance("Nwes", "news"), 2);
+        assert_eq!(edit_distance("", "abc"), 3);
+        let groups: Vec<String> = ["Sports", "UK News", "News", "Movies"].iter().map(|group| group.to_string()).collect();
+        assert_eq!(nearest("news", &groups, 2), ["News", "UK News"]);
+        assert_eq!(nearest("sprts", &groups, 1), ["Sports"]);
+        assert!(nearest("x", &[], 3).is_empty());
+    }
+
     #[test]
     fn test_format_duration() {
         assert_eq!(format_duration(Duration::from_secs(30)), "30s");
@@ -322,6 +707,17 @@ mod tests {
         assert_eq!(format_file_size(1048576), "1.0 MB");
     }
 
+    #[test]
+    fn test_separator() {
+        assert_eq!(separator('─', 10).chars().count(), 10);
+        assert_eq!(separator('═', 3), "═══");
+        assert_eq!(separator('─', 0), "");
+
+        let width = separator_width(Some(42), 60);
+        assert_eq!(separator('─', width).chars().count(), 42);
+        assert!(separator_width(None, 60) <= MAX_SEPARATOR_WIDTH);
+    }
+
     #[test]
     fn test_sanitize_filename() {
         assert_eq!(sanitize_filename("test/file<name>"), "test_file_name_");
@@ -343,10 +739,106 @@ mod tests {
         assert!(!is_valid_url(""));
     }
 
+    #[test]
+    fn test_parse_extinf_metadata() {
+        let metadata = parse_extinf_metadata(
+            r#"#EXTINF:-1 tvg-id="cnn.us" xtvg-name="wrong" group-title="News, US",CNN International"#,
+        );
+        assert_eq!(metadata.channel_name, "CNN International");
+        assert_eq!(metadata.tvg_id.as_deref(), Some("cnn.us"));
+        assert_eq!(metadata.tvg_name, None);
+        assert_eq!(metadata.group_title.as_deref(), Some("News, US"));
+    }
+
+    #[test]
+    fn test_parse_extvlcopt_header() {
+        assert_eq!(
+            parse_extvlcopt_header("#EXTVLCOPT:http-referrer=https://example.com/"),
+            Some(("Referer".to_string(), "https://example.com/".to_string()))
+        );
+        assert_eq!(
+            parse_extvlcopt_header("#EXTVLCOPT:http-user-agent=Mozilla/5.0 (X11)"),
+            Some(("User-Agent".to_string(), "Mozilla/5.0 (X11)".to_string()))
+        );
+        assert_eq!(parse_extvlcopt_header("#EXTVLCOPT:network-caching=1000"), None);
+    }
+
+    #[test]
+    fn test_parse_extinf_duration() {
+        assert_eq!(parse_extinf_metadata("#EXTINF:-1,Live Channel").duration, None);
+        assert_eq!(parse_extinf_metadata("#EXTINF:0 tvg-id=\"x\",Unknown").duration, None);
+        assert_eq!(parse_extinf_metadata("#EXTINF:120,Short Clip").duration, Some(120.0));
+        assert_eq!(
+            parse_extinf_metadata("#EXTINF:5400.5 group-title=\"Movies\",Film").duration,
+            Some(5400.5)
+        );
+    }
+
+    #[test]
+    fn test_sha256_hex() {
+        assert_eq!(sha256_hex(b""), "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855");
+        assert_eq!(sha256_hex(b"abc"), "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad");
+        assert_eq!(
+            sha256_hex(b"abcdbcdecdefdefgefghfghighijhijkijkljklmklmnlmnomnopnopq"),
+            "248d6a61d20638b8e5c026930c3e6039a33ce45964ff2167f6ecedd419db06c1"
+        );
+        assert_eq!(
+            sha256_hex(&[b'a'; 1000]),
+            "41edece42d63e8d9bf515a9ba6932e1c20cbc9f5a5d134645adb5db1b9737ea3"
+        );
+    }
+
     #[test]
     fn test_terminal_cleanup() {
         // Test that terminal utilities don't panic
         terminal::ensure_clean_terminal();
         terminal::emergency_terminal_reset();
     }
+
+    #[tokio::test]
+    async fn test_retry_async_backoff() {
+        let mut calls = 0;
+        let started = std::time::Instant::now();
+        let result: Result<u32, &str> = retry_async(
+            || {
+                calls += 1;
+                let attempt = calls;
+                async move { if attempt < 3 { Err("transient") } else { Ok(attempt) } }
+            },
+            3,
+            Duration::from_millis(10),
+            |_| true,
+        )
+        .await;
+
+        assert_eq!(result, Ok(3));
+        assert_eq!(calls, 3);
+        // 10ms then 20ms of backoff
+        assert!(started.elapsed() >= Duration::from_millis(30));
+
+        // Non-retryable errors are returned straight away
+        let mut calls = 0;
+        let result: Result<(), &str> = retry_async(
+            || {
+                calls += 1;
+                async { Err("fatal") }
+            },
+            5,
+            Duration::from_millis(1),
+            |e| *e != "fatal",
+        )
+        .await;
+        assert_eq!(result, Err("fatal"));
+        assert_eq!(calls, 1);
+    }
+
+    #[test]
+    fn test_terminal_guard_restores_once() {
+        let guard = terminal::TerminalGuard::new();
+        assert!(guard.restore());
+        assert!(!guard.restore());
+
+        // Dropping after an explicit restore must not restore again
+        drop(guard);
+    }
 }